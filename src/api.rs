@@ -3,11 +3,11 @@
 //!---------------------------------------------------------------------!
 
 use crate::data::{
-    self, AssetType, OptionContract, OptionSide, OrderSide, PendingOrder, TradeAction, TradeRecord,
+    self, AssetType, OptionContract, OptionSide, OptionType, OrderSide, PendingOrder, TradeAction, TradeFees, TradeRecord,
 };
 use crate::helper::{creds_to_price, fmt_pnl, fmt_qty, option_intrinsic, option_type_str, price_to_creds};
 use crate::serenity;
-use chrono::{DateTime, Datelike, Timelike, Utc, Weekday};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc, Weekday};
 use dashmap::DashMap;
 use poise::serenity_prelude::{futures, ChannelId, CreateMessage};
 use serde::Deserialize;
@@ -69,6 +69,40 @@ pub(crate) struct YfChartOuter {
 #[derive(Debug, Deserialize)]
 pub(crate) struct YfChartEntry {
     pub meta: YfChartMeta,
+    #[serde(default)]
+    pub indicators: Option<YfIndicators>,
+    #[serde(default)]
+    pub events: Option<YfEvents>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct YfEvents {
+    #[serde(default)]
+    pub dividends: HashMap<String, YfDividend>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct YfDividend {
+    pub amount: f64,
+    pub date: i64,
+}
+
+/// Next projected ex-dividend date and the per-share amount expected on it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct UpcomingDividend {
+    pub amount_usd: f64,
+    pub ex_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct YfIndicators {
+    pub quote: Vec<YfIndicatorQuote>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct YfIndicatorQuote {
+    #[serde(default)]
+    pub close: Vec<Option<f64>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -186,6 +220,16 @@ const FMP_RATIOS_CACHE_TTL: Duration = Duration::from_secs(60 * 60 * 24 * 15);
 pub static FMP_RATIOS_CACHE: LazyLock<DashMap<String, (FmpRatios, Instant)>> =
     LazyLock::new(DashMap::new);
 
+/// How long an underlying's historical volatility is cached (6 hours — built from daily bars).
+const VOLATILITY_CACHE_TTL: Duration = Duration::from_secs(60 * 60 * 6);
+pub static VOLATILITY_CACHE: LazyLock<DashMap<String, (Option<f64>, Instant)>> =
+    LazyLock::new(DashMap::new);
+
+/// How long a ticker's projected dividend is cached (1 day — ex-dates move slowly).
+const DIVIDEND_CACHE_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+pub static DIVIDEND_CACHE: LazyLock<DashMap<String, (Option<UpcomingDividend>, Instant)>> =
+    LazyLock::new(DashMap::new);
+
 pub static LOGO_API_KEY: LazyLock<Option<String>> =
     LazyLock::new(|| std::env::var("LOGO_API_KEY").ok());
 pub static FMP_API_KEY: LazyLock<Option<String>> =
@@ -317,6 +361,99 @@ pub(crate) async fn fetch_quote_detail(ticker: &str) -> Option<YfQuote> {
     Some(quote)
 }
 
+/// Daily closing prices over `range` (Yahoo range string, e.g. "3mo"), oldest first.
+pub(crate) async fn fetch_daily_closes(ticker: &str, range: &str) -> Option<Vec<f64>> {
+    if ticker.is_empty() || ticker.len() > 20 || !ticker.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+        tracing::warn!(ticker = ?ticker, "fetch_daily_closes: rejected invalid ticker");
+        return None;
+    }
+
+    let http_resp = HTTP_CLIENT
+        .get(format!(
+            "https://query2.finance.yahoo.com/v8/finance/chart/{ticker}"
+        ))
+        .query(&[("interval", "1d"), ("range", range)])
+        .send()
+        .await
+        .ok()?;
+
+    if http_resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        tracing::warn!(ticker = %ticker, "Yahoo Finance rate limit hit (429)");
+        YAHOO_RATE_LIMITED.store(true, Ordering::Relaxed);
+        return None;
+    }
+
+    let resp = http_resp.json::<YfChartResponse>().await.ok()?;
+    YAHOO_RATE_LIMITED.store(false, Ordering::Relaxed);
+
+    let quote = resp.chart.result?.into_iter().next()?.indicators?.quote.into_iter().next()?;
+    Some(quote.close.into_iter().flatten().collect())
+}
+
+/// Annualised 3-month historical volatility of `ticker`, or `None` if history is unavailable.
+pub(crate) async fn fetch_volatility(ticker: &str) -> Option<f64> {
+    if let Some(entry) = VOLATILITY_CACHE.get(ticker) {
+        if entry.1.elapsed() < VOLATILITY_CACHE_TTL {
+            return entry.0;
+        }
+    }
+    let vol = fetch_daily_closes(ticker, "3mo").await
+        .and_then(|closes| crate::options::historical_volatility(&closes));
+    VOLATILITY_CACHE.insert(ticker.to_string(), (vol, Instant::now()));
+    vol
+}
+
+/// Next ex-dividend date for `ticker`, projected from the past year's payments.
+/// `None` for non-payers or when history is unavailable.
+pub(crate) async fn fetch_upcoming_dividend(ticker: &str) -> Option<UpcomingDividend> {
+    if let Some(entry) = DIVIDEND_CACHE.get(ticker) {
+        if entry.1.elapsed() < DIVIDEND_CACHE_TTL {
+            return entry.0;
+        }
+    }
+    if ticker.is_empty() || ticker.len() > 20 || !ticker.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+        tracing::warn!(ticker = ?ticker, "fetch_upcoming_dividend: rejected invalid ticker");
+        return None;
+    }
+
+    let http_resp = HTTP_CLIENT
+        .get(format!(
+            "https://query2.finance.yahoo.com/v8/finance/chart/{ticker}"
+        ))
+        .query(&[("interval", "1d"), ("range", "1y"), ("events", "div")])
+        .send()
+        .await
+        .ok()?;
+
+    if http_resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        tracing::warn!(ticker = %ticker, "Yahoo Finance rate limit hit (429)");
+        YAHOO_RATE_LIMITED.store(true, Ordering::Relaxed);
+        return None;
+    }
+
+    let resp = http_resp.json::<YfChartResponse>().await.ok()?;
+    YAHOO_RATE_LIMITED.store(false, Ordering::Relaxed);
+
+    let mut dividends: Vec<(NaiveDate, f64)> = resp.chart.result?.into_iter().next()?
+        .events.map(|e| e.dividends.into_values().collect::<Vec<_>>()).unwrap_or_default()
+        .into_iter()
+        .filter_map(|d| DateTime::from_timestamp(d.date, 0).map(|dt| (dt.date_naive(), d.amount)))
+        .collect();
+    dividends.sort_by_key(|d| d.0);
+    let dates: Vec<NaiveDate> = dividends.iter().map(|d| d.0).collect();
+    let upcoming = crate::options::next_ex_dividend(&dates, Utc::now().date_naive())
+        .zip(dividends.last())
+        .map(|(ex_date, last)| UpcomingDividend { amount_usd: last.1, ex_date });
+    DIVIDEND_CACHE.insert(ticker.to_string(), (upcoming, Instant::now()));
+    upcoming
+}
+
+/// Pricing inputs for options on `ticker`: its historical volatility and the current fed funds rate.
+pub(crate) async fn option_pricing_inputs(ticker: &str, fed_rate: &Arc<RwLock<f64>>) -> crate::options::PricingInputs {
+    let vol = fetch_volatility(ticker).await;
+    crate::options::PricingInputs::new(vol, *fed_rate.read().await)
+}
+
 pub(crate) async fn fetch_fmp_profile(ticker: &str) -> Option<FmpProfile> {
     if let Some(entry) = FMP_CACHE.get(ticker) {
         if !is_market_hours() || entry.1.elapsed() < FMP_CACHE_TTL {
@@ -437,39 +574,80 @@ pub(crate) async fn refresh_market_rate(rate: &Arc<RwLock<f64>>) {
     }
 }
 
-pub(crate) async fn apply_monthly_interest(users: &UsersMap, rate: &Arc<RwLock<f64>>) {
+/// Accrues HYSA interest on every portfolio's cash at its owner's tier rate, posting the
+/// accrued amount on the 1st of each month. Days missed while the bot was down are back-filled,
+/// and each posting is recorded in trade history.
+pub(crate) async fn accrue_interest(users: &UsersMap, rate: &Arc<RwLock<f64>>) {
     let now = Utc::now();
-    if now.day() != 1 {
-        return;
-    }
-
     let fed_rate = *rate.read().await;
 
     for entry in users.iter() {
         let (_, u) = entry.pair();
         let mut user_data = u.write().await;
-        let annual_rate = if crate::helper::is_gold(&user_data) {
-            crate::helper::gold_hysa_rate(fed_rate)
-        } else {
-            data::BASE_HYSA_RATE
-        };
+        let annual_rate = crate::helper::hysa_rate(&crate::helper::HYSA_TIERS, user_data.get_level(), fed_rate);
 
+        let mut records = Vec::new();
         for portfolio in &mut user_data.stock.portfolios {
-            if portfolio.cash <= 0.0 {
-                continue;
+            for (date, interest) in portfolio.accrue_interest(now, annual_rate) {
+                tracing::info!(
+                    interest = format_args!("{interest:.2}"),
+                    portfolio = %portfolio.name,
+                    %date,
+                    "posted HYSA interest",
+                );
+                let dollars = creds_to_price(interest);
+                records.push(TradeRecord {
+                    portfolio: portfolio.name.clone(),
+                    ticker: data::TRANSFER_CASH_TICKER.to_string(),
+                    asset_name: "HYSA interest".to_string(),
+                    action: TradeAction::Interest,
+                    quantity: dollars,
+                    price_per_unit: interest / dollars,
+                    total_creds: interest,
+                    realized_pnl: None,
+                    timestamp: date.and_time(chrono::NaiveTime::MIN).and_utc(),
+                    fees: TradeFees::default(),
+                    lots: Vec::new(),
+                    option_leg: false,
+                });
             }
-            let last = portfolio.last_interest_credited;
-            if last.year() == now.year() && last.month() == now.month() {
+        }
+        for record in records {
+            user_data.stock.push_trade(record);
+        }
+    }
+}
+
+/// Records each portfolio's end-of-day value for performance history. Portfolios holding a
+/// ticker that can't be priced are skipped for the day rather than recorded at a false value.
+pub(crate) async fn snapshot_portfolios(users: &UsersMap) {
+    let today = Utc::now().date_naive();
+
+    // ── Phase 1: collect tickers (read lock) ─────────────────────────────────
+    let mut tickers = std::collections::HashSet::new();
+    for entry in users.iter() {
+        let user_data = entry.value().read().await;
+        for port in &user_data.stock.portfolios {
+            tickers.extend(port.positions.iter().map(|p| p.ticker.clone()));
+        }
+    }
+
+    // ── Phase 2: fetch prices (no locks held) ────────────────────────────────
+    tickers.insert(data::BENCHMARK_TICKER.to_string());
+    let tickers: Vec<String> = tickers.into_iter().collect();
+    let mut prices = fetch_prices_map(&tickers).await;
+    prices.retain(|_, p| *p > 0.0);
+    let benchmark = prices.get(data::BENCHMARK_TICKER).copied().unwrap_or(0.0);
+
+    // ── Phase 3: record snapshots (write lock) ───────────────────────────────
+    for entry in users.iter() {
+        let mut user_data = entry.value().write().await;
+        for port in &mut user_data.stock.portfolios {
+            if port.positions.iter().any(|p| !prices.contains_key(&p.ticker)) {
                 continue;
             }
-            let interest = (annual_rate / 100.0 / 12.0) * portfolio.cash;
-            portfolio.cash += interest;
-            portfolio.last_interest_credited = now;
-            tracing::info!(
-                interest = format_args!("{interest:.2}"),
-                portfolio = %portfolio.name,
-                "credited HYSA interest",
-            );
+            let total = crate::trader::portfolio_equity(port, &prices);
+            port.record_snapshot(today, total, benchmark);
         }
     }
 }
@@ -541,6 +719,44 @@ pub(crate) async fn sweep_expired_options(
         let type_str = option_type_str(info.contract.option_type);
         let is_short = info.contract.side == OptionSide::Short;
 
+        // Physically settled ITM shorts move shares at the strike; a cash-secured put
+        // whose cash has since been spent falls back to cash settlement. European
+        // contracts always settle in cash.
+        let physical = info.contract.settlement == data::SettlementMode::Physical
+            && info.contract.style == data::ExerciseStyle::American;
+        if is_short && itm && physical {
+            let settled = {
+                let mut user_data = u.write().await;
+                let stock = &mut user_data.stock;
+                stock.portfolios.iter_mut().find(|p| p.name == info.portfolio_name).and_then(|portfolio| {
+                    let strike_cost = price_to_creds(info.contract.strike) * f64::from(info.contract.contracts) * 100.0;
+                    let can_settle = info.contract.option_type == OptionType::Call
+                        || portfolio.cash - portfolio.locked_cash() >= strike_cost;
+                    let pos_idx = portfolio.option_idx(&info.contract.id)?;
+                    if !can_settle {
+                        return None;
+                    }
+                    crate::options::exercise_position(
+                        portfolio, &mut stock.trade_history, pos_idx, info.contract.contracts, price_usd, &crate::trader::COST_MODEL,
+                    ).ok()
+                })
+            };
+            if let Some(settlement) = settled {
+                let msg = match settlement.realized_pnl {
+                    Some(pnl) => format!(
+                        "<@{}> Options expired **ITM** — SHORT **{}** {} assigned | Delivered **{:.0}** shares at **${:.2}** (P&L: **{}**)",
+                        info.user_id, info.ticker, type_str, -settlement.shares, info.contract.strike, fmt_pnl(pnl),
+                    ),
+                    None => format!(
+                        "<@{}> Options expired **ITM** — SHORT **{}** {} assigned | Bought **{:.0}** shares at **${:.2}**, cost basis reduced by the premium",
+                        info.user_id, info.ticker, type_str, settlement.shares, info.contract.strike,
+                    ),
+                };
+                let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+                continue;
+            }
+        }
+
         let (cash_delta, pnl, msg) = if is_short {
             // Writer: premium already collected upfront; now settle obligation
             let obligation = intrinsic_creds; // amount owed if ITM
@@ -585,22 +801,11 @@ pub(crate) async fn sweep_expired_options(
                 .find(|p| p.name == info.portfolio_name)
             {
                 portfolio.cash += cash_delta;
-                portfolio.positions.retain(|p| {
-                    if p.ticker != info.ticker {
-                        return true;
-                    }
-                    #[expect(clippy::float_cmp, reason = "strike prices are stored/compared as exact values we set")]
-                    if let AssetType::Option(c) = &p.asset_type {
-                        !(c.strike == info.contract.strike
-                            && c.expiry == info.contract.expiry
-                            && c.option_type == info.contract.option_type
-                            && c.side == info.contract.side)
-                    } else {
-                        true
-                    }
-                });
+                portfolio.positions.retain(|p| !matches!(&p.asset_type, AssetType::Option(c) if c.id == info.contract.id));
+                portfolio.prune_strategies();
             }
 
+            // Expiry settles at intrinsic value without crossing a spread, so it carries no fees
             let record = TradeRecord {
                 portfolio: info.portfolio_name.clone(),
                 ticker: info.ticker.clone(),
@@ -618,6 +823,9 @@ pub(crate) async fn sweep_expired_options(
                 total_creds: intrinsic_creds,
                 realized_pnl: Some(pnl),
                 timestamp: now,
+                fees: TradeFees::default(),
+                lots: Vec::new(),
+                option_leg: true,
             };
             user_data.stock.push_trade(record);
         }
@@ -628,6 +836,435 @@ pub(crate) async fn sweep_expired_options(
     }
 }
 
+/// Rolls early assignment for short calls ahead of an ex-dividend date. Each contract rolls
+/// once per ex-date; deep-ITM calls whose time value is below the dividend are the likely
+/// targets, and assigned contracts settle physically by delivering shares at the strike.
+pub(crate) async fn sweep_early_assignments(
+    users: &UsersMap,
+    http: &Arc<serenity::Http>,
+    bot_chat: &str,
+    fed_rate: &Arc<RwLock<f64>>,
+) {
+    let now = Utc::now();
+    let today = now.date_naive();
+    let Ok(channel_id) = bot_chat.parse::<u64>() else {
+        return;
+    };
+    let channel = ChannelId::new(channel_id);
+
+    // Phase 1: collect standalone short calls under read lock
+    struct ShortCall {
+        user_id: serenity::UserId,
+        portfolio_name: String,
+        ticker: String,
+        contract: OptionContract,
+    }
+
+    let mut candidates: Vec<ShortCall> = Vec::new();
+    for entry in users.iter() {
+        let (user_id, u) = entry.pair();
+        let user_data = u.read().await;
+        for portfolio in &user_data.stock.portfolios {
+            for pos in &portfolio.positions {
+                if let AssetType::Option(c) = &pos.asset_type {
+                    if c.side == OptionSide::Short && c.option_type == OptionType::Call && c.style == data::ExerciseStyle::American
+                        && c.strategy.is_none() && c.expiry > now
+                    {
+                        candidates.push(ShortCall {
+                            user_id: *user_id,
+                            portfolio_name: portfolio.name.clone(),
+                            ticker: pos.ticker.clone(),
+                            contract: c.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    if candidates.is_empty() {
+        return;
+    }
+
+    // Phase 2: dividends, prices and pricing inputs per ticker (no locks held)
+    let mut tickers: Vec<String> = candidates.iter().map(|c| c.ticker.clone()).collect();
+    tickers.sort_unstable();
+    tickers.dedup();
+    let mut dividends = HashMap::new();
+    for ticker in &tickers {
+        if let Some(div) = fetch_upcoming_dividend(ticker).await {
+            let days = (div.ex_date - today).num_days();
+            if (0..=crate::options::EARLY_ASSIGNMENT_WINDOW_DAYS).contains(&days) {
+                dividends.insert(ticker.clone(), div);
+            }
+        }
+    }
+    if dividends.is_empty() {
+        return;
+    }
+    let at_risk: Vec<String> = dividends.keys().cloned().collect();
+    let prices = fetch_prices_map(&at_risk).await;
+    let mut inputs = HashMap::new();
+    for ticker in &at_risk {
+        inputs.insert(ticker.clone(), option_pricing_inputs(ticker, fed_rate).await);
+    }
+
+    // Phase 3: roll and settle under write lock (no await while holding)
+    for cand in candidates {
+        // An unpriced underlying leaves the contract unchecked so the next sweep retries it
+        let spot = prices.get(&cand.ticker).filter(|p| **p > 0.0);
+        let (Some(div), Some(&spot), Some(&pricing)) = (dividends.get(&cand.ticker), spot, inputs.get(&cand.ticker)) else {
+            continue;
+        };
+        if cand.contract.assignment_checked == Some(div.ex_date) {
+            continue;
+        }
+        let Some(u) = users.get(&cand.user_id) else { continue };
+
+        let years = crate::options::years_to_expiry(&cand.contract.expiry, now);
+        let prob = crate::options::early_assignment_probability(spot, cand.contract.strike, years, pricing, div.amount_usd);
+        let assigned = rand::random::<f64>() < prob;
+
+        let msg = {
+            let mut user_data = u.write().await;
+            let stock = &mut user_data.stock;
+            let Some(port) = stock.portfolios.iter_mut().find(|p| p.name == cand.portfolio_name) else { continue };
+            let Some(pos_idx) = port.option_idx(&cand.contract.id) else { continue };
+            let contracts = match &mut port.positions[pos_idx].asset_type {
+                AssetType::Option(c) => {
+                    c.assignment_checked = Some(div.ex_date);
+                    c.contracts
+                }
+                _ => continue,
+            };
+            if !assigned {
+                continue;
+            }
+            match crate::options::exercise_position(port, &mut stock.trade_history, pos_idx, contracts, spot, &crate::trader::COST_MODEL) {
+                Ok(settlement) => format!(
+                    "<@{}> Early assignment ahead of the **${:.2}** dividend — SHORT **{} CALL ${:.2}** × {} in **{}** | Delivered **{:.0}** shares at the strike (P&L: **{}**)",
+                    cand.user_id, div.amount_usd, cand.ticker, cand.contract.strike, contracts, cand.portfolio_name,
+                    -settlement.shares, fmt_pnl(settlement.realized_pnl.unwrap_or(0.0)),
+                ),
+                Err(e) => {
+                    tracing::warn!(ticker = %cand.ticker, error = %e, "early assignment failed to settle");
+                    continue;
+                }
+            }
+        };
+        let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+    }
+}
+
+/// Charges daily borrow fees on short stock and force-covers any portfolio whose
+/// equity has fallen below `SHORT_MAINTENANCE_MARGIN_RATIO` of its short market value.
+pub(crate) async fn sweep_short_stock(
+    users: &UsersMap,
+    http: &Arc<serenity::Http>,
+    bot_chat: &str,
+) {
+    let now = Utc::now();
+    let Ok(channel_id) = bot_chat.parse::<u64>() else {
+        return;
+    };
+    let channel = ChannelId::new(channel_id);
+
+    // ── Phase 1: find portfolios holding shorts (read lock) ─────────────────
+    let mut targets: Vec<(serenity::UserId, String)> = Vec::new();
+    let mut tickers = std::collections::HashSet::new();
+    for entry in users.iter() {
+        let (user_id, u) = entry.pair();
+        let user_data = u.read().await;
+        for port in &user_data.stock.portfolios {
+            if port.positions.iter().any(data::Position::is_short_stock) {
+                targets.push((*user_id, port.name.clone()));
+                tickers.extend(port.positions.iter().map(|p| p.ticker.clone()));
+            }
+        }
+    }
+    if targets.is_empty() {
+        return;
+    }
+
+    // ── Phase 2: fetch prices (no locks held) ────────────────────────────────
+    let tickers: Vec<String> = tickers.into_iter().collect();
+    let mut prices = fetch_prices_map(&tickers).await;
+    prices.retain(|_, p| *p > 0.0);
+
+    // ── Phase 3: charge fees and enforce maintenance margin (write lock) ────
+    for (user_id, port_name) in targets {
+        let Some(u) = users.get(&user_id) else { continue };
+        let mut user_data = u.write().await;
+        let Some(port_idx) = user_data.stock.find_portfolio_idx(&port_name) else { continue };
+
+        let shorts: Vec<(String, f64)> = user_data.stock.portfolios[port_idx].positions.iter()
+            .filter(|p| p.is_short_stock())
+            .map(|p| (p.ticker.clone(), -p.quantity))
+            .collect();
+        if shorts.iter().any(|(t, _)| !prices.contains_key(t)) {
+            continue; // can't price every short — try again next cycle
+        }
+        let short_value_usd: f64 = shorts.iter().map(|(t, q)| prices[t] * q).sum();
+
+        let mut msgs = Vec::new();
+        {
+            let port = &mut user_data.stock.portfolios[port_idx];
+            let last = *port.last_borrow_charged.get_or_insert(now);
+            let days = (now - last).num_days();
+            if days > 0 {
+                let fee = price_to_creds(short_value_usd) * data::SHORT_BORROW_RATE / 100.0 / 365.0 * days as f64;
+                port.cash -= fee;
+                port.last_borrow_charged = Some(last + chrono::Duration::days(days));
+            }
+        }
+
+        if user_data.stock.portfolios[port_idx].positions.iter().any(|p| !prices.contains_key(&p.ticker)) {
+            continue; // an unpriced holding would understate equity — try again next cycle
+        }
+        let equity = crate::trader::portfolio_equity(&user_data.stock.portfolios[port_idx], &prices);
+        let required: f64 = shorts.iter().map(|(t, q)| price_to_creds(crate::trader::short_maintenance_usd(prices[t], *q))).sum();
+        if equity < required {
+            let stock = &mut user_data.stock;
+            for (ticker, qty) in &shorts {
+                let price_usd = prices[ticker];
+                let pnl = crate::trader::apply_cover(
+                    &mut stock.portfolios[port_idx],
+                    &mut stock.trade_history,
+                    ticker,
+                    ticker,
+                    *qty,
+                    price_to_creds(price_usd),
+                    &port_name,
+                    &crate::trader::COST_MODEL,
+                ).unwrap_or(0.0);
+                msgs.push(format!("**{} {}** @ ${:.2} ({})", fmt_qty(*qty), ticker, price_usd, fmt_pnl(pnl)));
+            }
+        }
+        drop(user_data);
+
+        if !msgs.is_empty() {
+            let msg = format!(
+                "<@{}> **Forced buy-in** in **{}** — equity **${:.2}** fell below the **${:.2}** maintenance requirement.\nCovered: {}",
+                user_id, port_name, creds_to_price(equity), creds_to_price(required), msgs.join(", "),
+            );
+            let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+        }
+    }
+}
+
+/// Charges margin interest on borrowed cash, issues margin calls when equity falls below
+/// `MARGIN_MAINTENANCE_RATIO`, and liquidates longs if a call stays uncured past the grace period.
+pub(crate) async fn sweep_margin_accounts(
+    users: &UsersMap,
+    http: &Arc<serenity::Http>,
+    bot_chat: &str,
+    fed_rate: &Arc<RwLock<f64>>,
+) {
+    let now = Utc::now();
+    let Ok(channel_id) = bot_chat.parse::<u64>() else {
+        return;
+    };
+    let channel = ChannelId::new(channel_id);
+    let annual_rate = crate::trader::margin_annual_rate(*fed_rate.read().await);
+
+    // ── Phase 1: find borrowed portfolios (read lock) ────────────────────────
+    let mut targets: Vec<(serenity::UserId, String)> = Vec::new();
+    let mut tickers = std::collections::HashSet::new();
+    for entry in users.iter() {
+        let (user_id, u) = entry.pair();
+        let user_data = u.read().await;
+        for port in &user_data.stock.portfolios {
+            if crate::trader::margin_loan(port) > 0.0 || port.margin_call_at.is_some() {
+                targets.push((*user_id, port.name.clone()));
+                tickers.extend(port.positions.iter().map(|p| p.ticker.clone()));
+            }
+        }
+    }
+    if targets.is_empty() {
+        return;
+    }
+
+    // ── Phase 2: fetch prices (no locks held) ────────────────────────────────
+    let tickers: Vec<String> = tickers.into_iter().collect();
+    let mut prices = fetch_prices_map(&tickers).await;
+    prices.retain(|_, p| *p > 0.0);
+
+    // ── Phase 3: interest, margin calls, liquidation (write lock) ────────────
+    for (user_id, port_name) in targets {
+        let Some(u) = users.get(&user_id) else { continue };
+        let mut user_data = u.write().await;
+        let Some(port_idx) = user_data.stock.find_portfolio_idx(&port_name) else { continue };
+
+        {
+            let port = &mut user_data.stock.portfolios[port_idx];
+            let last = *port.last_margin_interest.get_or_insert(now);
+            let days = (now - last).num_days();
+            if days > 0 {
+                port.cash -= crate::trader::margin_interest(crate::trader::margin_loan(port), annual_rate, days);
+                port.last_margin_interest = Some(last + chrono::Duration::days(days));
+            }
+        }
+
+        let port = &user_data.stock.portfolios[port_idx];
+        if port.positions.iter().any(|p| !prices.contains_key(&p.ticker)) {
+            continue; // can't value every holding — try again next cycle
+        }
+
+        let msg = match (crate::trader::maintenance_shortfall(port, &prices), port.margin_call_at) {
+            (None, None) => None,
+            (None, Some(_)) => {
+                user_data.stock.portfolios[port_idx].margin_call_at = None;
+                Some(format!("<@{user_id}> Margin call on **{port_name}** has been cured."))
+            }
+            (Some(shortfall), None) => {
+                user_data.stock.portfolios[port_idx].margin_call_at = Some(now);
+                let deadline = now + chrono::Duration::hours(data::MARGIN_CALL_GRACE_HOURS);
+                Some(format!(
+                    "<@{}> **Margin call** on **{}** — equity is **${:.2}** short of the {:.0}% maintenance requirement. Deposit cash or sell holdings before <t:{}:f> to avoid liquidation.",
+                    user_id, port_name, creds_to_price(shortfall), data::MARGIN_MAINTENANCE_RATIO * 100.0, deadline.timestamp(),
+                ))
+            }
+            (Some(_), Some(called_at)) if now - called_at < chrono::Duration::hours(data::MARGIN_CALL_GRACE_HOURS) => None,
+            (Some(_), Some(_)) => {
+                let plan = crate::trader::liquidation_plan(port, &prices);
+                let stock = &mut user_data.stock;
+                let mut sold = Vec::new();
+                for (ticker, qty) in plan {
+                    let price_usd = prices[&ticker];
+                    let pnl = crate::trader::apply_sell(
+                        &mut stock.portfolios[port_idx],
+                        &mut stock.trade_history,
+                        &ticker,
+                        &ticker,
+                        qty,
+                        price_to_creds(price_usd),
+                        &port_name,
+                        &crate::trader::COST_MODEL,
+                    ).unwrap_or(0.0);
+                    sold.push(format!("**{} {}** @ ${:.2} ({})", fmt_qty(qty), ticker, price_usd, fmt_pnl(pnl)));
+                }
+                stock.portfolios[port_idx].margin_call_at = None;
+                Some(format!(
+                    "<@{}> Margin call on **{}** was not cured — **liquidated** to repay the loan.\nSold: {}",
+                    user_id, port_name, if sold.is_empty() { "nothing (no priced holdings)".to_string() } else { sold.join(", ") },
+                ))
+            }
+        };
+        drop(user_data);
+
+        if let Some(msg) = msg {
+            let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+        }
+    }
+}
+
+/// Re-marks naked short options against live prices: collateral follows the requirement,
+/// uncovered shortfalls trigger a margin call, and positions are bought back when collateral
+/// drops below `OPTION_MAINTENANCE_RATIO` of the requirement or a call outlasts its grace period.
+pub(crate) async fn sweep_option_margin(
+    users: &UsersMap,
+    http: &Arc<serenity::Http>,
+    bot_chat: &str,
+    fed_rate: &Arc<RwLock<f64>>,
+) {
+    let now = Utc::now();
+    let Ok(channel_id) = bot_chat.parse::<u64>() else {
+        return;
+    };
+    let channel = ChannelId::new(channel_id);
+
+    // ── Phase 1: find portfolios with naked shorts (read lock) ───────────────
+    let mut targets: Vec<(serenity::UserId, String)> = Vec::new();
+    let mut tickers = std::collections::HashSet::new();
+    for entry in users.iter() {
+        let (user_id, u) = entry.pair();
+        let user_data = u.read().await;
+        for port in &user_data.stock.portfolios {
+            let naked: Vec<&data::Position> = port.positions.iter().filter(|p| crate::options::is_naked_short(p)).collect();
+            if !naked.is_empty() || port.option_margin_call_at.is_some() {
+                targets.push((*user_id, port.name.clone()));
+                tickers.extend(naked.iter().map(|p| p.ticker.clone()));
+            }
+        }
+    }
+    if targets.is_empty() {
+        return;
+    }
+
+    // ── Phase 2: prices and pricing inputs (no locks held) ───────────────────
+    let tickers: Vec<String> = tickers.into_iter().collect();
+    let prices = fetch_prices_map(&tickers).await;
+    let mut marks = HashMap::new();
+    for ticker in &tickers {
+        if let Some(&spot) = prices.get(ticker).filter(|p| **p > 0.0) {
+            marks.insert(ticker.clone(), (spot, option_pricing_inputs(ticker, fed_rate).await));
+        }
+    }
+
+    // ── Phase 3: adjust collateral, margin calls, forced closes (write lock) ─
+    for (user_id, port_name) in targets {
+        let Some(u) = users.get(&user_id) else { continue };
+        let mut user_data = u.write().await;
+        let Some(port_idx) = user_data.stock.find_portfolio_idx(&port_name) else { continue };
+        let stock = &mut user_data.stock;
+        let port = &mut stock.portfolios[port_idx];
+        let Some(review) = crate::options::review_naked_margin(port, &marks, now) else {
+            continue; // can't price every naked short — try again next cycle
+        };
+
+        let grace = chrono::Duration::hours(data::MARGIN_CALL_GRACE_HOURS);
+        let to_close = match port.option_margin_call_at {
+            _ if !review.breached.is_empty() => review.breached.clone(),
+            Some(called_at) if review.underfunded.is_empty() || now - called_at < grace => Vec::new(),
+            Some(_) => review.underfunded.clone(),
+            None => Vec::new(),
+        };
+
+        let msg = if !to_close.is_empty() {
+            let mut closed = Vec::new();
+            // Highest index first so earlier indices stay valid
+            for &idx in to_close.iter().rev() {
+                let ticker = port.positions[idx].ticker.clone();
+                let (spot, inputs) = marks[&ticker];
+                let label = match &port.positions[idx].asset_type {
+                    AssetType::Option(c) => format!("{} {} ${:.2}", ticker, option_type_str(c.option_type), c.strike),
+                    _ => ticker.clone(),
+                };
+                let pnl = crate::options::force_close_short(port, &mut stock.trade_history, idx, spot, inputs, &crate::trader::COST_MODEL);
+                closed.push(format!("**{label}** ({})", fmt_pnl(pnl)));
+            }
+            // Buying back spends cash, so re-mark what's left before lifting the call
+            let cured = crate::options::review_naked_margin(port, &marks, now).is_some_and(|r| r.underfunded.is_empty());
+            let status = if cured {
+                port.option_margin_call_at = None;
+                ""
+            } else {
+                port.option_margin_call_at.get_or_insert(now);
+                "\nThe remaining naked shorts are still underfunded — the margin call stays open."
+            };
+            Some(format!(
+                "<@{}> **Forced close** of naked options in **{}** — collateral fell below the maintenance requirement.\nBought back: {}{}",
+                user_id, port_name, closed.join(", "), status,
+            ))
+        } else if review.underfunded.is_empty() {
+            port.option_margin_call_at.take()
+                .map(|_| format!("<@{user_id}> Options margin call on **{port_name}** has been cured."))
+        } else if port.option_margin_call_at.is_none() {
+            port.option_margin_call_at = Some(now);
+            Some(format!(
+                "<@{}> **Options margin call** on **{}** — naked shorts need **${:.2}** more collateral than is free. Add cash or cover positions before <t:{}:f> to avoid a forced close.",
+                user_id, port_name, creds_to_price(review.shortfall), (now + grace).timestamp(),
+            ))
+        } else {
+            None
+        };
+        drop(user_data);
+
+        if let Some(msg) = msg {
+            let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+        }
+    }
+}
+
 /// Fetches prices for a list of tickers concurrently and returns a ticker → USD price map.
 /// Tickers that fail to fetch are included with a value of 0.0.
 pub(crate) async fn fetch_prices_map(tickers: &[String]) -> HashMap<String, f64> {
@@ -636,6 +1273,19 @@ pub(crate) async fn fetch_prices_map(tickers: &[String]) -> HashMap<String, f64>
     ).await.into_iter().collect()
 }
 
+/// Last cached quote price per ticker regardless of age — no network calls, safe under a lock.
+pub(crate) fn cached_prices_map(tickers: &[String]) -> HashMap<String, f64> {
+    tickers.iter()
+        .filter_map(|t| QUOTE_CACHE.get(t).and_then(|e| e.0.regular_market_price).map(|p| (t.clone(), p)))
+        .collect()
+}
+
+/// Buying power of `port` priced from the quote cache (see `trader::buying_power`).
+pub(crate) fn cached_buying_power(port: &data::Portfolio) -> f64 {
+    let tickers: Vec<String> = port.positions.iter().map(|p| p.ticker.clone()).collect();
+    crate::trader::buying_power(port, &cached_prices_map(&tickers))
+}
+
 pub(crate) async fn is_market_open() -> bool {
     fetch_quote_detail("SPY").await
         .is_some_and(|q| q.is_market_open())
@@ -759,9 +1409,10 @@ pub(crate) async fn sweep_pending_orders(
         let msg = match order.side {
             OrderSide::Buy => {
                 let total_cost = price_per_unit * order.quantity;
+                let fees = crate::trader::COST_MODEL.fees(&order.asset_type, total_cost);
                 let port_idx = user_data.stock.find_portfolio_idx(&order.portfolio_name);
                 match port_idx {
-                    Some(idx) if user_data.stock.portfolios[idx].cash >= total_cost => {
+                    Some(idx) if cached_buying_power(&user_data.stock.portfolios[idx]) >= total_cost + fees.total() => {
                         let stock = &mut user_data.stock;
                         crate::trader::apply_buy(
                             &mut stock.portfolios[idx],
@@ -773,16 +1424,17 @@ pub(crate) async fn sweep_pending_orders(
                             price_per_unit,
                             total_cost,
                             &order.portfolio_name,
+                            &crate::trader::COST_MODEL,
                         );
                         format!(
-                            "<@{}> Limit buy filled: **{} {}** @ **${:.2}**/unit (${:.2} total) in **{}**.",
+                            "<@{}> Limit buy filled: **{} {}** @ **${:.2}**/unit (${:.2} total, ${:.2} fees) in **{}**.",
                             snap.user_id, fmt_qty(order.quantity), order.ticker, price_usd,
-                            creds_to_price(total_cost), order.portfolio_name,
+                            creds_to_price(total_cost), creds_to_price(fees.total()), order.portfolio_name,
                         )
                     }
                     Some(_) => {
                         format!(
-                            "<@{}> Limit buy **{}** (#{}) cancelled — insufficient cash in **{}**.",
+                            "<@{}> Limit buy **{}** (#{}) cancelled — insufficient buying power in **{}**.",
                             snap.user_id, order.ticker, order.id, order.portfolio_name,
                         )
                     }
@@ -799,7 +1451,7 @@ pub(crate) async fn sweep_pending_orders(
                 match port_idx {
                     Some(idx) => {
                         let held = user_data.stock.portfolios[idx].positions.iter()
-                            .find(|p| p.ticker == order.ticker && !matches!(&p.asset_type, AssetType::Option(_)))
+                            .find(|p| p.ticker == order.ticker && p.is_long_stock())
                             .map_or(0.0, |p| p.quantity);
                         let qty = if (order.quantity - held).abs() < 5e-5 { held } else { order.quantity };
                         if held < qty - 1e-9 {
@@ -818,6 +1470,7 @@ pub(crate) async fn sweep_pending_orders(
                                 qty,
                                 price_per_unit,
                                 &order.portfolio_name,
+                                &crate::trader::COST_MODEL,
                             ).unwrap_or(0.0);
                             format!(
                                 "<@{}> Limit sell filled: **{} {}** @ **${:.2}**/unit (${:.2}) | P&L: **{}** | Portfolio: **{}**.",
@@ -841,6 +1494,287 @@ pub(crate) async fn sweep_pending_orders(
     }
 }
 
+/// Sweep recurring (DCA) plans: buy each due plan's dollar amount at market, skipping the
+/// run if the portfolio lacks free cash, then schedule the next run.
+pub(crate) async fn sweep_recurring_buys(
+    users: &UsersMap,
+    http: &Arc<serenity::Http>,
+    bot_chat: &str,
+) {
+    let channel = ChannelId::new(
+        bot_chat.parse::<u64>().expect("bot_chat must be a valid u64"),
+    );
+    let now = Utc::now();
+
+    // ── Phase 1: snapshot due plans (no await inside lock) ──────────────────
+    let mut due: Vec<(serenity::UserId, u32, String)> = Vec::new();
+    for entry in users.iter() {
+        let user_id = *entry.key();
+        let guard = entry.value().read().await;
+        for plan in &guard.stock.recurring {
+            if !plan.paused && plan.next_run <= now {
+                due.push((user_id, plan.id, plan.ticker.clone()));
+            }
+        }
+    }
+
+    if due.is_empty() {
+        return;
+    }
+
+    // ── Phase 2: fetch prices concurrently (no locks held) ──────────────────
+    let unique_tickers: Vec<String> = {
+        let mut seen = std::collections::HashSet::new();
+        due.iter().filter_map(|(_, _, t)| if seen.insert(t.as_str()) { Some(t.clone()) } else { None }).collect()
+    };
+    let mut prices = fetch_prices_map(&unique_tickers).await;
+    prices.retain(|_, p| *p > 0.0);
+
+    // ── Phase 3: buy or skip under write lock ───────────────────────────────
+    for (user_id, plan_id, ticker) in &due {
+        let Some(&price_usd) = prices.get(ticker) else { continue }; // retry next cycle
+        let Some(entry) = users.get(user_id) else { continue };
+        let mut user_data = entry.value().write().await;
+
+        // Confirm the plan is still present and due (could have been paused or cancelled)
+        let Some(plan_idx) = user_data.stock.recurring.iter().position(|p| p.id == *plan_id && !p.paused && p.next_run <= now) else {
+            continue;
+        };
+        let plan = user_data.stock.recurring[plan_idx].clone();
+
+        let Some(port_idx) = user_data.stock.find_portfolio_idx(&plan.portfolio_name) else {
+            user_data.stock.recurring.remove(plan_idx);
+            drop(user_data);
+            let msg = format!(
+                "<@{}> Recurring buy **{}** (#{}) cancelled — portfolio **{}** not found.",
+                user_id, plan.ticker, plan.id, plan.portfolio_name,
+            );
+            let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+            continue;
+        };
+
+        let price_per_unit = price_to_creds(price_usd);
+        let total_cost = price_to_creds(plan.amount_usd);
+        let quantity = total_cost / price_per_unit;
+        let fees = crate::trader::COST_MODEL.fees(&plan.asset_type, total_cost);
+        let port = &user_data.stock.portfolios[port_idx];
+        let buying_power = cached_buying_power(port);
+
+        let msg = if buying_power >= total_cost + fees.total() {
+            let stock = &mut user_data.stock;
+            crate::trader::apply_buy(
+                &mut stock.portfolios[port_idx],
+                &mut stock.trade_history,
+                &plan.ticker,
+                &plan.asset_name,
+                plan.asset_type.clone(),
+                quantity,
+                price_per_unit,
+                total_cost,
+                &plan.portfolio_name,
+                &crate::trader::COST_MODEL,
+            );
+            stock.recurring[plan_idx].skipped = 0;
+            format!(
+                "<@{}> Recurring buy filled: **{} {}** @ **${:.2}**/unit (${:.2} total, ${:.2} fees) in **{}**.",
+                user_id, fmt_qty(quantity), plan.ticker, price_usd,
+                plan.amount_usd, creds_to_price(fees.total()), plan.portfolio_name,
+            )
+        } else {
+            user_data.stock.recurring[plan_idx].skipped += 1;
+            format!(
+                "<@{}> Recurring buy **{}** (#{}) skipped — **{}** needs ${:.2} of buying power but has ${:.2}.",
+                user_id, plan.ticker, plan.id, plan.portfolio_name,
+                creds_to_price(total_cost + fees.total()), creds_to_price(buying_power.max(0.0)),
+            )
+        };
+        user_data.stock.recurring[plan_idx].next_run = plan.frequency.next_after(plan.next_run, now);
+
+        drop(user_data);
+        let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+    }
+}
+
+/// Sweep scheduled auto-rebalances: re-plan each due portfolio against fresh prices, fill the
+/// orders that are outside their drift bands, and schedule the next run.
+pub(crate) async fn sweep_auto_rebalance(
+    users: &UsersMap,
+    http: &Arc<serenity::Http>,
+    bot_chat: &str,
+) {
+    let channel = ChannelId::new(
+        bot_chat.parse::<u64>().expect("bot_chat must be a valid u64"),
+    );
+    let now = Utc::now();
+
+    // ── Phase 1: snapshot due portfolios and the tickers they need ──────────
+    let mut due: Vec<(serenity::UserId, String, Vec<String>)> = Vec::new();
+    for entry in users.iter() {
+        let user_id = *entry.key();
+        let guard = entry.value().read().await;
+        for port in &guard.stock.portfolios {
+            let settings = &port.rebalance;
+            if !settings.targets.is_empty() && settings.next_run.is_some_and(|t| t <= now) {
+                due.push((user_id, port.name.clone(), crate::trader::rebalance_tickers(port)));
+            }
+        }
+    }
+
+    if due.is_empty() {
+        return;
+    }
+
+    // ── Phase 2: fetch prices concurrently (no locks held) ──────────────────
+    let unique_tickers: Vec<String> = {
+        let mut seen = std::collections::HashSet::new();
+        due.iter().flat_map(|(_, _, t)| t).filter(|t| seen.insert(t.as_str())).cloned().collect()
+    };
+    let prices = fetch_prices_map(&unique_tickers).await;
+
+    // ── Phase 3: re-plan and fill under write lock ──────────────────────────
+    for (user_id, port_name, _) in &due {
+        let Some(entry) = users.get(user_id) else { continue };
+        let mut user_data = entry.value().write().await;
+        let Some(port_idx) = user_data.stock.find_portfolio_idx(port_name) else { continue };
+        let settings = &user_data.stock.portfolios[port_idx].rebalance;
+        let Some(next_run) = settings.next_run.filter(|t| *t <= now) else { continue };
+        let schedule = settings.schedule;
+
+        let plan = crate::trader::rebalance_plan(&user_data.stock.portfolios[port_idx], &prices);
+        // Retry on the next tick rather than trade on a misjudged plan
+        if plan.unpriced {
+            continue;
+        }
+        let fills = crate::trader::execute_rebalance(&mut user_data.stock, port_idx, &plan);
+        user_data.stock.portfolios[port_idx].rebalance.next_run = schedule.next_after(next_run, now);
+
+        drop(user_data);
+        if fills.is_empty() {
+            continue;
+        }
+        let msg = format!("<@{}> Auto-rebalanced **{}** ({}):\n{}", user_id, port_name, schedule.label(), fills.join("\n"));
+        let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+    }
+}
+
+/// A copied portfolio and its trades since the oldest of its followers' syncs.
+type LeaderSnapshot = (data::Portfolio, Vec<TradeRecord>);
+
+/// Sweep copy-trading subscriptions: mirror each leader trade made since the follower last
+/// synced, scaled by the two portfolios' equity, and ping the follower. Followers of a portfolio
+/// that's no longer open to copying are unsubscribed.
+pub(crate) async fn sweep_copy_trades(
+    users: &UsersMap,
+    http: &Arc<serenity::Http>,
+    bot_chat: &str,
+) {
+    let channel = ChannelId::new(
+        bot_chat.parse::<u64>().expect("bot_chat must be a valid u64"),
+    );
+
+    // ── Phase 1: snapshot subscriptions, then each leader's portfolio and new trades ──
+    let mut follows: Vec<(serenity::UserId, String, data::CopySettings, Vec<String>)> = Vec::new();
+    for entry in users.iter() {
+        let guard = entry.value().read().await;
+        for port in &guard.stock.portfolios {
+            if let Some(settings) = &port.copy {
+                let tickers = port.positions.iter().map(|p| p.ticker.clone()).collect();
+                follows.push((*entry.key(), port.name.clone(), settings.clone(), tickers));
+            }
+        }
+    }
+    if follows.is_empty() {
+        return;
+    }
+
+    // `None` once the leader portfolio is gone or closed to copying
+    let mut leaders: HashMap<(serenity::UserId, String), Option<LeaderSnapshot>> = HashMap::new();
+    for (_, _, settings, _) in &follows {
+        let key = (settings.leader, settings.leader_port.clone());
+        if leaders.contains_key(&key) {
+            continue;
+        }
+        let since = follows.iter()
+            .filter(|(_, _, f, _)| (f.leader, &f.leader_port) == (key.0, &key.1))
+            .map(|(_, _, f, _)| f.synced_at)
+            .min()
+            .unwrap_or(settings.synced_at);
+        let snapshot = match users.get(&key.0) {
+            None => None,
+            Some(entry) => {
+                let guard = entry.value().read().await;
+                guard.stock.portfolios.iter().find(|p| p.name == key.1 && p.copyable).map(|p| {
+                    let trades = guard.stock.trade_history.iter().filter(|t| t.portfolio == p.name && t.timestamp > since).cloned().collect();
+                    (p.clone(), trades)
+                })
+            }
+        };
+        leaders.insert(key, snapshot);
+    }
+    follows.retain(|(_, _, settings, _)| match &leaders[&(settings.leader, settings.leader_port.clone())] {
+        None => true,
+        Some((_, trades)) => trades.iter().any(|t| t.timestamp > settings.synced_at),
+    });
+    if follows.is_empty() {
+        return;
+    }
+
+    // ── Phase 2: fetch prices for both sides of every live subscription (no locks held) ──
+    let unique_tickers: Vec<String> = {
+        let mut seen = std::collections::HashSet::new();
+        let leader_tickers = leaders.values().flatten()
+            .flat_map(|(p, trades)| p.positions.iter().map(|pos| &pos.ticker).chain(trades.iter().map(|t| &t.ticker)));
+        follows.iter().flat_map(|(_, _, _, t)| t).chain(leader_tickers).filter(|t| seen.insert(t.as_str())).cloned().collect()
+    };
+    let prices = fetch_prices_map(&unique_tickers).await;
+
+    // ── Phase 3: mirror under write lock ─────────────────────────────────────
+    for (user_id, port_name, settings, _) in &follows {
+        let Some(entry) = users.get(user_id) else { continue };
+        let mut user_data = entry.value().write().await;
+        let Some(idx) = user_data.stock.find_portfolio_idx(port_name) else { continue };
+        // Skip subscriptions that changed since phase 1
+        let Some(current) = user_data.stock.portfolios[idx].copy.clone()
+            .filter(|c| (c.leader, &c.leader_port) == (settings.leader, &settings.leader_port))
+        else { continue };
+
+        let msg = match &leaders[&(settings.leader, settings.leader_port.clone())] {
+            None => {
+                user_data.stock.portfolios[idx].copy = None;
+                format!(
+                    "<@{}> **{}** stopped copying <@{}>'s **{}** — it's no longer open to copying.",
+                    user_id, port_name, settings.leader, settings.leader_port,
+                )
+            }
+            Some((leader_port, trades)) => {
+                let new: Vec<&TradeRecord> = trades.iter().filter(|t| t.timestamp > current.synced_at).collect();
+                let Some(last) = new.iter().map(|t| t.timestamp).max() else { continue };
+                // An unpriced holding would skew the equity ratio; retry these trades next sweep
+                let priced = |t: &String| prices.get(t).is_some_and(|p| *p > 0.0);
+                if !leader_port.positions.iter().chain(&user_data.stock.portfolios[idx].positions).all(|p| priced(&p.ticker))
+                    || !new.iter().filter(|t| !t.option_leg).all(|t| priced(&t.ticker))
+                {
+                    continue;
+                }
+                let leader_equity = crate::trader::portfolio_equity(leader_port, &prices);
+                let follower_equity = crate::trader::portfolio_equity(&user_data.stock.portfolios[idx], &prices);
+                let ratio = if leader_equity > 0.0 { follower_equity.max(0.0) / leader_equity } else { 0.0 };
+
+                let fills = crate::trader::mirror_trades(&mut user_data.stock, idx, leader_port, &new, (ratio, current.max_trade_creds), &prices);
+                if let Some(c) = user_data.stock.portfolios[idx].copy.as_mut() {
+                    c.synced_at = last;
+                }
+                if fills.is_empty() {
+                    continue;
+                }
+                crate::trader::fmt_copy_notice(*user_id, port_name, &current, &fills)
+            }
+        };
+        drop(user_data);
+        let _ = channel.send_message(http, CreateMessage::new().content(msg)).await;
+    }
+}
+
 impl OrderSide {
     pub const fn label(&self) -> &'static str {
         match self {
@@ -1,7 +1,8 @@
-//! /leaderboard command — creds, fortune, and investment rankings with pagination.
+//! /leaderboard command — creds, fortune, investment, and vs-benchmark rankings with pagination.
 
 use crate::{data, serenity, Context, Error};
 use crate::helper::{creds_to_price, default_footer};
+use crate::trader::benchmark_stats;
 use poise::serenity_prelude::{EditMessage, futures, UserId};
 use std::sync::Arc;
 use std::time::Duration;
@@ -19,11 +20,14 @@ pub fn fmt_pnl_short(pnl: f64) -> String {
 }
 
 #[derive(Clone, Copy, PartialEq)]
-enum Sort { Creds, Fortune, Invest }
+enum Sort { Creds, Fortune, Invest, Benchmark }
 
 /// (`user_id`, `sort_key`, `display_label`, `username`)
 type Entry = (UserId, i64, String, String);
 
+/// (`user_id`, creds, luck score, luck label, realized P&L, cost, best portfolio's (return over benchmark, name))
+type UserStats = (UserId, i32, i32, String, f64, f64, Option<(f64, String)>);
+
 fn build_page(entries: &[Entry], page: usize) -> String {
     let start = page * 10;
     let mut text = String::from("﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\n```\n");
@@ -47,6 +51,10 @@ fn make_components(active: Sort) -> Vec<serenity::CreateActionRow> {
         serenity::CreateButton::new("lb_invest")
             .label("Investment")
             .style(if active == Sort::Invest { Primary } else { Secondary }),
+        serenity::CreateButton::new("lb_bench")
+            .label(format!("vs {}", data::BENCHMARK_TICKER))
+            .style(if active == Sort::Benchmark { Primary } else { Secondary }),
+    ]), serenity::CreateActionRow::Buttons(vec![
         serenity::CreateButton::new("lb_back").label("<").style(Secondary),
         serenity::CreateButton::new("lb_next").label(">").style(Secondary),
     ])]
@@ -58,6 +66,7 @@ fn make_embed(entries: &[Entry], sort: Sort, page: usize, thumbnail: &str) -> se
         Sort::Creds   => "Leaderboard — Creds",
         Sort::Fortune => "Leaderboard — Rolling Fortune",
         Sort::Invest  => "Leaderboard — Investment Gains",
+        Sort::Benchmark => "Leaderboard — Beating the Market",
     };
     serenity::CreateEmbed::new()
         .title(title)
@@ -74,6 +83,7 @@ fn empty_embed(sort: Sort) -> serenity::CreateEmbed {
         Sort::Fortune => ("Leaderboard — Rolling Fortune", "No fortune data yet — users need to /uwu first."),
         Sort::Invest  => ("Leaderboard — Investment Gains", "No investment data yet — users need to make trades first."),
         Sort::Creds   => ("Leaderboard — Creds", "No users found."),
+        Sort::Benchmark => ("Leaderboard — Beating the Market", "No performance history yet — portfolios are snapshotted daily."),
     };
     serenity::CreateEmbed::new()
         .title(title)
@@ -91,6 +101,7 @@ struct Boards<'a> {
     creds:   Board<'a>,
     fortune: Board<'a>,
     invest:  Board<'a>,
+    bench:   Board<'a>,
 }
 
 impl<'a> Boards<'a> {
@@ -99,6 +110,7 @@ impl<'a> Boards<'a> {
             Sort::Creds   => &self.creds,
             Sort::Fortune => &self.fortune,
             Sort::Invest  => &self.invest,
+            Sort::Benchmark => &self.bench,
         }
     }
 }
@@ -108,7 +120,7 @@ fn render(boards: &Boards<'_>, sort: Sort, page: usize) -> serenity::CreateEmbed
     if board.entries.is_empty() { empty_embed(sort) } else { make_embed(board.entries, sort, page, board.thumb) }
 }
 
-/// show server rankings — use buttons to switch between Creds, Fortune, and Investment
+/// show server rankings — use buttons to switch between Creds, Fortune, Investment, and vs SPY
 #[poise::command(slash_command)]
 pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
     ctx.defer().await?;
@@ -120,7 +132,7 @@ pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
         .collect();
 
     // Read all user stats concurrently — RwLock reads, no DashMap involvement.
-    let stats: Vec<(UserId, i32, i32, String, f64, f64)> =
+    let stats: Vec<UserStats> =
         futures::future::join_all(user_arcs.iter().map(|(id, u)| async move {
             let u = u.read().await;
             let creds      = u.get_creds();
@@ -129,7 +141,11 @@ pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
             let (pnl, cost) = u.stock.trade_history.iter()
                 .filter_map(|t| t.realized_pnl.map(|p| (p, t.total_creds - p)))
                 .fold((0.0f64, 0.0f64), |(pa, ca), (p, c)| (pa + p, ca + c));
-            (*id, creds, luck_score, luck_label, pnl, cost)
+            // Best portfolio's return over the benchmark since it was created
+            let vs_bench = u.stock.portfolios.iter()
+                .filter_map(|p| Some((benchmark_stats(&p.snapshots, p.created_at.date_naive())?.relative(), p.name.clone())))
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            (*id, creds, luck_score, luck_label, pnl, cost, vs_bench)
         })).await;
 
     // Fetch username + avatar URL for every user concurrently — one API call per user,
@@ -146,12 +162,13 @@ pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
             }
         })).await;
 
-    // Build the three sorted leaderboard vectors.
+    // Build the four sorted leaderboard vectors.
     let mut creds_info:   Vec<Entry> = Vec::new();
     let mut fortune_info: Vec<Entry> = Vec::new();
     let mut invest_info:  Vec<Entry> = Vec::new();
+    let mut bench_info:   Vec<Entry> = Vec::new();
 
-    for ((id, creds, luck_score, luck_label, pnl, cost), (name, _)) in stats.iter().zip(meta.iter()) {
+    for ((id, creds, luck_score, luck_label, pnl, cost, vs_bench), (name, _)) in stats.iter().zip(meta.iter()) {
         creds_info.push((*id, i64::from(*creds), creds.to_string(), name.clone()));
 
         if *luck_score > 0 {
@@ -164,12 +181,19 @@ pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
             let label = format!("{} ({:+.1}%)", fmt_pnl_short(creds_to_price(*pnl)), pct);
             invest_info.push((*id, *pnl as i64, label, name.clone()));
         }
+
+        if let Some((relative, port)) = vs_bench {
+            let port_col: String = port.chars().take(8).collect();
+            // Sort key in basis points
+            bench_info.push((*id, (relative * 10_000.0) as i64, format!("{:+.2}% {port_col}", relative * 100.0), name.clone()));
+        }
     }
 
     // Primary: sort key descending. Secondary: username ascending — deterministic tie-breaking.
     creds_info.sort_by(|a, b| b.1.cmp(&a.1).then(a.3.cmp(&b.3)));
     fortune_info.sort_by(|a, b| b.1.cmp(&a.1).then(a.3.cmp(&b.3)));
     invest_info.sort_by(|a, b| b.1.cmp(&a.1).then(a.3.cmp(&b.3)));
+    bench_info.sort_by(|a, b| b.1.cmp(&a.1).then(a.3.cmp(&b.3)));
 
     if creds_info.is_empty() {
         ctx.say("No users found.").await?;
@@ -186,11 +210,13 @@ pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
     let creds_thumb   = avatar_of(creds_info[0].0);
     let fortune_thumb = fortune_info.first().map_or_else(|| creds_thumb.clone(), |(id, ..)| avatar_of(*id));
     let invest_thumb  = invest_info.first().map_or_else(|| creds_thumb.clone(), |(id, ..)| avatar_of(*id));
+    let bench_thumb   = bench_info.first().map_or_else(|| creds_thumb.clone(), |(id, ..)| avatar_of(*id));
 
     let boards = Boards {
         creds:   Board { entries: &creds_info,   thumb: creds_thumb.as_str()   },
         fortune: Board { entries: &fortune_info, thumb: fortune_thumb.as_str() },
         invest:  Board { entries: &invest_info,  thumb: invest_thumb.as_str()  },
+        bench:   Board { entries: &bench_info,   thumb: bench_thumb.as_str()   },
     };
 
     let reply = ctx.send(poise::CreateReply::default()
@@ -224,6 +250,7 @@ pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
             "lb_creds"   => { sort = Sort::Creds;   page = 0; }
             "lb_fortune" => { sort = Sort::Fortune; page = 0; }
             "lb_invest"  => { sort = Sort::Invest;  page = 0; }
+            "lb_bench"   => { sort = Sort::Benchmark; page = 0; }
             "lb_back"    => { page = page.saturating_sub(1); }
             "lb_next"    => { if page + 1 < total_pages { page += 1; } }
             _            => continue,
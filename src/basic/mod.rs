@@ -1,10 +1,12 @@
-//! Core commands — ping, wallet, `voice_status`, info, and the economy/leaderboard sub-modules.
+//! Core commands — ping, wallet, `voice_status`, info, and the economy/leaderboard/pay sub-modules.
 
 mod economy;
 mod leaderboard;
+mod pay;
 
 #[doc(inline)] pub use economy::{buy_tickets, claim_bonus, simulate_claim, simulate_uwu, uwu};
 #[doc(inline)] pub use leaderboard::leaderboard;
+#[doc(inline)] pub use pay::{ledger, pay, ticket_trade};
 
 use crate::{data, serenity, Context, Error};
 use crate::helper::{creds_to_price, default_footer};
@@ -0,0 +1,382 @@
+//! Member-to-member transfers — /pay, escrowed /ticket_trade offers, and the /ledger that records them.
+
+use crate::data::{self, Escrow, LedgerEntry, LedgerKind, UserData};
+use crate::helper::{creds_to_price, default_footer, price_to_creds};
+use crate::{serenity, Context, Error};
+use chrono::Utc;
+use poise::serenity_prelude::UserId;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+/// How long a ticket trade offer stays open.
+const TRADE_OFFER_TIMEOUT_SECS: u64 = 300;
+/// Longest note kept on a payment.
+const MAX_NOTE_LEN: usize = 100;
+/// Ledger entries shown by /ledger.
+const LEDGER_PAGE: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum TradeSide {
+    #[name = "Sell my tickets"]
+    Sell,
+    #[name = "Buy their tickets"]
+    Buy,
+}
+
+/// Why `user` can't send or receive value yet, if anything — keeps fresh alt accounts out.
+fn ineligible(user: &serenity::User, level: i32) -> Option<String> {
+    let age_days = (Utc::now().timestamp() - user.id.created_at().unix_timestamp()) / 86_400;
+    if age_days < data::MIN_PAY_ACCOUNT_AGE_DAYS {
+        return Some(format!("<@{}>'s Discord account must be at least **{}** days old.", user.id, data::MIN_PAY_ACCOUNT_AGE_DAYS));
+    }
+    (level < data::MIN_PAY_LEVEL).then(|| format!("<@{}> must reach **Level {}** first.", user.id, data::MIN_PAY_LEVEL))
+}
+
+/// Creds `sender` may still send within the rolling 24-hour limit.
+fn daily_pay_left(sender: &UserData) -> i64 {
+    let sent = sender.creds_sent_since(Utc::now() - chrono::Duration::hours(24));
+    (data::MAX_DAILY_PAY_CREDS - sent).max(0)
+}
+
+/// Why `sender` can't send `creds` right now — balance or the rolling 24-hour limit.
+fn send_blocked(sender: &UserData, creds: i32) -> Option<String> {
+    let left = daily_pay_left(sender);
+    if i64::from(creds) > left {
+        return Some(format!(
+            "You can send **${:.2}** more today (limit ${:.2} per 24 hours).",
+            creds_to_price(left as f64), creds_to_price(data::MAX_DAILY_PAY_CREDS as f64),
+        ));
+    }
+    (sender.get_creds() < creds).then(|| format!("You only have **${:.2}**.", creds_to_price(f64::from(sender.get_creds()))))
+}
+
+/// Write-locks two users, always the lower id first so opposite transfers can't deadlock.
+async fn write_pair<'a>(
+    a_id: UserId,
+    a: &'a RwLock<UserData>,
+    b_id: UserId,
+    b: &'a RwLock<UserData>,
+) -> (RwLockWriteGuard<'a, UserData>, RwLockWriteGuard<'a, UserData>) {
+    if a_id < b_id {
+        let ga = a.write().await;
+        (ga, b.write().await)
+    } else {
+        let gb = b.write().await;
+        (a.write().await, gb)
+    }
+}
+
+/// Records a transfer on both sides' ledgers. `creds` and `tickets` are what `from` gave `to`
+/// (negative for what `to` gave back).
+fn log_both((from, from_id): (&mut UserData, UserId), (to, to_id): (&mut UserData, UserId), kind: LedgerKind, creds: i32, tickets: i32, note: Option<&str>) {
+    let timestamp = Utc::now();
+    let note = note.map(str::to_string);
+    from.push_ledger(LedgerEntry { timestamp, kind, counterparty: to_id, creds: -creds, tickets: -tickets, note: note.clone() });
+    to.push_ledger(LedgerEntry { timestamp, kind, counterparty: from_id, creds, tickets, note });
+}
+
+/// Looks up both parties, checking neither is a bot, the caller themselves, unregistered, or too new.
+async fn counterparty(ctx: Context<'_>, user: &serenity::User) -> Result<(Arc<RwLock<UserData>>, Arc<RwLock<UserData>>), String> {
+    if user.bot || user.id == ctx.author().id {
+        return Err("Pick another member.".to_string());
+    }
+    let me = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let Some(them) = ctx.data().users.get(&user.id).map(|u| Arc::clone(u.value())) else {
+        return Err(format!("<@{}> hasn't started using ProfessorBot yet.", user.id));
+    };
+    let (my_level, their_level) = (me.read().await.get_level(), them.read().await.get_level());
+    if let Some(why) = ineligible(ctx.author(), my_level).or_else(|| ineligible(user, their_level)) {
+        return Err(why);
+    }
+    Ok((me, them))
+}
+
+/// send creds to another member
+#[poise::command(slash_command, guild_only)]
+pub async fn pay(
+    ctx: Context<'_>,
+    #[description = "Member to pay"] user: serenity::User,
+    #[description = "Dollars to send"] amount: f64,
+    #[description = "What it's for"] note: Option<String>,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Pay").description(desc).color(data::EMBED_ERROR),
+    );
+    if !(0.01..=creds_to_price(data::MAX_DAILY_PAY_CREDS as f64)).contains(&amount) {
+        ctx.send(err_embed(format!("Amount must be between $0.01 and ${:.2}.", creds_to_price(data::MAX_DAILY_PAY_CREDS as f64)))).await?;
+        return Ok(());
+    }
+    let (me, them) = match counterparty(ctx, &user).await {
+        Ok(pair) => pair,
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+    let creds = price_to_creds(amount).round() as i32;
+    let note: Option<String> = note.map(|n| n.trim().chars().take(MAX_NOTE_LEN).collect()).filter(|n: &String| !n.is_empty());
+    if let Some(desc) = send_blocked(&*me.read().await, creds) {
+        ctx.send(err_embed(desc)).await?;
+        return Ok(());
+    }
+
+    let note_line = note.as_deref().map(|n| format!("\n> {n}")).unwrap_or_default();
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(serenity::CreateEmbed::new()
+            .title("Pay")
+            .description(format!("Send **${amount:.2}** ({creds} creds) to <@{}>?{note_line}", user.id))
+            .color(data::EMBED_GOLD)
+            .footer(default_footer()))
+        .components(vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new("pay_yes").label("Send").style(serenity::ButtonStyle::Success),
+            serenity::CreateButton::new("pay_no").label("Cancel").style(serenity::ButtonStyle::Secondary),
+        ])])
+    ).await?;
+
+    let press = reply.message().await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(30))
+        .await;
+    let Some(press) = press.filter(|p| p.data.custom_id == "pay_yes") else {
+        reply.edit(ctx, err_embed("Payment cancelled.".to_string()).components(vec![])).await?;
+        return Ok(());
+    };
+    press.defer(ctx.http()).await?;
+
+    let result = {
+        let (mut sender, mut recipient) = write_pair(ctx.author().id, &me, user.id, &them).await;
+        // Balance and limit may have moved while the confirmation was up
+        match send_blocked(&sender, creds) {
+            Some(desc) => Err(desc),
+            None => {
+                sender.sub_creds(creds);
+                recipient.add_creds(creds);
+                log_both((&mut sender, ctx.author().id), (&mut recipient, user.id), LedgerKind::Payment, creds, 0, note.as_deref());
+                Ok(sender.get_creds())
+            }
+        }
+    };
+    match result {
+        Err(desc) => { reply.edit(ctx, err_embed(desc).components(vec![])).await?; }
+        Ok(left) => {
+            reply.edit(ctx, poise::CreateReply::default().content(format!("<@{}>", user.id)).embed(
+                serenity::CreateEmbed::new()
+                    .title("Pay")
+                    .description(format!(
+                        "<@{}> sent **${amount:.2}** to <@{}>.{note_line}\nRemaining balance: **${:.2}**",
+                        ctx.author().id, user.id, creds_to_price(f64::from(left)),
+                    ))
+                    .color(data::EMBED_SUCCESS)
+                    .footer(default_footer()),
+            ).components(vec![])).await?;
+        }
+    }
+    Ok(())
+}
+
+/// offer to swap raffle tickets for creds with another member
+#[poise::command(slash_command, guild_only)]
+pub async fn ticket_trade(
+    ctx: Context<'_>,
+    #[description = "Member to trade with"] user: serenity::User,
+    #[description = "Whether you're selling your tickets or buying theirs"] side: TradeSide,
+    #[description = "Number of tickets"] tickets: i32,
+    #[description = "Total price in dollars"] price: f64,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Ticket Trade").description(desc).color(data::EMBED_ERROR),
+    );
+    if !(1..=data::MAX_TICKET_PURCHASE).contains(&tickets) {
+        ctx.send(err_embed(format!("Trade between 1 and {} tickets.", data::MAX_TICKET_PURCHASE))).await?;
+        return Ok(());
+    }
+    if !(0.01..=creds_to_price(data::MAX_DAILY_PAY_CREDS as f64)).contains(&price) {
+        ctx.send(err_embed(format!("Price must be between $0.01 and ${:.2}.", creds_to_price(data::MAX_DAILY_PAY_CREDS as f64)))).await?;
+        return Ok(());
+    }
+    let (me, them) = match counterparty(ctx, &user).await {
+        Ok(pair) => pair,
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+    let creds = price_to_creds(price).round() as i32;
+
+    // The offerer's side goes into escrow until the offer is answered
+    let escrowed = {
+        let mut ud = me.write().await;
+        let blocked = match side {
+            TradeSide::Sell => (ud.get_tickets() < tickets).then(|| format!("You only have **{}** tickets.", ud.get_tickets())),
+            TradeSide::Buy => send_blocked(&ud, creds),
+        };
+        let (escrow_creds, escrow_tickets) = if side == TradeSide::Sell { (0, tickets) } else { (creds, 0) };
+        match blocked {
+            Some(desc) => Err(desc),
+            None if !ud.open_escrow(escrow_creds, escrow_tickets) => Err("You already have an open trade offer.".to_string()),
+            None => Ok(Escrow { creds: escrow_creds, tickets: escrow_tickets }),
+        }
+    };
+    let escrow = match escrowed {
+        Ok(e) => e,
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+
+    let (offer, seller, buyer) = match side {
+        TradeSide::Sell => (format!("<@{}> offers **{tickets}** tickets to <@{}> for **${price:.2}**.", ctx.author().id, user.id), ctx.author().id, user.id),
+        TradeSide::Buy => (format!("<@{}> offers **${price:.2}** to <@{}> for **{tickets}** tickets.", ctx.author().id, user.id), user.id, ctx.author().id),
+    };
+    let offer_embed = serenity::CreateEmbed::new()
+        .title("Ticket Trade")
+        .description(format!("{offer}\nThe offer is held in escrow and expires <t:{}:R>.", Utc::now().timestamp() + TRADE_OFFER_TIMEOUT_SECS as i64))
+        .color(data::EMBED_GOLD)
+        .footer(default_footer());
+
+    // Any Discord error from here on must hand the escrow back, or it stays locked
+    let offered = async {
+        let reply = ctx.send(poise::CreateReply::default()
+            .content(format!("<@{}>", user.id))
+            .embed(offer_embed)
+            .components(vec![serenity::CreateActionRow::Buttons(vec![
+                serenity::CreateButton::new("trade_accept").label("Accept").style(serenity::ButtonStyle::Success),
+                serenity::CreateButton::new("trade_decline").label("Decline").style(serenity::ButtonStyle::Danger),
+                serenity::CreateButton::new("trade_cancel").label("Withdraw Offer").style(serenity::ButtonStyle::Secondary),
+            ])])
+        ).await?;
+        let msg = reply.message().await?.into_owned();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(TRADE_OFFER_TIMEOUT_SECS);
+
+        let outcome = loop {
+            let Some(press) = msg
+                .await_component_interaction(ctx.serenity_context())
+                .timeout(deadline.saturating_duration_since(tokio::time::Instant::now()))
+                .await
+            else {
+                me.write().await.refund_escrow();
+                break (format!("{offer}\nThe offer expired — escrow returned."), data::EMBED_ERROR);
+            };
+            let id = press.data.custom_id.as_str();
+            let presser = press.user.id;
+            let ephemeral = |text: &str| serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new().content(text).ephemeral(true),
+            );
+
+            if presser == ctx.author().id && id == "trade_cancel" {
+                press.defer(ctx.http()).await?;
+                me.write().await.refund_escrow();
+                break (format!("{offer}\nOffer withdrawn — escrow returned."), data::EMBED_ERROR);
+            }
+            if presser != user.id || id == "trade_cancel" {
+                press.create_response(ctx.http(), ephemeral("This offer isn't yours to answer.")).await?;
+                continue;
+            }
+            if id == "trade_decline" {
+                press.defer(ctx.http()).await?;
+                me.write().await.refund_escrow();
+                break (format!("{offer}\n<@{}> declined — escrow returned.", user.id), data::EMBED_ERROR);
+            }
+
+            let settled = {
+                let (mut offerer, taker) = write_pair(ctx.author().id, &me, user.id, &them).await;
+                let blocked = match side {
+                    TradeSide::Sell => send_blocked(&taker, creds),
+                    TradeSide::Buy => (taker.get_tickets() < tickets).then(|| format!("You only have **{}** tickets.", taker.get_tickets()))
+                        // Escrow already holds the creds, but other payments may have used up the limit since
+                        .or_else(|| (i64::from(creds) > daily_pay_left(&offerer)).then(|| format!(
+                            "<@{}> has hit their 24-hour sending limit since making this offer.", ctx.author().id,
+                        ))),
+                };
+                match blocked {
+                    Some(desc) => Err(desc),
+                    None if offerer.escrow != Some(escrow) => Err("This offer is no longer backed by escrow.".to_string()),
+                    None => {
+                        offerer.escrow = None;
+                        let (mut s, mut b) = if seller == ctx.author().id { (offerer, taker) } else { (taker, offerer) };
+                        // The escrowed side already left the offerer's balance
+                        if side == TradeSide::Buy {
+                            s.sub_tickets(tickets);
+                        } else {
+                            b.sub_creds(creds);
+                        }
+                        s.add_creds(creds);
+                        b.add_tickets(tickets);
+                        log_both((&mut b, buyer), (&mut s, seller), LedgerKind::TicketTrade, creds, -tickets, None);
+                        Ok(())
+                    }
+                }
+            };
+            match settled {
+                Err(desc) => {
+                    press.create_response(ctx.http(), ephemeral(&desc)).await?;
+                }
+                Ok(()) => {
+                    press.defer(ctx.http()).await?;
+                    break (format!("{offer}\n✅ Trade complete."), data::EMBED_SUCCESS);
+                }
+            }
+        };
+        Ok::<_, Error>((reply, outcome))
+    }.await;
+    let (reply, outcome) = match offered {
+        Ok(done) => done,
+        Err(e) => {
+            let mut ud = me.write().await;
+            if ud.escrow == Some(escrow) {
+                ud.refund_escrow();
+            }
+            return Err(e);
+        }
+    };
+
+    reply.edit(ctx, poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Ticket Trade").description(outcome.0).color(outcome.1).footer(default_footer()),
+    ).components(vec![])).await?;
+    Ok(())
+}
+
+/// see your recent payments and ticket trades
+#[poise::command(slash_command)]
+pub async fn ledger(ctx: Context<'_>) -> Result<(), Error> {
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let ud = u.read().await;
+    let sent = ud.creds_sent_since(Utc::now() - chrono::Duration::hours(24));
+
+    let mut desc = format!(
+        "**Sent in the last 24h:** ${:.2} of ${:.2}\n﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\n",
+        creds_to_price(sent as f64), creds_to_price(data::MAX_DAILY_PAY_CREDS as f64),
+    );
+    if ud.ledger.is_empty() {
+        desc += "*No payments or trades yet.*";
+    }
+    for e in ud.ledger.iter().rev().take(LEDGER_PAGE) {
+        let (arrow, kind) = match (e.kind, e.creds < 0 || e.tickets > 0) {
+            (LedgerKind::Payment, true) => ("→", "Paid"),
+            (LedgerKind::Payment, false) => ("←", "Received from"),
+            (LedgerKind::TicketTrade, true) => ("→", "Bought tickets from"),
+            (LedgerKind::TicketTrade, false) => ("←", "Sold tickets to"),
+        };
+        let tickets = if e.tickets == 0 { String::new() } else { format!(" | **{:+}** tickets", e.tickets) };
+        let note = e.note.as_deref().map(|n| format!(" — *{n}*")).unwrap_or_default();
+        desc += &format!(
+            "<t:{}:d> {arrow} {kind} <@{}> **{}${:.2}**{tickets}{note}\n",
+            e.timestamp.timestamp(), e.counterparty, if e.creds < 0 { "-" } else { "+" }, creds_to_price(f64::from(e.creds.abs())),
+        );
+    }
+    if let Some(escrow) = ud.escrow {
+        desc += &format!("\n🔒 In escrow: **${:.2}**, **{}** tickets", creds_to_price(f64::from(escrow.creds)), escrow.tickets);
+    }
+
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("Ledger")
+            .description(desc)
+            .color(data::EMBED_GOLD)
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}
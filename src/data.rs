@@ -1,6 +1,6 @@
 //! Shared bot state, user data models, and global constants.
 use crate::serenity;
-use chrono::prelude::{DateTime, Utc};
+use chrono::prelude::{DateTime, Datelike, NaiveDate, Utc};
 use dashmap::DashMap;
 use std::collections::VecDeque;
 use poise::serenity_prelude::RoleId;
@@ -25,12 +25,66 @@ pub struct ProfessorMemory {
 
 /// Minimum level required to unlock Gold Status and the elevated HYSA rate.
 pub const GOLD_LEVEL_THRESHOLD: i32 = 10;
-/// Annual HYSA interest rate (as a fraction) applied to uninvested cash for non-gold users.
+/// Annual HYSA interest rate, in percent, paid on uninvested cash in the lowest tier.
 pub const BASE_HYSA_RATE: f64 = 0.1;
+/// Days of missed accrual back-filled after downtime; older gaps are forfeited.
+pub const MAX_INTEREST_BACKFILL_DAYS: i64 = 62;
+/// HYSA rate tiers used when `HYSA_TIERS` isn't set, lowest level first.
+pub const DEFAULT_HYSA_TIERS: [HysaTier; 3] = [
+    HysaTier { min_level: 0, fed_share: 0.0, floor: BASE_HYSA_RATE },
+    HysaTier { min_level: 5, fed_share: 0.5, floor: 0.25 },
+    HysaTier { min_level: GOLD_LEVEL_THRESHOLD, fed_share: 0.92, floor: 0.5 },
+];
 /// Maximum number of trade history records retained per user before oldest entries are dropped.
 pub const TRADE_HISTORY_LIMIT: usize = 500;
+/// Index fund portfolio performance is measured against.
+pub const BENCHMARK_TICKER: &str = "SPY";
+/// Daily value snapshots retained per portfolio (about five years).
+pub const SNAPSHOT_HISTORY_LIMIT: usize = 1_830;
 /// Maximum number of pending (queued) orders a user may have at once.
 pub const MAX_PENDING_ORDERS: usize = 20;
+/// Maximum number of recurring (DCA) buy plans a user may have at once.
+pub const MAX_RECURRING_PLANS: usize = 5;
+/// Ticker recorded in trade history for cash movements: transfers and interest.
+pub const TRANSFER_CASH_TICKER: &str = "CASH";
+/// Ledger entries kept per user; older ones are dropped.
+pub const LEDGER_LIMIT: usize = 200;
+/// Most creds a member may send to others in any 24 hours ($1,000).
+pub const MAX_DAILY_PAY_CREDS: i64 = 100_000;
+/// Level both sides of a payment or trade must have reached.
+pub const MIN_PAY_LEVEL: i32 = 3;
+/// Age in days both sides' Discord accounts must have reached before paying or trading.
+pub const MIN_PAY_ACCOUNT_AGE_DAYS: i64 = 30;
+/// Members a club portfolio may have, manager included.
+pub const MAX_CLUB_MEMBERS: usize = 20;
+/// What one club unit is worth, in creds, before the club holds anything ($1).
+pub const CLUB_INITIAL_UNIT_CREDS: f64 = 100.0;
+/// Why units can't be issued or redeemed while the club is worth nothing per unit.
+pub const NAV_UNUSABLE_ERR: &str = "The club's value is at or below zero — deposits and redemptions are paused until it recovers.";
+/// Most portfolios that may copy any one leader portfolio.
+pub const MAX_COPY_FOLLOWERS: usize = 25;
+/// Maximum number of rebalance targets per portfolio.
+pub const MAX_REBALANCE_TARGETS: usize = 20;
+/// Percentage points a holding may drift from its target before a rebalance trades it, unless the owner sets their own.
+pub const DEFAULT_DRIFT_BAND_PCT: f64 = 5.0;
+/// Days a lot must be held before its gains count as long-term.
+pub const LONG_TERM_HOLDING_DAYS: i64 = 365;
+/// Extra margin locked on a short stock sale, as a fraction of its value, on top of the proceeds.
+pub const SHORT_INITIAL_MARGIN_RATIO: f64 = 0.50;
+/// Minimum equity, as a fraction of short market value, before a forced buy-in.
+pub const SHORT_MAINTENANCE_MARGIN_RATIO: f64 = 0.30;
+/// Annual stock borrow fee (percent of short market value), charged daily.
+pub const SHORT_BORROW_RATE: f64 = 3.0;
+/// Share of a portfolio one ticker may reach before the risk tab warns, unless the owner sets their own.
+pub const DEFAULT_CONCENTRATION_LIMIT_PCT: f64 = 25.0;
+/// Highest loan-to-value a margin portfolio may borrow against its long holdings.
+pub const MAX_MARGIN_LOAN_RATIO: f64 = 0.50;
+/// Minimum equity, as a fraction of long market value, before a margin call is issued.
+pub const MARGIN_MAINTENANCE_RATIO: f64 = 0.25;
+/// Margin loan rate premium (percentage points) over the fed funds rate.
+pub const MARGIN_RATE_SPREAD: f64 = 2.0;
+/// Hours a margin call can stay uncured before positions are liquidated.
+pub const MARGIN_CALL_GRACE_HOURS: i64 = 24;
 /// Starting cred balance for every newly registered user (100,000 creds = $1,000 notional).
 pub const NEW_USER_STARTING_CREDS: i32 = 100_000;
 
@@ -135,6 +189,13 @@ pub struct UserData {
 
     #[serde(default)]
     recent_rolls: VecDeque<i32>,
+
+    /// Creds and tickets moved to and from other members, newest last.
+    #[serde(default)]
+    pub ledger: VecDeque<LedgerEntry>,
+    /// Creds and tickets held back for an open ticket trade offer.
+    #[serde(default)]
+    pub escrow: Option<Escrow>,
 }
 
 impl UserData {
@@ -220,6 +281,49 @@ impl UserData {
         true
     }
 
+    pub const fn sub_tickets(&mut self, tickets: i32) -> bool {
+        if tickets < 0 {
+            return false;
+        }
+        self.tickets -= tickets;
+        true
+    }
+
+    pub fn push_ledger(&mut self, entry: LedgerEntry) {
+        self.ledger.push_back(entry);
+        if self.ledger.len() > LEDGER_LIMIT {
+            self.ledger.pop_front();
+        }
+    }
+
+    /// Creds sent to other members since `since`, through payments and trades.
+    pub fn creds_sent_since(&self, since: DateTime<Utc>) -> i64 {
+        self.ledger.iter()
+            .filter(|e| e.timestamp >= since && e.creds < 0)
+            .map(|e| -i64::from(e.creds))
+            .sum()
+    }
+
+    /// Moves `creds` and `tickets` out of the balance into escrow. `false`, with nothing
+    /// moved, if either balance is short or an escrow is already open.
+    pub fn open_escrow(&mut self, creds: i32, tickets: i32) -> bool {
+        if self.escrow.is_some() || creds < 0 || tickets < 0 || self.creds < creds || self.tickets < tickets {
+            return false;
+        }
+        self.creds -= creds;
+        self.tickets -= tickets;
+        self.escrow = Some(Escrow { creds, tickets });
+        true
+    }
+
+    /// Returns whatever is in escrow to the balance.
+    pub fn refund_escrow(&mut self) {
+        if let Some(e) = self.escrow.take() {
+            self.creds += e.creds;
+            self.tickets += e.tickets;
+        }
+    }
+
     pub const fn get_creds(&self) -> i32 {
         self.creds
     }
@@ -462,7 +566,13 @@ impl Data {
         let users = Arc::new(DashMap::default());
         for x in users_data.iter() {
             let (id, u) = x.pair();
-            users.insert(*id, Arc::new(RwLock::new(u.clone())));
+            let mut u = u.clone();
+            for port in &mut u.stock.portfolios {
+                port.assign_option_ids();
+            }
+            // Offers don't survive a restart, so whatever they held goes back
+            u.refund_escrow();
+            users.insert(*id, Arc::new(RwLock::new(u)));
         }
 
         let meme = read_lines("reference/meme.txt");
@@ -560,6 +670,10 @@ pub struct StockProfile {
     pub pending_orders: Vec<PendingOrder>,
     #[serde(default)]
     pub next_order_id: u32,
+    #[serde(default)]
+    pub recurring: Vec<RecurringPlan>,
+    #[serde(default)]
+    pub next_recurring_id: u32,
 }
 
 impl StockProfile {
@@ -586,6 +700,35 @@ impl StockProfile {
         self.pending_orders.push(order);
         true
     }
+
+    /// Adds a recurring buy plan, assigning the next plan ID.
+    /// Returns `false` if `MAX_RECURRING_PLANS` is reached.
+    pub fn add_recurring(&mut self, mut plan: RecurringPlan) -> bool {
+        if self.recurring.len() >= MAX_RECURRING_PLANS {
+            return false;
+        }
+        let id = self.next_recurring_id;
+        self.next_recurring_id = id.wrapping_add(1);
+        plan.id = id;
+        self.recurring.push(plan);
+        true
+    }
+}
+
+/// One HYSA rate tier: members at `min_level` or above earn `fed_share` of the fed funds rate,
+/// but never less than `floor` percent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HysaTier {
+    pub min_level: i32,
+    pub fed_share: f64,
+    pub floor: f64,
+}
+
+impl HysaTier {
+    /// Annual rate in percent for the given fed funds rate.
+    pub fn annual_rate(&self, fed_rate: f64) -> f64 {
+        (fed_rate * self.fed_share).max(self.floor)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -595,6 +738,77 @@ pub struct Portfolio {
     pub last_interest_credited: DateTime<Utc>,
     pub positions: Vec<Position>,
     pub created_at: DateTime<Utc>,
+    /// Last time short-stock borrow fees were charged. `None` until the first short is opened.
+    #[serde(default)]
+    pub last_borrow_charged: Option<DateTime<Utc>>,
+    /// Loan-to-value this portfolio may borrow against long holdings. 0 = cash account.
+    #[serde(default)]
+    pub margin_ratio: f64,
+    /// Last time margin interest was charged on a negative cash balance.
+    #[serde(default)]
+    pub last_margin_interest: Option<DateTime<Utc>>,
+    /// When the outstanding margin call was issued, if any.
+    #[serde(default)]
+    pub margin_call_at: Option<DateTime<Utc>>,
+    /// Which tax lots `apply_sell` relieves first.
+    #[serde(default)]
+    pub lot_method: LotMethod,
+    /// Open multi-leg option strategies; their legs are positions tagged with the strategy id.
+    #[serde(default)]
+    pub strategies: Vec<OptionStrategy>,
+    /// When the outstanding naked-option margin call was issued, if any.
+    #[serde(default)]
+    pub option_margin_call_at: Option<DateTime<Utc>>,
+    /// Daily value history, oldest first, one entry per day.
+    #[serde(default)]
+    pub snapshots: Vec<ValueSnapshot>,
+    /// Deposits minus withdrawals since the last snapshot, in creds.
+    #[serde(default)]
+    pub pending_flow: f64,
+    /// Percent of the portfolio above which a single ticker is flagged on the risk tab.
+    #[serde(default = "default_concentration_limit")]
+    pub concentration_limit_pct: f64,
+    /// Target allocation and auto-rebalance schedule.
+    #[serde(default)]
+    pub rebalance: RebalanceSettings,
+    /// Membership and unit accounting when this is a club portfolio shared with other members.
+    #[serde(default)]
+    pub club: Option<Club>,
+    /// Whether the owner lets other members copy this portfolio's trades.
+    #[serde(default)]
+    pub copyable: bool,
+    /// The leader portfolio this one mirrors, if it copy-trades.
+    #[serde(default)]
+    pub copy: Option<CopySettings>,
+    /// How much of this portfolio other members can see with /portfolio_view.
+    #[serde(default)]
+    pub privacy: PortfolioPrivacy,
+    /// HYSA interest accrued since the last monthly posting, in creds. Not yet spendable.
+    #[serde(default)]
+    pub accrued_interest: f64,
+    /// Whether interest accrues daily. Portfolios saved under the old monthly credit load as
+    /// `false` and are migrated on their next accrual.
+    #[serde(default)]
+    pub daily_interest: bool,
+}
+
+const fn default_concentration_limit() -> f64 {
+    DEFAULT_CONCENTRATION_LIMIT_PCT
+}
+
+/// A portfolio's value at the end of one day, in creds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ValueSnapshot {
+    pub date: NaiveDate,
+    pub total: f64,
+    pub cash: f64,
+    pub positions: f64,
+    /// Deposits minus withdrawals since the previous snapshot, so returns can exclude them.
+    #[serde(default)]
+    pub net_flow: f64,
+    /// `BENCHMARK_TICKER` price in USD when the snapshot was taken; 0 if it couldn't be fetched.
+    #[serde(default)]
+    pub benchmark: f64,
 }
 
 impl Portfolio {
@@ -605,26 +819,241 @@ impl Portfolio {
             last_interest_credited: Utc::now(),
             positions: Vec::new(),
             created_at: Utc::now(),
+            last_borrow_charged: None,
+            margin_ratio: 0.0,
+            last_margin_interest: None,
+            margin_call_at: None,
+            lot_method: LotMethod::default(),
+            strategies: Vec::new(),
+            option_margin_call_at: None,
+            snapshots: Vec::new(),
+            pending_flow: 0.0,
+            concentration_limit_pct: DEFAULT_CONCENTRATION_LIMIT_PCT,
+            rebalance: RebalanceSettings::default(),
+            club: None,
+            copyable: false,
+            copy: None,
+            privacy: PortfolioPrivacy::default(),
+            accrued_interest: 0.0,
+            daily_interest: true,
+        }
+    }
+
+    /// One-time switch from the old monthly credit, which paid the previous month on the 1st:
+    /// pays this month's credit if it was missed, then daily accrual carries on from that 1st.
+    /// Returns the posting, if any.
+    fn migrate_monthly_interest(&mut self, now: DateTime<Utc>, annual_rate: f64) -> Option<(NaiveDate, f64)> {
+        if std::mem::replace(&mut self.daily_interest, true) {
+            return None;
+        }
+        let first = now.date_naive().with_day(1)?;
+        if self.last_interest_credited.date_naive() >= first {
+            return None;
         }
+        self.last_interest_credited = first.and_time(chrono::NaiveTime::MIN).and_utc();
+        (self.cash > 0.0).then(|| {
+            let interest = annual_rate / 100.0 / 12.0 * self.cash;
+            self.cash += interest;
+            (first, interest)
+        })
+    }
+
+    /// Accrues daily HYSA interest for every whole day since `last_interest_credited`, up to
+    /// `MAX_INTEREST_BACKFILL_DAYS`. Interest compounds daily and is posted to cash on the 1st
+    /// of each month crossed. Returns each posting's date and amount in creds.
+    pub fn accrue_interest(&mut self, now: DateTime<Utc>, annual_rate: f64) -> Vec<(NaiveDate, f64)> {
+        let mut postings: Vec<_> = self.migrate_monthly_interest(now, annual_rate).into_iter().collect();
+        let today = now.date_naive();
+        let last = self.last_interest_credited.date_naive();
+        let days = (today - last).num_days();
+        if days <= 0 {
+            return postings;
+        }
+        let daily_rate = annual_rate / 100.0 / 365.0;
+        let start = (days - MAX_INTEREST_BACKFILL_DAYS).max(0);
+        for offset in start + 1..=days {
+            let date = last + chrono::Duration::days(offset);
+            if date.day() == 1 && self.accrued_interest > 0.0 {
+                let posted = std::mem::take(&mut self.accrued_interest);
+                self.cash += posted;
+                postings.push((date, posted));
+            }
+            if self.cash > 0.0 {
+                self.accrued_interest += (self.cash + self.accrued_interest) * daily_rate;
+            }
+        }
+        self.last_interest_credited = now;
+        postings
+    }
+
+    /// Records today's value, folding in the cash flows since the last snapshot. A second
+    /// snapshot on the same day replaces the first's values and keeps both days' flows.
+    pub fn record_snapshot(&mut self, date: NaiveDate, total: f64, benchmark: f64) {
+        let net_flow = std::mem::take(&mut self.pending_flow);
+        let snapshot = ValueSnapshot { date, total, cash: self.cash, positions: total - self.cash, net_flow, benchmark };
+        match self.snapshots.last_mut() {
+            Some(last) if last.date == date => *last = ValueSnapshot { net_flow: last.net_flow + net_flow, ..snapshot },
+            _ => self.snapshots.push(snapshot),
+        }
+        if self.snapshots.len() > SNAPSHOT_HISTORY_LIMIT {
+            self.snapshots.remove(0);
+        }
+    }
+
+    /// Next free strategy id in this portfolio.
+    pub fn next_strategy_id(&self) -> u32 {
+        self.strategies.iter().map(|s| s.id).max().map_or(1, |id| id + 1)
+    }
+
+    /// Adds an option position under a fresh contract id and returns the id.
+    pub fn push_option(&mut self, ticker: &str, mut contract: OptionContract, quantity: f64, avg_cost: f64) -> String {
+        contract.id = self.unique_option_id(contract.occ_symbol(ticker));
+        let id = contract.id.clone();
+        self.positions.push(Position {
+            ticker: ticker.to_string(),
+            asset_type: AssetType::Option(contract),
+            quantity,
+            avg_cost,
+            lots: Vec::new(),
+        });
+        id
+    }
+
+    /// `base` if no option position uses it yet, otherwise the first free `base-N`.
+    fn unique_option_id(&self, base: String) -> String {
+        let taken = |id: &str| self.positions.iter().any(|p| matches!(&p.asset_type, AssetType::Option(c) if c.id == id));
+        if !taken(&base) {
+            return base;
+        }
+        (2..).map(|n| format!("{base}-{n}")).find(|id| !taken(id)).unwrap_or(base)
+    }
+
+    /// Index of the option position with contract id `id` (case-insensitive).
+    pub fn option_idx(&self, id: &str) -> Option<usize> {
+        self.positions.iter().position(|p| matches!(&p.asset_type, AssetType::Option(c) if c.id.eq_ignore_ascii_case(id)))
+    }
+
+    /// Gives option positions saved before contract ids existed one of their own.
+    pub fn assign_option_ids(&mut self) {
+        for i in 0..self.positions.len() {
+            let base = match &self.positions[i].asset_type {
+                AssetType::Option(c) if c.id.is_empty() => c.occ_symbol(&self.positions[i].ticker),
+                _ => continue,
+            };
+            let id = self.unique_option_id(base);
+            if let AssetType::Option(c) = &mut self.positions[i].asset_type {
+                c.id = id;
+            }
+        }
+    }
+
+    /// Positions that are legs of strategy `id`.
+    pub fn strategy_legs(&self, id: u32) -> impl Iterator<Item = &Position> {
+        self.positions.iter().filter(move |p| matches!(&p.asset_type, AssetType::Option(c) if c.strategy == Some(id)))
+    }
+
+    /// Drops strategy records whose legs have all been closed or expired.
+    pub fn prune_strategies(&mut self) {
+        let positions = &self.positions;
+        self.strategies.retain(|s| positions.iter().any(|p| matches!(&p.asset_type, AssetType::Option(c) if c.strategy == Some(s.id))));
     }
 
-    /// Sum of collateral locked across all naked short option positions.
+    /// Sum of collateral locked across naked short options plus short stock proceeds and margin.
     pub fn locked_cash(&self) -> f64 {
         self.positions.iter().filter_map(|p| {
             if let AssetType::Option(c) = &p.asset_type {
                 if c.side == OptionSide::Short { return Some(c.collateral); }
+            } else if p.is_short_stock() {
+                return Some(-p.quantity * p.avg_cost * (1.0 + SHORT_INITIAL_MARGIN_RATIO));
             }
             None
         }).sum()
     }
 }
 
+/// A holding. Short stock positions carry a negative `quantity` and their
+/// `avg_cost` is the average short-sale price.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub ticker: String,
     pub asset_type: AssetType,
     pub quantity: f64,
     pub avg_cost: f64,
+    /// Individual purchase lots of a long stock position; `avg_cost` is their weighted average.
+    /// Empty for options, shorts, and positions opened before lots were tracked.
+    #[serde(default)]
+    pub lots: Vec<TaxLot>,
+}
+
+impl Position {
+    pub const fn is_long_stock(&self) -> bool {
+        !matches!(self.asset_type, AssetType::Option(_)) && self.quantity > 0.0
+    }
+
+    pub const fn is_short_stock(&self) -> bool {
+        !matches!(self.asset_type, AssetType::Option(_)) && self.quantity < 0.0
+    }
+
+    /// Backfills a single lot for positions opened before lots were tracked, dated `acquired`.
+    pub fn ensure_lots(&mut self, acquired: DateTime<Utc>) {
+        let tracked: f64 = self.lots.iter().map(|l| l.quantity).sum();
+        let untracked = self.quantity - tracked;
+        if untracked > 1e-9 {
+            let id = self.next_lot_id();
+            self.lots.push(TaxLot { id, acquired, quantity: untracked, cost: self.avg_cost });
+        }
+    }
+
+    pub fn next_lot_id(&self) -> u32 {
+        self.lots.iter().map(|l| l.id).max().map_or(1, |id| id + 1)
+    }
+}
+
+/// One purchase of a long position, kept separate so sales can relieve specific lots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxLot {
+    pub id: u32,
+    pub acquired: DateTime<Utc>,
+    pub quantity: f64,
+    /// Cost per unit in creds.
+    pub cost: f64,
+}
+
+/// Order in which lots are relieved on a sale.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LotMethod {
+    #[default]
+    Fifo,
+    Lifo,
+    /// Highest cost first — minimizes realized gains.
+    Hifo,
+}
+
+impl LotMethod {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Fifo => "FIFO",
+            Self::Lifo => "LIFO",
+            Self::Hifo => "Highest cost",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HoldingPeriod {
+    ShortTerm,
+    LongTerm,
+}
+
+/// The part of a lot relieved by one sale and the P&L it realized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LotRelief {
+    pub lot_id: u32,
+    pub acquired: DateTime<Utc>,
+    pub quantity: f64,
+    pub cost: f64,
+    pub pnl: f64,
+    pub holding_period: HoldingPeriod,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -655,8 +1084,135 @@ pub struct OptionContract {
     #[serde(default = "default_long")]
     pub side: OptionSide,
     /// Total creds locked as margin collateral for naked short positions. 0 for covered/cash-secured.
+    /// For a strategy, the whole combo's margin sits on its first short leg.
     #[serde(default)]
     pub collateral: f64,
+    /// Id of the `OptionStrategy` this contract is a leg of. Legs never merge with standalone positions.
+    #[serde(default)]
+    pub strategy: Option<u32>,
+    /// Ex-dividend date this short call was last rolled for early assignment, so each date rolls once.
+    #[serde(default)]
+    pub assignment_checked: Option<NaiveDate>,
+    /// How an in-the-money short settles at expiry. Physical is only offered on covered calls and cash-secured puts.
+    #[serde(default)]
+    pub settlement: SettlementMode,
+    /// American contracts can be exercised any day; European only settle at expiry.
+    #[serde(default)]
+    pub style: ExerciseStyle,
+    /// Stable id the close/cover/exercise commands take: the OCC symbol, suffixed `-2`, `-3`, …
+    /// when the portfolio holds the same contract more than once. Unique within its portfolio.
+    #[serde(default)]
+    pub id: String,
+}
+
+impl OptionContract {
+    /// OCC-style symbol: root, `YYMMDD` expiry, `C`/`P`, strike × 1000 in eight digits (`AAPL260116C00150000`).
+    pub fn occ_symbol(&self, ticker: &str) -> String {
+        let cp = match self.option_type {
+            OptionType::Call => 'C',
+            OptionType::Put => 'P',
+        };
+        format!("{ticker}{}{cp}{:08}", self.expiry.format("%y%m%d"), (self.strike * 1000.0).round() as u64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum SettlementMode {
+    /// Pay or receive the intrinsic value in cash.
+    #[default]
+    #[name = "Cash"]
+    Cash,
+    /// Deliver or receive the underlying shares at the strike.
+    #[name = "Physical (shares)"]
+    Physical,
+}
+
+impl SettlementMode {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Cash => "cash-settled",
+            Self::Physical => "physical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum ExerciseStyle {
+    /// Exercisable on any day up to expiry; shorts can be assigned early.
+    #[default]
+    #[name = "American"]
+    American,
+    /// Exercisable only at expiry. Listed on index ETFs, always cash-settled.
+    #[name = "European (index ETFs)"]
+    European,
+}
+
+impl ExerciseStyle {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::American => "American",
+            Self::European => "European",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum StrategyKind {
+    #[name = "Bull call spread (debit)"]
+    CallDebitSpread,
+    #[name = "Bear put spread (debit)"]
+    PutDebitSpread,
+    #[name = "Bear call spread (credit)"]
+    CallCreditSpread,
+    #[name = "Bull put spread (credit)"]
+    PutCreditSpread,
+    #[name = "Long straddle"]
+    LongStraddle,
+    #[name = "Short straddle"]
+    ShortStraddle,
+    #[name = "Long strangle"]
+    LongStrangle,
+    #[name = "Short strangle"]
+    ShortStrangle,
+    #[name = "Iron condor"]
+    IronCondor,
+    #[name = "Call calendar"]
+    CallCalendar,
+    #[name = "Put calendar"]
+    PutCalendar,
+}
+
+impl StrategyKind {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::CallDebitSpread => "Bull Call Spread",
+            Self::PutDebitSpread => "Bear Put Spread",
+            Self::CallCreditSpread => "Bear Call Spread",
+            Self::PutCreditSpread => "Bull Put Spread",
+            Self::LongStraddle => "Long Straddle",
+            Self::ShortStraddle => "Short Straddle",
+            Self::LongStrangle => "Long Strangle",
+            Self::ShortStrangle => "Short Strangle",
+            Self::IronCondor => "Iron Condor",
+            Self::CallCalendar => "Call Calendar",
+            Self::PutCalendar => "Put Calendar",
+        }
+    }
+}
+
+/// A group of option legs opened together as one strategy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionStrategy {
+    pub id: u32,
+    pub kind: StrategyKind,
+    pub ticker: String,
+    pub contracts: u32,
+    pub opened_at: DateTime<Utc>,
+    /// Net premium in creds at open: positive = debit paid, negative = credit received.
+    pub net_premium: f64,
+    /// Best and worst outcome at expiry in creds, net of premium. `None` = unlimited or path-dependent.
+    pub max_profit: Option<f64>,
+    pub max_loss: Option<f64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
@@ -676,15 +1232,59 @@ pub struct TradeRecord {
     pub total_creds: f64,
     pub realized_pnl: Option<f64>,
     pub timestamp: DateTime<Utc>,
+    /// Execution costs charged on top of `total_creds` (buys) or deducted from it (sells).
+    /// `realized_pnl` is gross of these; net P&L subtracts them.
+    #[serde(default)]
+    pub fees: TradeFees,
+    /// Lots relieved by a sale, with per-lot P&L and holding period. Empty for other actions.
+    #[serde(default)]
+    pub lots: Vec<LotRelief>,
+    /// An option trade, or a share leg of an option exercise or assignment.
+    #[serde(default)]
+    pub option_leg: bool,
+}
+
+impl TradeRecord {
+    /// Realized P&L split into (short-term, long-term) from the relieved lots.
+    pub fn pnl_by_term(&self) -> (f64, f64) {
+        self.lots.iter().fold((0.0, 0.0), |(st, lt), l| match l.holding_period {
+            HoldingPeriod::ShortTerm => (st + l.pnl, lt),
+            HoldingPeriod::LongTerm  => (st, lt + l.pnl),
+        })
+    }
+}
+
+/// Simulated execution costs of a single fill, all in creds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradeFees {
+    pub commission: f64,
+    pub spread: f64,
+    pub slippage: f64,
+}
+
+impl TradeFees {
+    pub fn total(&self) -> f64 {
+        self.commission + self.spread + self.slippage
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TradeAction {
     Buy,
     Sell,
+    /// Sell-to-open of borrowed stock.
+    Short,
+    /// Buy-to-close of a short stock position.
+    Cover,
+    /// Cash or holdings moved in from another of the user's portfolios, at cost basis.
+    TransferIn,
+    /// Cash or holdings moved out to another of the user's portfolios, at cost basis.
+    TransferOut,
+    /// Monthly HYSA interest posted to cash.
+    Interest,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
 pub enum OrderSide {
     Buy,
     Sell,
@@ -705,6 +1305,90 @@ mod tests {
         assert_eq!(u.get_creds(), 500); // unchanged
     }
 
+    #[test]
+    fn escrow_holds_and_refunds_balance() {
+        let mut u = UserData::default();
+        u.add_creds(1_000);
+        u.add_tickets(5);
+        assert!(!u.open_escrow(2_000, 0));
+        assert!(u.open_escrow(400, 3));
+        assert_eq!((u.get_creds(), u.get_tickets()), (600, 2));
+        // Only one offer at a time
+        assert!(!u.open_escrow(1, 0));
+        u.refund_escrow();
+        assert_eq!((u.get_creds(), u.get_tickets()), (1_000, 5));
+        assert!(u.escrow.is_none());
+    }
+
+    #[test]
+    fn creds_sent_counts_only_recent_outflows() {
+        let mut u = UserData::default();
+        let now = Utc::now();
+        let entry = |hours_ago: i64, creds: i32| LedgerEntry {
+            timestamp: now - chrono::Duration::hours(hours_ago),
+            kind: LedgerKind::Payment,
+            counterparty: serenity::UserId::new(1),
+            creds,
+            tickets: 0,
+            note: None,
+        };
+        u.push_ledger(entry(30, -5_000));
+        u.push_ledger(entry(2, -1_000));
+        u.push_ledger(entry(1, 700));
+        u.push_ledger(entry(0, -250));
+        assert_eq!(u.creds_sent_since(now - chrono::Duration::hours(24)), 1_250);
+    }
+
+    // ── Club ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn club_units_track_nav_and_member_pnl() {
+        let (a, b) = (serenity::UserId::new(1), serenity::UserId::new(2));
+        let mut club = Club::new(a);
+        club.add_member(b).unwrap();
+        assert!(club.add_member(b).is_err());
+
+        // A puts in $100 at the opening $1 NAV, the club doubles, then B buys in at $2
+        assert!((club.issue(a, 10_000.0, club.nav(0.0)).unwrap() - 100.0).abs() < 1e-9);
+        let nav = club.nav(20_000.0);
+        assert!((nav - 200.0).abs() < 1e-9);
+        assert!((club.issue(b, 10_000.0, nav).unwrap() - 50.0).abs() < 1e-9);
+        assert!((club.member(a).unwrap().pnl(nav) - 10_000.0).abs() < 1e-9);
+        assert!(club.member(b).unwrap().pnl(nav).abs() < 1e-9);
+
+        assert!(club.redeem(b, 60.0, nav).is_err());
+        assert!((club.redeem(b, 50.0, nav).unwrap() - 10_000.0).abs() < 1e-9);
+        assert!(club.remove_member(a).is_err());
+        club.remove_member(b).unwrap();
+    }
+
+    #[test]
+    fn club_units_need_a_positive_nav() {
+        let a = serenity::UserId::new(1);
+        let mut club = Club::new(a);
+        club.issue(a, 10_000.0, CLUB_INITIAL_UNIT_CREDS).unwrap();
+        // A club wiped out by a short squeeze can't issue or cancel units
+        for nav in [club.nav(-500.0), 0.0, f64::INFINITY, f64::NAN] {
+            assert!(club.issue(a, 10_000.0, nav).is_err());
+            assert!(club.redeem(a, 10.0, nav).is_err());
+        }
+        assert!((club.member(a).unwrap().units - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn club_votes_are_weighted_by_units() {
+        let (a, b, c) = (serenity::UserId::new(1), serenity::UserId::new(2), serenity::UserId::new(3));
+        let mut club = Club::new(a);
+        club.add_member(b).unwrap();
+        club.add_member(c).unwrap();
+        for (id, creds) in [(a, 5_000.0), (b, 3_000.0), (c, 2_000.0)] {
+            club.issue(id, creds, CLUB_INITIAL_UNIT_CREDS).unwrap();
+        }
+        assert_eq!(club.tally(&[b, c], &[]), None); // exactly half isn't a majority
+        assert_eq!(club.tally(&[a, c], &[b]), Some(true));
+        assert_eq!(club.tally(&[b], &[a]), Some(false));
+    }
+
     #[test]
     fn sub_creds_normal_and_negative_guard() {
         let mut u = UserData::default();
@@ -836,9 +1520,15 @@ mod tests {
                 contracts: 1,
                 side: OptionSide::Short,
                 collateral,
+                strategy: None,
+                assignment_checked: None,
+                settlement: SettlementMode::Cash,
+                style: ExerciseStyle::American,
+                id: String::new(),
             }),
             quantity: 1.0,
             avg_cost: 0.0,
+            lots: Vec::new(),
         }
     }
 
@@ -852,9 +1542,15 @@ mod tests {
                 contracts: 1,
                 side: OptionSide::Long,
                 collateral,
+                strategy: None,
+                assignment_checked: None,
+                settlement: SettlementMode::Cash,
+                style: ExerciseStyle::American,
+                id: String::new(),
             }),
             quantity: 1.0,
             avg_cost: 0.0,
+            lots: Vec::new(),
         }
     }
 
@@ -864,6 +1560,7 @@ mod tests {
             asset_type: AssetType::Stock,
             quantity: 10.0,
             avg_cost: 500.0,
+            lots: Vec::new(),
         }
     }
 
@@ -883,6 +1580,113 @@ mod tests {
         assert_eq!(port.locked_cash(), 0.0);
     }
 
+    #[test]
+    fn option_ids_are_occ_symbols_unique_per_portfolio() {
+        let mut port = Portfolio::new("test".to_string());
+        port.positions.push(make_short_option(0.0));
+        port.positions.push(make_short_option(0.0));
+        port.assign_option_ids();
+        let base = format!("TEST{}C00100000", Utc::now().format("%y%m%d"));
+        let AssetType::Option(a) = &port.positions[0].asset_type else { panic!() };
+        let AssetType::Option(b) = &port.positions[1].asset_type else { panic!() };
+        assert_eq!(a.id, base);
+        assert_eq!(b.id, format!("{base}-2"));
+
+        // Ids stick once assigned; a new duplicate takes the next free suffix
+        port.positions.remove(0);
+        let AssetType::Option(c) = make_short_option(0.0).asset_type else { panic!() };
+        assert_eq!(port.push_option("TEST", c, 1.0, 0.0), base);
+        assert_eq!(port.option_idx(&base.to_lowercase()), Some(1));
+        assert_eq!(port.option_idx(&format!("{base}-2")), Some(0));
+    }
+
+    #[test]
+    fn record_snapshot_keeps_one_per_day_and_carries_flows() {
+        let mut port = Portfolio::new("test".to_string());
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        port.cash = 1_000.0;
+        port.pending_flow = 1_000.0;
+        port.record_snapshot(day, 1_000.0, 500.0);
+        port.pending_flow = 200.0;
+        port.record_snapshot(day, 1_250.0, 505.0);
+        assert_eq!(port.snapshots.len(), 1);
+        assert_eq!(port.snapshots[0].total, 1_250.0);
+        assert_eq!(port.snapshots[0].net_flow, 1_200.0);
+        assert_eq!(port.pending_flow, 0.0);
+
+        port.record_snapshot(day.succ_opt().unwrap(), 1_300.0, 510.0);
+        assert_eq!(port.snapshots.len(), 2);
+        assert_eq!(port.snapshots[1].net_flow, 0.0);
+        assert_eq!(port.snapshots[1].positions, 300.0);
+    }
+
+    #[test]
+    fn accrue_interest_posts_each_missed_month() {
+        let at = |m, d| NaiveDate::from_ymd_opt(2026, m, d).unwrap().and_hms_opt(12, 0, 0).unwrap().and_utc();
+        let mut port = Portfolio::new("test".to_string());
+        port.cash = 36_500.0;
+        port.last_interest_credited = at(1, 20);
+
+        // 1% a year on 36,500 creds is about 1 cred a day
+        let postings = port.accrue_interest(at(3, 3), 1.0);
+        assert_eq!(postings.len(), 2);
+        assert_eq!(postings[0].0, NaiveDate::from_ymd_opt(2026, 2, 1).unwrap());
+        assert!((postings[0].1 - 11.0).abs() < 0.01);
+        assert_eq!(postings[1].0, NaiveDate::from_ymd_opt(2026, 3, 1).unwrap());
+        assert!((postings[1].1 - 28.0).abs() < 0.05);
+        assert!((port.accrued_interest - 3.0).abs() < 0.01);
+        assert!((port.cash - 36_539.0).abs() < 0.1);
+        assert!(port.accrue_interest(at(3, 3), 1.0).is_empty());
+    }
+
+    #[test]
+    fn accrue_interest_caps_backfill_and_skips_negative_cash() {
+        let now = NaiveDate::from_ymd_opt(2026, 6, 15).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let mut port = Portfolio::new("test".to_string());
+        port.cash = 36_500.0;
+        port.last_interest_credited = now - chrono::Duration::days(400);
+        let posted: f64 = port.accrue_interest(now, 1.0).iter().map(|(_, c)| c).sum();
+        let total = posted + port.accrued_interest;
+        assert!((total - MAX_INTEREST_BACKFILL_DAYS as f64).abs() < 0.1);
+
+        let mut margin = Portfolio::new("margin".to_string());
+        margin.cash = -1_000.0;
+        margin.last_interest_credited = now - chrono::Duration::days(10);
+        assert!(margin.accrue_interest(now, 5.0).is_empty());
+        assert_eq!(margin.accrued_interest, 0.0);
+        assert_eq!(margin.last_interest_credited, now);
+    }
+
+    #[test]
+    fn accrue_interest_migrates_monthly_credit() {
+        let at = |m, d| NaiveDate::from_ymd_opt(2026, m, d).unwrap().and_hms_opt(12, 0, 0).unwrap().and_utc();
+        // A portfolio saved before daily accrual, without the flag
+        let legacy = |credited| {
+            let mut saved = serde_json::to_value(Portfolio::new("test".to_string())).unwrap();
+            saved.as_object_mut().unwrap().remove("daily_interest");
+            let mut port: Portfolio = serde_json::from_value(saved).unwrap();
+            assert!(!port.daily_interest);
+            port.cash = 36_500.0;
+            port.last_interest_credited = credited;
+            port
+        };
+
+        // Credited on the 1st: only the days since accrue
+        let mut port = legacy(at(10, 1));
+        assert!(port.accrue_interest(at(10, 6), 1.0).is_empty());
+        assert!((port.accrued_interest - 5.0).abs() < 0.01);
+        assert!(port.daily_interest);
+
+        // The 1st's credit was missed: it's paid, then accrual runs from the 1st
+        let mut port = legacy(at(9, 1));
+        let postings = port.accrue_interest(at(10, 6), 1.0);
+        assert_eq!(postings.len(), 1);
+        assert_eq!(postings[0].0, NaiveDate::from_ymd_opt(2026, 10, 1).unwrap());
+        assert!((postings[0].1 - 36_500.0 * 0.01 / 12.0).abs() < 1e-9);
+        assert!((port.accrued_interest - 5.0).abs() < 0.01);
+        assert!(port.accrue_interest(at(10, 6), 1.0).is_empty());
+    }
+
     // ── StockProfile ──────────────────────────────────────────────────────
 
     #[test]
@@ -899,12 +1703,75 @@ mod tests {
                 total_creds: 100.0,
                 realized_pnl: None,
                 timestamp: Utc::now(),
+                fees: TradeFees::default(),
+                lots: Vec::new(),
+                option_leg: false,
             });
         }
         assert_eq!(sp.trade_history.len(), TRADE_HISTORY_LIMIT);
         // oldest entry (T0) should have been dropped
         assert_eq!(sp.trade_history.front().unwrap().ticker, "T1");
     }
+
+    #[test]
+    fn add_recurring_assigns_ids_and_enforces_limit() {
+        let mut sp = StockProfile::default();
+        let plan = RecurringPlan {
+            id: 0,
+            ticker: "SPY".to_string(),
+            asset_name: "SPDR S&P 500".to_string(),
+            asset_type: AssetType::ETF,
+            portfolio_name: "p".to_string(),
+            amount_usd: 50.0,
+            frequency: RecurringFrequency::Weekly,
+            next_run: Utc::now(),
+            paused: false,
+            skipped: 0,
+        };
+        for _ in 0..MAX_RECURRING_PLANS {
+            assert!(sp.add_recurring(plan.clone()));
+        }
+        assert!(!sp.add_recurring(plan));
+        assert_eq!(sp.recurring.iter().map(|p| p.id).collect::<Vec<_>>(), (0..MAX_RECURRING_PLANS as u32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rebalance_targets_stay_within_100_percent() {
+        let ticker = |s: &str| TargetKey::Ticker { symbol: s.to_string(), asset_name: s.to_string(), asset_type: AssetType::Stock };
+        let mut settings = RebalanceSettings::default();
+        assert!(settings.set_target(ticker("AAPL"), 60.0).is_ok());
+        assert!(settings.set_target(TargetKey::Class(AssetClass::Etfs), 30.0).is_ok());
+        assert!(settings.set_target(ticker("MSFT"), 20.0).is_err());
+        // Replacing a target only counts its new weight
+        assert!(settings.set_target(ticker("AAPL"), 70.0).is_ok());
+        assert_eq!(settings.targets.len(), 2);
+        assert!((settings.total_weight_pct() - 100.0).abs() < 1e-9);
+        assert!(settings.set_target(ticker("AAPL"), 0.0).is_ok());
+        assert!(settings.set_target(ticker("AAPL"), 0.0).is_err());
+        assert_eq!(settings.targets.len(), 1);
+    }
+
+    #[test]
+    fn rebalance_schedule_skips_missed_runs() {
+        use chrono::TimeZone;
+        let jan31 = Utc.with_ymd_and_hms(2026, 1, 31, 15, 0, 0).unwrap();
+        assert_eq!(RebalanceSchedule::Off.next_after(jan31, jan31), None);
+        assert_eq!(RebalanceSchedule::Quarterly.next_after(jan31, jan31), Some(Utc.with_ymd_and_hms(2026, 4, 30, 15, 0, 0).unwrap()));
+        let later = jan31 + chrono::Duration::days(20);
+        assert_eq!(RebalanceSchedule::Weekly.next_after(jan31, later), Some(jan31 + chrono::Duration::weeks(3)));
+    }
+
+    #[test]
+    fn recurring_next_after_skips_missed_runs() {
+        use chrono::TimeZone;
+        let jan31 = Utc.with_ymd_and_hms(2026, 1, 31, 15, 0, 0).unwrap();
+        // Monthly clamps to the end of February
+        assert_eq!(RecurringFrequency::Monthly.step(jan31), Utc.with_ymd_and_hms(2026, 2, 28, 15, 0, 0).unwrap());
+        // Three missed weekly runs collapse into the next future slot
+        let now = jan31 + chrono::Duration::days(22);
+        assert_eq!(RecurringFrequency::Weekly.next_after(jan31, now), jan31 + chrono::Duration::weeks(4));
+        assert_eq!(RecurringFrequency::Daily.next_after(jan31, jan31), jan31 + chrono::Duration::days(1));
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -920,3 +1787,374 @@ pub struct PendingOrder {
     pub limit_price: Option<f64>,
     pub expiry: DateTime<Utc>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum RecurringFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl RecurringFrequency {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    /// One period after `from`. Monthly plans clamp to the last day of shorter months.
+    pub fn step(self, from: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::Daily => from + chrono::Duration::days(1),
+            Self::Weekly => from + chrono::Duration::weeks(1),
+            Self::Monthly => from.checked_add_months(chrono::Months::new(1)).unwrap_or(from + chrono::Duration::days(30)),
+        }
+    }
+
+    /// First scheduled run strictly after `now`, stepping from `from`, so missed runs are skipped.
+    pub fn next_after(self, from: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut next = self.step(from);
+        while next <= now {
+            next = self.step(next);
+        }
+        next
+    }
+}
+
+/// A dollar-cost-averaging plan: buy `amount_usd` of `ticker` in `portfolio_name` every period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringPlan {
+    pub id: u32,
+    pub ticker: String,
+    pub asset_name: String,
+    pub asset_type: AssetType,
+    pub portfolio_name: String,
+    pub amount_usd: f64,
+    pub frequency: RecurringFrequency,
+    pub next_run: DateTime<Utc>,
+    pub paused: bool,
+    /// Runs skipped in a row for lack of cash; reset on each successful buy.
+    #[serde(default)]
+    pub skipped: u32,
+}
+
+/// Holdings a class-wide rebalance target covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum AssetClass {
+    Stocks,
+    #[name = "ETFs"]
+    Etfs,
+    Crypto,
+}
+
+impl AssetClass {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Stocks => "Stocks",
+            Self::Etfs => "ETFs",
+            Self::Crypto => "Crypto",
+        }
+    }
+
+    /// The class of a stock, ETF or crypto holding; `None` for options.
+    pub const fn of(asset_type: &AssetType) -> Option<Self> {
+        match asset_type {
+            AssetType::Stock => Some(Self::Stocks),
+            AssetType::ETF => Some(Self::Etfs),
+            AssetType::Crypto => Some(Self::Crypto),
+            AssetType::Option(_) => None,
+        }
+    }
+}
+
+/// What a rebalance target weighs: one ticker, or every holding of a class without its own target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TargetKey {
+    Ticker { symbol: String, asset_name: String, asset_type: AssetType },
+    Class(AssetClass),
+}
+
+impl TargetKey {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Ticker { symbol, .. } => symbol.clone(),
+            Self::Class(class) => format!("All {}", class.label()),
+        }
+    }
+
+    pub fn same_as(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Ticker { symbol: a, .. }, Self::Ticker { symbol: b, .. }) => a == b,
+            (Self::Class(a), Self::Class(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationTarget {
+    pub key: TargetKey,
+    pub weight_pct: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum RebalanceSchedule {
+    Off,
+    Weekly,
+    Monthly,
+    Quarterly,
+}
+
+impl RebalanceSchedule {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Quarterly => "quarterly",
+        }
+    }
+
+    /// First run strictly after `now`, stepping from `from`; `None` when auto-rebalance is off.
+    pub fn next_after(self, from: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let step: fn(DateTime<Utc>) -> DateTime<Utc> = match self {
+            Self::Off => return None,
+            Self::Weekly => |t| RecurringFrequency::Weekly.step(t),
+            Self::Monthly => |t| RecurringFrequency::Monthly.step(t),
+            Self::Quarterly => |t| t.checked_add_months(chrono::Months::new(3)).unwrap_or(t + chrono::Duration::days(91)),
+        };
+        let mut next = step(from);
+        while next <= now {
+            next = step(next);
+        }
+        Some(next)
+    }
+}
+
+/// A portfolio's target weights, the drift it tolerates, and when it rebalances on its own.
+/// Weights that sum below 100% leave the remainder in cash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebalanceSettings {
+    pub targets: Vec<AllocationTarget>,
+    pub drift_band_pct: f64,
+    pub schedule: RebalanceSchedule,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+impl Default for RebalanceSettings {
+    fn default() -> Self {
+        Self { targets: Vec::new(), drift_band_pct: DEFAULT_DRIFT_BAND_PCT, schedule: RebalanceSchedule::Off, next_run: None }
+    }
+}
+
+impl RebalanceSettings {
+    /// Sum of all target weights, in percent.
+    pub fn total_weight_pct(&self) -> f64 {
+        self.targets.iter().map(|t| t.weight_pct).sum()
+    }
+
+    /// Sets, replaces, or (at 0%) removes the target for `key`, keeping the total at or under 100%.
+    pub fn set_target(&mut self, key: TargetKey, weight_pct: f64) -> Result<(), String> {
+        if !(0.0..=100.0).contains(&weight_pct) {
+            return Err("Target weight must be between 0% and 100%.".to_string());
+        }
+        let existing = self.targets.iter().position(|t| t.key.same_as(&key));
+        if weight_pct == 0.0 {
+            return existing.map(|i| { self.targets.remove(i); }).ok_or_else(|| format!("**{}** has no target to remove.", key.label()));
+        }
+        let others = self.total_weight_pct() - existing.map_or(0.0, |i| self.targets[i].weight_pct);
+        if others + weight_pct > 100.0 + 1e-9 {
+            return Err(format!("Targets would total **{:.1}%** — keep them at or under 100% (the rest stays in cash).", others + weight_pct));
+        }
+        match existing {
+            Some(i) => self.targets[i].weight_pct = weight_pct,
+            None if self.targets.len() >= MAX_REBALANCE_TARGETS => {
+                return Err(format!("A portfolio can have at most {MAX_REBALANCE_TARGETS} targets."));
+            }
+            None => self.targets.push(AllocationTarget { key, weight_pct }),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LedgerKind {
+    Payment,
+    TicketTrade,
+}
+
+/// One side of a payment or trade between members.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: LedgerKind,
+    pub counterparty: serenity::UserId,
+    /// Creds received (positive) or sent (negative).
+    pub creds: i32,
+    /// Tickets received (positive) or sent (negative).
+    pub tickets: i32,
+    pub note: Option<String>,
+}
+
+/// Balance set aside while a trade offer waits for an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Escrow {
+    pub creds: i32,
+    pub tickets: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClubRole {
+    /// Owns the portfolio and trades it directly.
+    Manager,
+    /// Deposits, redeems and votes on trade proposals.
+    Member,
+}
+
+/// One member's stake in a club, in units of the club's NAV.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClubMember {
+    pub user_id: serenity::UserId,
+    pub role: ClubRole,
+    pub units: f64,
+    /// Creds deposited minus creds redeemed, the basis their P&L is measured from.
+    pub contributed: f64,
+}
+
+impl ClubMember {
+    /// Market value of the stake less what the member put in, in creds.
+    pub fn pnl(&self, nav: f64) -> f64 {
+        self.units.mul_add(nav, -self.contributed)
+    }
+}
+
+/// Unit accounting for a portfolio pooled between several members.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Club {
+    pub members: Vec<ClubMember>,
+}
+
+impl Club {
+    pub fn new(manager: serenity::UserId) -> Self {
+        Self { members: vec![ClubMember { user_id: manager, role: ClubRole::Manager, units: 0.0, contributed: 0.0 }] }
+    }
+
+    pub fn member(&self, id: serenity::UserId) -> Option<&ClubMember> {
+        self.members.iter().find(|m| m.user_id == id)
+    }
+
+    pub fn manager(&self) -> Option<serenity::UserId> {
+        self.members.iter().find(|m| m.role == ClubRole::Manager).map(|m| m.user_id)
+    }
+
+    pub fn total_units(&self) -> f64 {
+        self.members.iter().map(|m| m.units).sum()
+    }
+
+    /// Creds per unit for a club worth `equity` creds.
+    pub fn nav(&self, equity: f64) -> f64 {
+        let units = self.total_units();
+        if units > 0.0 { equity / units } else { CLUB_INITIAL_UNIT_CREDS }
+    }
+
+    pub fn add_member(&mut self, id: serenity::UserId) -> Result<(), String> {
+        if self.member(id).is_some() {
+            return Err(format!("<@{id}> is already a member."));
+        }
+        if self.members.len() >= MAX_CLUB_MEMBERS {
+            return Err(format!("Clubs are limited to **{MAX_CLUB_MEMBERS}** members."));
+        }
+        self.members.push(ClubMember { user_id: id, role: ClubRole::Member, units: 0.0, contributed: 0.0 });
+        Ok(())
+    }
+
+    /// Removes a member who holds no units. The manager can't leave their own club.
+    pub fn remove_member(&mut self, id: serenity::UserId) -> Result<(), String> {
+        match self.members.iter().position(|m| m.user_id == id) {
+            None => Err(format!("<@{id}> isn't a member.")),
+            Some(i) if self.members[i].role == ClubRole::Manager => Err("The manager can't leave their own club.".to_string()),
+            Some(i) if self.members[i].units > 0.0 => Err(format!("<@{id}> must redeem their units first.")),
+            Some(i) => {
+                self.members.remove(i);
+                Ok(())
+            }
+        }
+    }
+
+    /// Issues units for `creds` deposited at `nav`. Returns the units issued.
+    pub fn issue(&mut self, id: serenity::UserId, creds: f64, nav: f64) -> Result<f64, String> {
+        if !(nav.is_finite() && nav > 0.0) {
+            return Err(NAV_UNUSABLE_ERR.to_string());
+        }
+        let member = self.members.iter_mut().find(|m| m.user_id == id).ok_or("You aren't a member of this club.")?;
+        let units = creds / nav;
+        member.units += units;
+        member.contributed += creds;
+        Ok(units)
+    }
+
+    /// Cancels `units` at `nav`. Returns the creds they're worth.
+    pub fn redeem(&mut self, id: serenity::UserId, units: f64, nav: f64) -> Result<f64, String> {
+        if !(nav.is_finite() && nav > 0.0) {
+            return Err(NAV_UNUSABLE_ERR.to_string());
+        }
+        let member = self.members.iter_mut().find(|m| m.user_id == id).ok_or("You aren't a member of this club.")?;
+        if units > member.units + 1e-9 {
+            return Err(format!("You only hold **{:.4}** units.", member.units));
+        }
+        let units = units.min(member.units);
+        let creds = units * nav;
+        member.units -= units;
+        member.contributed -= creds;
+        Ok(creds)
+    }
+
+    /// Outcome of a vote: `Some(true)` once members holding more than half the units approve,
+    /// `Some(false)` once that can no longer happen.
+    pub fn tally(&self, approve: &[serenity::UserId], reject: &[serenity::UserId]) -> Option<bool> {
+        let total = self.total_units();
+        let weight = |ids: &[serenity::UserId]| -> f64 { ids.iter().filter_map(|id| self.member(*id)).map(|m| m.units).sum() };
+        if total > 0.0 && weight(approve) > total / 2.0 {
+            Some(true)
+        } else if total <= 0.0 || weight(reject) >= total / 2.0 {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// A follower portfolio's subscription to another member's portfolio.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopySettings {
+    pub leader: serenity::UserId,
+    pub leader_port: String,
+    /// Largest single mirrored trade, in creds.
+    pub max_trade_creds: f64,
+    /// Leader trades up to this time have already been mirrored or skipped.
+    pub synced_at: DateTime<Utc>,
+}
+
+/// Who can see a portfolio through /portfolio_view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum PortfolioPrivacy {
+    /// Only the owner.
+    #[default]
+    Private,
+    /// Value, returns and the benchmark comparison, without holdings.
+    Summary,
+    /// The summary plus every holding's weight and return.
+    Full,
+}
+
+impl PortfolioPrivacy {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Private => "Private",
+            Self::Summary => "Summary",
+            Self::Full => "Full holdings",
+        }
+    }
+}
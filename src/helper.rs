@@ -1,7 +1,8 @@
 //! Shared formatting, financial math, and embed utilities.
 
-use crate::data::{OptionType, UserData, GOLD_LEVEL_THRESHOLD};
+use crate::data::{HysaTier, OptionType, UserData, DEFAULT_HYSA_TIERS, GOLD_LEVEL_THRESHOLD};
 use poise::serenity_prelude as serenity;
+use std::sync::LazyLock;
 
 pub fn parse_user_mention(user_mention: &str) -> Option<u64> {
     user_mention
@@ -76,14 +77,117 @@ pub fn fmt_pct_change(value: f64, basis: f64) -> String {
     }
 }
 
-pub fn gold_hysa_rate(fed_rate: f64) -> f64 {
-    (fed_rate * 0.92).max(0.5)
+/// HYSA rate tiers, read from `HYSA_TIERS` as comma-separated `level:fed_share:floor` entries
+/// (e.g. `0:0:0.1,5:0.5:0.25,10:0.92:0.5`). Falls back to `DEFAULT_HYSA_TIERS` if unset or invalid.
+pub static HYSA_TIERS: LazyLock<Vec<HysaTier>> = LazyLock::new(|| {
+    let Ok(raw) = std::env::var("HYSA_TIERS") else { return DEFAULT_HYSA_TIERS.to_vec() };
+    parse_hysa_tiers(&raw).unwrap_or_else(|| {
+        tracing::warn!(%raw, "invalid HYSA_TIERS; using default tiers");
+        DEFAULT_HYSA_TIERS.to_vec()
+    })
+});
+
+pub fn parse_hysa_tiers(raw: &str) -> Option<Vec<HysaTier>> {
+    let mut tiers = raw
+        .split(',')
+        .map(|entry| {
+            let mut parts = entry.trim().split(':').map(str::trim);
+            let tier = HysaTier {
+                min_level: parts.next()?.parse().ok()?,
+                fed_share: parts.next()?.parse().ok()?,
+                floor: parts.next()?.parse().ok()?,
+            };
+            let valid = parts.next().is_none() && tier.fed_share >= 0.0 && tier.floor >= 0.0;
+            valid.then_some(tier)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    tiers.sort_by_key(|t| t.min_level);
+    Some(tiers)
+}
+
+/// Annual HYSA rate in percent for a member at `level`: the highest tier they've reached.
+pub fn hysa_rate(tiers: &[HysaTier], level: i32, fed_rate: f64) -> f64 {
+    tiers.iter().rfind(|t| level >= t.min_level).map_or(0.0, |t| t.annual_rate(fed_rate))
 }
 
 pub const fn is_gold(user_data: &UserData) -> bool {
     user_data.get_level() >= GOLD_LEVEL_THRESHOLD
 }
 
+/// RGB pixel buffer with the drawing primitives the PNG charts need.
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, background: [u8; 3]) -> Self {
+        Self { width, height, pixels: background.repeat(width * height) }
+    }
+
+    pub fn put(&mut self, x: usize, y: usize, color: [u8; 3]) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 3;
+            self.pixels[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    pub fn blend(&mut self, x: usize, y: usize, color: [u8; 3], alpha: f64) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 3;
+            for (c, &target) in self.pixels[i..i + 3].iter_mut().zip(&color) {
+                *c = (f64::from(target) - f64::from(*c)).mul_add(alpha, f64::from(*c)).round() as u8;
+            }
+        }
+    }
+
+    pub fn fill(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, color: [u8; 3]) {
+        for y in y0..y1 {
+            self.hline(x0, x1, y, color);
+        }
+    }
+
+    pub fn hline(&mut self, x0: usize, x1: usize, y: usize, color: [u8; 3]) {
+        for x in x0..x1 {
+            self.put(x, y, color);
+        }
+    }
+
+    pub fn vline(&mut self, x: usize, y0: usize, y1: usize, color: [u8; 3]) {
+        for y in y0..=y1 {
+            self.put(x, y, color);
+        }
+    }
+
+    pub fn dashed_hline(&mut self, x0: usize, x1: usize, y: usize, color: [u8; 3]) {
+        for x in (x0..x1).filter(|x| (x / 6) % 2 == 0) {
+            self.put(x, y, color);
+        }
+    }
+
+    pub fn dashed_vline(&mut self, x: usize, y0: usize, y1: usize, color: [u8; 3]) {
+        for y in (y0..y1).filter(|y| (y / 6) % 2 == 0) {
+            self.put(x, y, color);
+        }
+    }
+
+    pub fn vline_blend(&mut self, x: usize, ya: usize, yb: usize, color: [u8; 3], alpha: f64) {
+        for y in ya.min(yb)..=ya.max(yb) {
+            self.blend(x, y, color, alpha);
+        }
+    }
+
+    pub fn encode(&self) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().ok()?.write_image_data(&self.pixels).ok()?;
+        Some(out)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,8 +243,24 @@ mod tests {
 
     #[test]
     fn gold_hysa_rate_floor_and_normal() {
-        assert_eq!(gold_hysa_rate(0.0), 0.5);   // floor
-        assert!((gold_hysa_rate(5.0) - 4.6).abs() < 1e-9);
+        let gold = crate::data::GOLD_LEVEL_THRESHOLD;
+        assert_eq!(hysa_rate(&DEFAULT_HYSA_TIERS, gold, 0.0), 0.5);   // floor
+        assert!((hysa_rate(&DEFAULT_HYSA_TIERS, gold, 5.0) - 4.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hysa_rate_picks_highest_tier_reached() {
+        assert_eq!(hysa_rate(&DEFAULT_HYSA_TIERS, 1, 5.0), crate::data::BASE_HYSA_RATE);
+        assert_eq!(hysa_rate(&DEFAULT_HYSA_TIERS, 5, 5.0), 2.5);
+        assert_eq!(hysa_rate(&[], 5, 5.0), 0.0);
+
+        let tiers = parse_hysa_tiers("10:1:0, 0:0:0.2").unwrap();
+        assert_eq!(tiers[0].min_level, 0);
+        assert_eq!(hysa_rate(&tiers, 3, 4.0), 0.2);
+        assert_eq!(hysa_rate(&tiers, 12, 4.0), 4.0);
+        assert!(parse_hysa_tiers("5:0.5").is_none());
+        assert!(parse_hysa_tiers("x:1:1").is_none());
+        assert!(parse_hysa_tiers("0:-1:0").is_none());
     }
 
     #[test]
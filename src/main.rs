@@ -19,6 +19,8 @@
 #![allow(clippy::redundant_else)]              // explicit else after always-continuing if is intentional for readability
 #![allow(clippy::redundant_pub_crate)]         // pub(crate) documents intent even in private modules
 #![allow(clippy::single_match_else)]           // match with one arm + else is clearer than if-let for early-return error patterns
+#![allow(clippy::unnecessary_sort_by)]         // an explicit comparator reads fine for one-field sorts
+#![allow(clippy::field_reassign_with_default)] // tests set up fixtures field by field after Default
 mod api;
 mod basic;
 mod clips;
@@ -86,6 +88,15 @@ async fn main() {
             // Save all data after running a command
             post_command: |ctx: Context<'_>| {
                 Box::pin(async move {
+                    // Mirror any trades the command made in a copyable portfolio into its followers
+                    let started = chrono::DateTime::from_timestamp(ctx.created_at().unix_timestamp(), 0).unwrap_or_default();
+                    let traded = match ctx.data().users.get(&ctx.author().id) {
+                        Some(entry) => trader::traded_copyable_since(&entry.value().read().await.stock, started),
+                        None => false,
+                    };
+                    if traded {
+                        api::sweep_copy_trades(&ctx.data().users, &ctx.serenity_context().http, &ctx.data().bot_chat).await;
+                    }
                     ctx.data().save().await;
                 })
             },
@@ -99,6 +110,9 @@ async fn main() {
                 basic::info(),
                 basic::buy_tickets(),
                 basic::leaderboard(),
+                basic::pay(),
+                basic::ticket_trade(),
+                basic::ledger(),
                 clips::submit_clip(),
                 clips::server_clips(),
                 clips::my_clips(),
@@ -106,17 +120,39 @@ async fn main() {
                 mods::give_creds(),
                 mods::take_creds(),
                 trader::portfolio(),
+                trader::portfolio_privacy(),
+                trader::portfolio_view(),
+                trader::share_card(),
+                trader::rebalance(),
+                trader::transfer(),
+                trader::copy(),
+                trader::copy_stop(),
+                trader::copy_allow(),
+                trader::club(),
+                trader::club_create(),
+                trader::club_member(),
+                trader::club_deposit(),
+                trader::club_redeem(),
+                trader::club_propose(),
                 stock::search(),
                 // /buy and /sell hidden — users go through /search interface
                 // stock::buy(),
                 // stock::sell(),
+                stock::short(),
+                stock::cover(),
+                stock::recurring(),
                 trader::watchlist(),
                 trader::trades(),
+                trader::lots(),
                 options::options_quote(),
+                options::options_chain(),
                 options::options_buy(),
                 options::options_sell(),
                 options::options_write(),
                 options::options_cover(),
+                options::options_strategy(),
+                options::options_close_strategy(),
+                options::options_exercise(),
                 professor::professor(),
             ],
             prefix_options: poise::PrefixFrameworkOptions {
@@ -158,6 +194,10 @@ async fn main() {
                     if let Some(mem) = prof.professor_memory.as_mut() {
                         mem.core_behavior = core_behavior;
                     }
+                    // Members may always copy the professor
+                    for port in prof.stock.portfolios.iter_mut().filter(|p| p.name == professor::PROFESSOR_PORT) {
+                        port.copyable = true;
+                    }
                 } else {
                     let mut prof = data::UserData::default();
                     prof.add_creds(data::NEW_USER_STARTING_CREDS);
@@ -165,13 +205,14 @@ async fn main() {
                         core_behavior: core_behavior.clone(),
                         entries: std::collections::VecDeque::new(),
                     });
-                    let port = data::Portfolio::new("ProfessorPort".to_string());
+                    let mut port = data::Portfolio::new("ProfessorPort".to_string());
+                    port.copyable = true;
                     prof.stock.portfolios.push(port);
                     data.users.insert(bot_user_id, Arc::new(RwLock::new(prof)));
                 }
 
                 professor_task(users.clone(), http.clone(), bot_chat.clone(), bot_user_id);
-                pending_orders_task(users, http, bot_chat);
+                pending_orders_task(users, http, bot_chat, data.hysa_fed_rate.clone());
                 Ok(data)
             })
         })
@@ -316,8 +357,12 @@ fn maintenance_task(
             if INTEREST_REFRESH_DAYS.contains(&today) {
                 api::refresh_market_rate(&hysa_rate).await;
             }
-            api::apply_monthly_interest(&users, &hysa_rate).await;
+            api::accrue_interest(&users, &hysa_rate).await;
             api::sweep_expired_options(&users, &http, &bot_chat).await;
+            api::sweep_early_assignments(&users, &http, &bot_chat, &hysa_rate).await;
+            api::sweep_short_stock(&users, &http, &bot_chat).await;
+            api::sweep_margin_accounts(&users, &http, &bot_chat, &hysa_rate).await;
+            api::snapshot_portfolios(&users).await;
             tokio::time::sleep(std::time::Duration::from_secs(MAINTENANCE_INTERVAL_SECS)).await;
         }
     });
@@ -351,6 +396,7 @@ fn professor_task(
 
             if api::is_market_open().await {
                 professor::professor_daily_session(&users, &http, &bot_chat, bot_user_id).await;
+                api::sweep_copy_trades(&users, &http, &bot_chat).await;
             }
         }
     });
@@ -360,11 +406,16 @@ fn pending_orders_task(
     users: Arc<DashMap<serenity::UserId, Arc<RwLock<UserData>>>>,
     http: Arc<serenity::Http>,
     bot_chat: String,
+    fed_rate: Arc<RwLock<f64>>,
 ) {
     tokio::spawn(async move {
         loop {
             if api::is_market_hours() {
                 api::sweep_pending_orders(&users, &http, &bot_chat).await;
+                api::sweep_recurring_buys(&users, &http, &bot_chat).await;
+                api::sweep_auto_rebalance(&users, &http, &bot_chat).await;
+                api::sweep_copy_trades(&users, &http, &bot_chat).await;
+                api::sweep_option_margin(&users, &http, &bot_chat, &fed_rate).await;
             }
             tokio::time::sleep(std::time::Duration::from_secs(ORDER_SWEEP_INTERVAL_SECS)).await;
         }
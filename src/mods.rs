@@ -1,7 +1,7 @@
 //! Moderator commands: `give_creds`, `take_creds`, and test-seeding utilities.
 
 use crate::clips::check_mod;
-use crate::data::{self, Portfolio, StockProfile, TradeAction, TradeRecord, UserData};
+use crate::data::{self, Portfolio, StockProfile, TradeAction, TradeFees, TradeRecord, UserData};
 use crate::helper::{default_footer, parse_user_mention, price_to_creds};
 use crate::{serenity, Context, Error};
 use chrono::Utc;
@@ -246,6 +246,9 @@ pub async fn test_seed_data(
                         total_creds: proceeds,
                         realized_pnl: Some(pnl),
                         timestamp: Utc::now(),
+                        fees: TradeFees::default(),
+                        lots: Vec::new(),
+                        option_leg: false,
                     });
                 }
             }
@@ -0,0 +1,156 @@
+//! `/options_chain` — browse listed strikes and expiries, then buy or write from the chain.
+
+use super::engine::{
+    listed_expiries, listed_strikes, option_price_usd, years_to_expiry, PricingInputs, SHARES_PER_CONTRACT,
+};
+use super::long::buy_to_open;
+use super::short::write_to_open;
+use crate::api::{fetch_price, market_data_err, option_pricing_inputs};
+use crate::data::{self, ExerciseStyle, OptionType, SettlementMode};
+use crate::helper::default_footer;
+use crate::{serenity, Context, Error};
+use chrono::{NaiveDate, Utc};
+use poise::serenity_prelude::EditMessage;
+use std::time::Duration;
+
+/// Strikes shown on each side of the money in the chain view.
+const CHAIN_VIEW_STRIKES_EACH_SIDE: usize = 5;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Options Order"]
+pub(crate) struct ChainOrderModal {
+    #[name = "Strike (USD)"]
+    pub strike: String,
+    #[name = "Contracts"]
+    pub contracts: String,
+    #[name = "Portfolio"]
+    pub portfolio: String,
+}
+
+fn build_chain_embed(ticker: &str, spot: f64, expiry: NaiveDate, inputs: PricingInputs) -> serenity::CreateEmbed {
+    let expiry_dt = expiry.and_hms_opt(23, 59, 59).unwrap().and_utc();
+    let years = years_to_expiry(&expiry_dt, Utc::now());
+    let dte = (expiry - Utc::now().date_naive()).num_days();
+
+    let mut table = format!("{:>9} │ {:^9} │ {:<9}\n", "CALL", "STRIKE", "PUT");
+    table += "──────────┼───────────┼──────────\n";
+    for strike in listed_strikes(spot, CHAIN_VIEW_STRIKES_EACH_SIDE) {
+        let call = option_price_usd(OptionType::Call, ExerciseStyle::American, spot, strike, years, inputs) * SHARES_PER_CONTRACT;
+        let put = option_price_usd(OptionType::Put, ExerciseStyle::American, spot, strike, years, inputs) * SHARES_PER_CONTRACT;
+        let call_mark = if spot > strike { "*" } else { " " };
+        let put_mark = if spot < strike { "*" } else { " " };
+        table += &format!("{call_mark}{call:>8.2} │ {strike:^9.2} │ {put:<8.2}{put_mark}\n");
+    }
+
+    serenity::CreateEmbed::new()
+        .title(format!("Options Chain — {ticker}"))
+        .description(format!(
+            "Underlying: **${spot:.2}** | Expiry: **{}** ({} DTE)\nVolatility: **{:.1}%** | Risk-free rate: **{:.2}%**\n\n```\n{table}```\n*Premiums per contract (100 shares). `*` = in the money.*",
+            expiry.format("%a %Y-%m-%d"), dte, inputs.volatility * 100.0, inputs.risk_free_rate * 100.0,
+        ))
+        .color(data::EMBED_CYAN)
+        .footer(default_footer())
+}
+
+fn chain_buttons(idx: usize, count: usize) -> Vec<serenity::CreateActionRow> {
+    vec![
+        serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new("chain_prev").label("◀ Earlier").style(serenity::ButtonStyle::Secondary).disabled(idx == 0),
+            serenity::CreateButton::new("chain_next").label("Later ▶").style(serenity::ButtonStyle::Secondary).disabled(idx + 1 >= count),
+        ]),
+        serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new("chain_buy_call").label("Buy Call").style(serenity::ButtonStyle::Success),
+            serenity::CreateButton::new("chain_buy_put").label("Buy Put").style(serenity::ButtonStyle::Success),
+            serenity::CreateButton::new("chain_write_call").label("Write Call").style(serenity::ButtonStyle::Danger),
+            serenity::CreateButton::new("chain_write_put").label("Write Put").style(serenity::ButtonStyle::Danger),
+        ]),
+    ]
+}
+
+/// Browse an options chain with listed strikes and expiries
+#[poise::command(slash_command)]
+pub async fn options_chain(
+    ctx: Context<'_>,
+    #[description = "Underlying ticker (e.g. AAPL)"] ticker: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let ticker = ticker.to_uppercase();
+    let Some(spot) = fetch_price(&ticker).await else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Chain").description(market_data_err(&ticker)).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+    let expiries = listed_expiries(Utc::now().date_naive());
+    let atm_strike = listed_strikes(spot, 0).first().copied().unwrap_or(spot);
+    let serenity_ctx = ctx.serenity_context().clone();
+    let mut idx = 0;
+
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(build_chain_embed(&ticker, spot, expiries[idx], inputs))
+        .components(chain_buttons(idx, expiries.len())))
+        .await?;
+    let mut msg = reply.into_message().await?;
+
+    loop {
+        let Some(press) = msg
+            .await_component_interaction(&serenity_ctx)
+            .author_id(ctx.author().id)
+            .timeout(Duration::from_secs(90))
+            .await
+        else {
+            msg.edit(&serenity_ctx, EditMessage::default().components(vec![])).await.ok();
+            break;
+        };
+
+        let order = match press.data.custom_id.as_str() {
+            "chain_prev" => { idx = idx.saturating_sub(1); None }
+            "chain_next" => { idx = (idx + 1).min(expiries.len() - 1); None }
+            "chain_buy_call" => Some((true, OptionType::Call)),
+            "chain_buy_put" => Some((true, OptionType::Put)),
+            "chain_write_call" => Some((false, OptionType::Call)),
+            "chain_write_put" => Some((false, OptionType::Put)),
+            _ => continue,
+        };
+
+        let Some((is_buy, opt_type)) = order else {
+            press.create_response(&serenity_ctx, serenity::CreateInteractionResponse::Acknowledge).await.ok();
+            msg.edit(&serenity_ctx, EditMessage::default()
+                .embed(build_chain_embed(&ticker, spot, expiries[idx], inputs))
+                .components(chain_buttons(idx, expiries.len())))
+                .await.ok();
+            continue;
+        };
+
+        let default_port = {
+            let u = ctx.data().users.get(&ctx.author().id).unwrap();
+            let ud = u.read().await;
+            ud.stock.portfolios.first().map(|p| p.name.clone()).unwrap_or_default()
+        };
+        let Some(modal) = poise::execute_modal_on_component_interaction::<ChainOrderModal>(
+            ctx, press,
+            Some(ChainOrderModal { strike: format!("{atm_strike:.2}"), contracts: "1".to_string(), portfolio: default_port }),
+            Some(Duration::from_secs(60)),
+        ).await? else { continue; };
+
+        let (Ok(strike), Ok(contracts)) = (modal.strike.trim().trim_start_matches('$').parse::<f64>(), modal.contracts.trim().parse::<u32>()) else {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Options Chain")
+                    .description("Enter a numeric strike and a whole number of contracts.")
+                    .color(data::EMBED_ERROR),
+            )).await?;
+            continue;
+        };
+        let expiry = expiries[idx].format("%Y-%m-%d").to_string();
+        let portfolio = modal.portfolio.trim().to_string();
+        if is_buy {
+            buy_to_open(ctx, ticker.clone(), strike, expiry, opt_type, contracts, portfolio, ExerciseStyle::American).await?;
+        } else {
+            write_to_open(ctx, ticker.clone(), strike, expiry, opt_type, contracts, portfolio, SettlementMode::Cash, ExerciseStyle::American).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,318 @@
+//! `/options_strategy` and `/options_close_strategy` — open and close multi-leg strategies atomically.
+
+use super::engine::{
+    is_listed_expiry, is_listed_strike, option_premium_creds, parse_expiry, unlisted_strike_err, ERR_CLUB_OPTIONS,
+    ERR_EXPIRY_PAST, ERR_INVALID_EXPIRY, ERR_MIN_CONTRACTS, ERR_UNLISTED_EXPIRY,
+};
+use super::strategy::{max_profit_loss, strategy_legs, strategy_margin_creds};
+use crate::api::{fetch_price, market_data_err, option_pricing_inputs};
+use crate::data::{
+    self, AssetType, ExerciseStyle, OptionContract, OptionSide, OptionStrategy, SettlementMode, StrategyKind, TradeAction, TradeFees, TradeRecord,
+};
+use crate::helper::{creds_to_price, default_footer, fmt_pnl, option_type_str};
+use crate::trader::COST_MODEL;
+use crate::{serenity, Context, Error};
+use chrono::Utc;
+
+/// "$1,234.56" or "Unlimited" for a max profit/loss bound.
+pub(crate) fn fmt_bound(creds: Option<f64>) -> String {
+    creds.map_or_else(|| "Unlimited".to_string(), |c| format!("${:.2}", creds_to_price(c)))
+}
+
+/// Short leg description, e.g. "+C105 11/20" or "−P95 12/18".
+pub(crate) fn fmt_leg(c: &OptionContract) -> String {
+    format!(
+        "{}{}{} {}",
+        if c.side == OptionSide::Short { "−" } else { "+" },
+        &option_type_str(c.option_type)[..1],
+        c.strike,
+        c.expiry.format("%m/%d"),
+    )
+}
+
+/// Open a multi-leg options strategy
+#[allow(clippy::too_many_arguments)] // each slash-command option is its own argument
+#[poise::command(slash_command)]
+pub async fn options_strategy(
+    ctx: Context<'_>,
+    #[description = "Strategy to open"] strategy: StrategyKind,
+    #[description = "Underlying ticker (e.g. AAPL)"] ticker: String,
+    #[description = "Strikes low to high, e.g. 95/100/105/110"] strikes: String,
+    #[description = "Expiry date (YYYY-MM-DD) — the near expiry for calendars"] expiry: String,
+    #[description = "Number of contracts per leg"] contracts: u32,
+    #[description = "Portfolio to trade in"] portfolio: String,
+    #[description = "Far expiry (YYYY-MM-DD), calendars only"] far_expiry: Option<String>,
+) -> Result<(), Error> {
+    let title = format!("Options Strategy — {}", strategy.label());
+    let fail = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title(&title).description(desc).color(data::EMBED_ERROR),
+    );
+
+    if contracts == 0 {
+        ctx.send(fail(ERR_MIN_CONTRACTS.to_string())).await?;
+        return Ok(());
+    }
+    let parsed: Result<Vec<f64>, _> = strikes.split(['/', ',', ' ']).filter(|s| !s.is_empty())
+        .map(|s| s.trim_start_matches('$').parse::<f64>())
+        .collect();
+    let Ok(strike_list) = parsed else {
+        ctx.send(fail("Strikes must be numbers separated by `/`, e.g. `95/100`.".to_string())).await?;
+        return Ok(());
+    };
+
+    let today = Utc::now().date_naive();
+    let mut expiries = Vec::new();
+    for date in std::iter::once(&expiry).chain(far_expiry.as_ref()) {
+        let Some(dt) = parse_expiry(date) else {
+            ctx.send(fail(ERR_INVALID_EXPIRY.to_string())).await?;
+            return Ok(());
+        };
+        if dt < Utc::now() {
+            ctx.send(fail(ERR_EXPIRY_PAST.to_string())).await?;
+            return Ok(());
+        }
+        if !is_listed_expiry(dt.date_naive(), today) {
+            ctx.send(fail(ERR_UNLISTED_EXPIRY.to_string())).await?;
+            return Ok(());
+        }
+        expiries.push(dt);
+    }
+
+    let legs = match strategy_legs(strategy, &strike_list, expiries[0], expiries.get(1).copied()) {
+        Ok(legs) => legs,
+        Err(e) => {
+            ctx.send(fail(e)).await?;
+            return Ok(());
+        }
+    };
+
+    ctx.defer().await?;
+    let ticker = ticker.to_uppercase();
+    let Some(price_usd) = fetch_price(&ticker).await else {
+        ctx.send(fail(market_data_err(&ticker))).await?;
+        return Ok(());
+    };
+    if let Some(bad) = strike_list.iter().find(|&&k| !is_listed_strike(price_usd, k)) {
+        ctx.send(fail(unlisted_strike_err(price_usd, *bad))).await?;
+        return Ok(());
+    }
+
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+    let leg_premiums: Vec<f64> = legs.iter()
+        .map(|l| option_premium_creds(l.option_type, ExerciseStyle::American, price_usd, l.strike, &l.expiry, contracts, inputs))
+        .collect();
+    let net_premium: f64 = legs.iter().zip(&leg_premiums)
+        .map(|(l, p)| if l.side == OptionSide::Long { *p } else { -p })
+        .sum();
+    let margin = strategy_margin_creds(strategy, &legs, price_usd, contracts, &leg_premiums);
+    let (max_profit, max_loss) = max_profit_loss(&legs, contracts, net_premium);
+
+    let data_ref = &ctx.data().users;
+    let u = data_ref.get(&ctx.author().id).unwrap();
+    let mut user_data = u.write().await;
+
+    let Some(port_idx) = user_data.stock.find_portfolio_idx(&portfolio) else {
+        drop(user_data);
+        ctx.send(fail(format!("No portfolio named **{portfolio}** found."))).await?;
+        return Ok(());
+    };
+    if user_data.stock.portfolios[port_idx].club.is_some() {
+        drop(user_data);
+        ctx.send(fail(ERR_CLUB_OPTIONS.to_string())).await?;
+        return Ok(());
+    }
+
+    // Every leg is its own fill and pays its own costs
+    let leg_fees: Vec<TradeFees> = leg_premiums.iter().map(|p| COST_MODEL.option_fees(*p)).collect();
+    let total_fees: f64 = leg_fees.iter().map(TradeFees::total).sum();
+    let port = &mut user_data.stock.portfolios[port_idx];
+    let available = port.cash - port.locked_cash();
+    if available - net_premium - total_fees < margin {
+        drop(user_data);
+        ctx.send(fail(format!(
+            "Needs **${:.2}** free — {} **${:.2}** plus **${:.2}** margin and **${:.2}** fees — but **{}** has **${:.2}** free.",
+            creds_to_price(net_premium.max(0.0) + margin + total_fees),
+            if net_premium >= 0.0 { "debit" } else { "credit offsets" },
+            creds_to_price(net_premium.abs()), creds_to_price(margin), creds_to_price(total_fees),
+            portfolio, creds_to_price(available),
+        ))).await?;
+        return Ok(());
+    }
+
+    // ── All checks passed: open every leg together ──────────────────────────
+    let id = port.next_strategy_id();
+    let port_name = port.name.clone();
+    port.cash -= net_premium + total_fees;
+    let margin_leg = legs.iter().position(|l| l.side == OptionSide::Short);
+    for (i, (leg, premium)) in legs.iter().zip(&leg_premiums).enumerate() {
+        port.push_option(&ticker, OptionContract {
+            strike: leg.strike,
+            expiry: leg.expiry,
+            option_type: leg.option_type,
+            contracts,
+            side: leg.side.clone(),
+            collateral: if Some(i) == margin_leg { margin } else { 0.0 },
+            strategy: Some(id),
+            assignment_checked: None,
+            settlement: SettlementMode::Cash,
+            style: ExerciseStyle::American,
+            id: String::new(),
+        }, f64::from(contracts), premium / f64::from(contracts));
+    }
+    port.strategies.push(OptionStrategy {
+        id,
+        kind: strategy,
+        ticker: ticker.clone(),
+        contracts,
+        opened_at: Utc::now(),
+        net_premium,
+        max_profit,
+        max_loss,
+    });
+
+    for ((leg, premium), fees) in legs.iter().zip(&leg_premiums).zip(leg_fees) {
+        let is_short = leg.side == OptionSide::Short;
+        user_data.stock.push_trade(TradeRecord {
+            portfolio: port_name.clone(),
+            ticker: ticker.clone(),
+            asset_name: format!(
+                "{}{ticker} {} ${:.2} {}",
+                if is_short { "SHORT " } else { "" },
+                option_type_str(leg.option_type), leg.strike, leg.expiry.format("%Y-%m-%d"),
+            ),
+            action: if is_short { TradeAction::Sell } else { TradeAction::Buy },
+            quantity: f64::from(contracts),
+            price_per_unit: premium / f64::from(contracts),
+            total_creds: *premium,
+            realized_pnl: None,
+            timestamp: Utc::now(),
+            fees,
+            lots: Vec::new(),
+            option_leg: true,
+        });
+    }
+    drop(user_data);
+
+    let leg_list: Vec<String> = legs.iter().zip(&leg_premiums).map(|(l, p)| format!(
+        "{} {} ${:.2} exp {} — ${:.2}",
+        if l.side == OptionSide::Short { "Sell" } else { "Buy" },
+        option_type_str(l.option_type), l.strike, l.expiry.format("%Y-%m-%d"), creds_to_price(*p),
+    )).collect();
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title(title)
+            .description(format!(
+                "Opened **#{id} {} {}** × {} in **{}**\n{}\n\nNet {}: **${:.2}** | Fees: **${:.2}** | Margin: **${:.2}**\nMax profit: **{}** | Max loss: **{}**",
+                ticker, strategy.label(), contracts, port_name, leg_list.join("\n"),
+                if net_premium >= 0.0 { "debit" } else { "credit" }, creds_to_price(net_premium.abs()),
+                creds_to_price(total_fees), creds_to_price(margin), fmt_bound(max_profit), fmt_bound(max_loss),
+            ))
+            .color(data::EMBED_SUCCESS)
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// Close every leg of an options strategy at market
+#[poise::command(slash_command)]
+pub async fn options_close_strategy(
+    ctx: Context<'_>,
+    #[description = "Portfolio holding the strategy"] portfolio: String,
+    #[description = "Strategy number shown in /portfolio"] strategy_id: u32,
+) -> Result<(), Error> {
+    let fail = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Close Strategy").description(desc).color(data::EMBED_ERROR),
+    );
+
+    let ticker = {
+        let u = ctx.data().users.get(&ctx.author().id).unwrap();
+        let ud = u.read().await;
+        ud.stock.find_portfolio_idx(&portfolio)
+            .and_then(|i| ud.stock.portfolios[i].strategies.iter().find(|s| s.id == strategy_id))
+            .map(|s| s.ticker.clone())
+    };
+    let Some(ticker) = ticker else {
+        ctx.send(fail(format!("No strategy **#{strategy_id}** in **{portfolio}**."))).await?;
+        return Ok(());
+    };
+
+    ctx.defer().await?;
+    let Some(price_usd) = fetch_price(&ticker).await else {
+        ctx.send(fail(market_data_err(&ticker))).await?;
+        return Ok(());
+    };
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+
+    let data_ref = &ctx.data().users;
+    let u = data_ref.get(&ctx.author().id).unwrap();
+    let mut user_data = u.write().await;
+    let Some(port_idx) = user_data.stock.find_portfolio_idx(&portfolio) else {
+        drop(user_data);
+        ctx.send(fail(format!("No portfolio named **{portfolio}** found."))).await?;
+        return Ok(());
+    };
+
+    let port = &mut user_data.stock.portfolios[port_idx];
+    let Some(group) = port.strategies.iter().find(|s| s.id == strategy_id).cloned() else {
+        drop(user_data);
+        ctx.send(fail(format!("No strategy **#{strategy_id}** in **{portfolio}**."))).await?;
+        return Ok(());
+    };
+    let port_name = port.name.clone();
+
+    let mut records = Vec::new();
+    let mut total_pnl = 0.0;
+    let mut net_cash = 0.0;
+    let mut total_fees = 0.0;
+    for pos in port.strategy_legs(strategy_id) {
+        let AssetType::Option(c) = &pos.asset_type else { continue };
+        let value = option_premium_creds(c.option_type, c.style, price_usd, c.strike, &c.expiry, c.contracts, inputs);
+        let cost_basis = pos.avg_cost * pos.quantity;
+        let is_short = c.side == OptionSide::Short;
+        let (cash, pnl) = if is_short { (-value, cost_basis - value) } else { (value, value - cost_basis) };
+        let fees = COST_MODEL.option_fees(value);
+        net_cash += cash - fees.total();
+        total_fees += fees.total();
+        total_pnl += pnl;
+        records.push(TradeRecord {
+            portfolio: port_name.clone(),
+            ticker: ticker.clone(),
+            asset_name: format!(
+                "{}{ticker} {} ${:.2} {}",
+                if is_short { "SHORT " } else { "" },
+                option_type_str(c.option_type), c.strike, c.expiry.format("%Y-%m-%d"),
+            ),
+            action: if is_short { TradeAction::Buy } else { TradeAction::Sell },
+            quantity: pos.quantity,
+            price_per_unit: value / pos.quantity.max(1.0),
+            total_creds: value,
+            realized_pnl: Some(pnl),
+            timestamp: Utc::now(),
+            fees,
+            lots: Vec::new(),
+            option_leg: true,
+        });
+    }
+
+    port.cash += net_cash;
+    port.positions.retain(|p| !matches!(&p.asset_type, AssetType::Option(c) if c.strategy == Some(strategy_id)));
+    port.prune_strategies();
+    for record in records {
+        user_data.stock.push_trade(record);
+    }
+    drop(user_data);
+
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("Close Strategy")
+            .description(format!(
+                "Closed **#{} {} {}** × {} in **{}**\nNet {}: **${:.2}** | Fees: **${:.2}** | Realized P&L: **{}**",
+                strategy_id, ticker, group.kind.label(), group.contracts, port_name,
+                if net_cash >= 0.0 { "credit" } else { "debit" }, creds_to_price(net_cash.abs()),
+                creds_to_price(total_fees), fmt_pnl(total_pnl),
+            ))
+            .color(if total_pnl >= 0.0 { data::EMBED_SUCCESS } else { data::EMBED_FAIL })
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}
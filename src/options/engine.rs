@@ -1,26 +1,258 @@
 //! Pure option pricing functions — no Discord concerns, fully unit-testable.
 
-use crate::data::{AssetType, OptionSide, OptionType, Position};
-use crate::helper::price_to_creds;
-use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use crate::data::{AssetType, ExerciseStyle, OptionSide, OptionType, Position};
+use crate::helper::{option_intrinsic, price_to_creds};
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc, Weekday};
 
 /// Number of underlying shares represented by one options contract (industry standard).
 pub const SHARES_PER_CONTRACT: f64 = 100.0;
-/// Intrinsic time-value premium added per day-to-expiry when pricing options.
-pub const TIME_VALUE_PER_DTE: f64 = 0.05;
+/// Annualised volatility assumed when an underlying has too little price history to measure.
+pub const DEFAULT_VOLATILITY: f64 = 0.30;
+/// Floor on measured volatility so flat-lined tickers still carry some time value.
+pub const MIN_VOLATILITY: f64 = 0.05;
+/// Trading days per year used to annualise daily return volatility.
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+/// Smallest per-share premium quoted for any contract.
+pub const MIN_PREMIUM_USD: f64 = 0.01;
+/// Steps in the binomial tree used for American-style puts.
+const BINOMIAL_STEPS: usize = 200;
 /// Margin requirement as a fraction of notional value for a naked call position.
 pub const CALL_MARGIN_RATIO: f64 = 0.20;
 /// Margin requirement as a fraction of notional value for a naked put position.
 pub const PUT_MARGIN_RATIO: f64 = 0.10;
+/// Naked shorts are force-closed once their collateral covers less than this fraction of the live requirement.
+pub const OPTION_MAINTENANCE_RATIO: f64 = 0.75;
 
 pub const ERR_INVALID_EXPIRY: &str = "Invalid expiry date. Use YYYY-MM-DD format.";
 pub const ERR_EXPIRY_PAST: &str = "Expiry date is in the past.";
 pub const ERR_MIN_CONTRACTS: &str = "Contracts must be at least 1.";
+pub const ERR_EUROPEAN_UNDERLYING: &str = "European-style options are only listed on the index ETFs SPY, QQQ, IWM and DIA.";
+pub const ERR_EUROPEAN_PHYSICAL: &str = "European-style options are cash-settled only.";
+pub const ERR_CLUB_OPTIONS: &str = "Club portfolios can't hold options — unit NAV only values stock and cash.";
+pub const ERR_UNLISTED_EXPIRY: &str = "That expiry isn't listed. Options expire on weekly Fridays and monthly third Fridays — see `/options_chain`.";
 
-pub fn option_premium_creds(intrinsic_usd: f64, expiry: &DateTime<Utc>, contracts: u32) -> f64 {
-    let dte = (*expiry - Utc::now()).num_days().max(0) as f64;
-    let per_contract_usd = (intrinsic_usd + dte * TIME_VALUE_PER_DTE).max(0.01);
-    price_to_creds(per_contract_usd * f64::from(contracts) * SHARES_PER_CONTRACT)
+/// Upcoming weekly (Friday) expiries listed in a chain.
+pub const CHAIN_WEEKLY_EXPIRIES: usize = 4;
+/// Upcoming monthly (third-Friday) expiries listed in a chain.
+pub const CHAIN_MONTHLY_EXPIRIES: usize = 6;
+/// Strikes listed on each side of the at-the-money strike.
+pub const LISTED_STRIKES_EACH_SIDE: usize = 25;
+/// Index ETFs that list European, cash-settled contracts alongside American ones, SPX-style.
+pub const INDEX_PROXIES: [&str; 4] = ["SPY", "QQQ", "IWM", "DIA"];
+
+/// Market inputs to the option pricer, both as annual fractions (0.25 = 25%).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PricingInputs {
+    pub volatility: f64,
+    pub risk_free_rate: f64,
+}
+
+impl PricingInputs {
+    /// Builds inputs from a measured volatility (if any) and the fed funds rate in percent.
+    pub fn new(volatility: Option<f64>, fed_rate_pct: f64) -> Self {
+        Self {
+            volatility: volatility.unwrap_or(DEFAULT_VOLATILITY).max(MIN_VOLATILITY),
+            risk_free_rate: fed_rate_pct / 100.0,
+        }
+    }
+}
+
+/// Year fraction between `now` and `expiry` (0 once expired).
+pub fn years_to_expiry(expiry: &DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    ((*expiry - now).num_seconds().max(0) as f64) / (365.0 * 86_400.0)
+}
+
+/// Standard normal cumulative distribution (Abramowitz & Stegun 26.2.17, |error| < 7.5e-8).
+pub fn norm_cdf(x: f64) -> f64 {
+    let t = 1.0 / 0.231_641_9_f64.mul_add(x.abs(), 1.0);
+    let poly = t * t.mul_add(t.mul_add(t.mul_add(t.mul_add(1.330_274_429, -1.821_255_978), 1.781_477_937), -0.356_563_782), 0.319_381_530);
+    let tail = norm_pdf(x) * poly;
+    if x >= 0.0 { 1.0 - tail } else { tail }
+}
+
+/// Standard normal density.
+pub fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// The Black–Scholes `d1` and `d2` terms.
+pub fn d1_d2(spot: f64, strike: f64, years: f64, rate: f64, vol: f64) -> (f64, f64) {
+    let vol_sqrt_t = vol * years.sqrt();
+    let d1 = (0.5 * vol).mul_add(vol, rate).mul_add(years, (spot / strike).ln()) / vol_sqrt_t;
+    (d1, d1 - vol_sqrt_t)
+}
+
+/// European Black–Scholes price per share in USD (no dividends).
+pub fn black_scholes(opt_type: OptionType, spot: f64, strike: f64, years: f64, rate: f64, vol: f64) -> f64 {
+    if years <= 0.0 || vol <= 0.0 || spot <= 0.0 || strike <= 0.0 {
+        return option_intrinsic(opt_type, spot, strike);
+    }
+    let (d1, d2) = d1_d2(spot, strike, years, rate, vol);
+    let discounted_strike = strike * (-rate * years).exp();
+    match opt_type {
+        OptionType::Call => spot.mul_add(norm_cdf(d1), -discounted_strike * norm_cdf(d2)),
+        OptionType::Put  => discounted_strike.mul_add(norm_cdf(-d2), -spot * norm_cdf(-d1)),
+    }
+}
+
+/// American-style price per share in USD from a Cox–Ross–Rubinstein binomial tree.
+pub fn binomial_american(opt_type: OptionType, spot: f64, strike: f64, years: f64, rate: f64, vol: f64, steps: usize) -> f64 {
+    if years <= 0.0 || vol <= 0.0 || spot <= 0.0 || steps == 0 {
+        return option_intrinsic(opt_type, spot, strike);
+    }
+    let dt = years / steps as f64;
+    let up = (vol * dt.sqrt()).exp();
+    let down = 1.0 / up;
+    let disc = (-rate * dt).exp();
+    let p_up = (((rate * dt).exp() - down) / (up - down)).clamp(0.0, 1.0);
+
+    let mut values: Vec<f64> = (0..=steps)
+        .map(|i| option_intrinsic(opt_type, spot * up.powi(i as i32) * down.powi((steps - i) as i32), strike))
+        .collect();
+    for step in (0..steps).rev() {
+        for i in 0..=step {
+            let continuation = disc * p_up.mul_add(values[i + 1], (1.0 - p_up) * values[i]);
+            let node_spot = spot * up.powi(i as i32) * down.powi((step - i) as i32);
+            values[i] = continuation.max(option_intrinsic(opt_type, node_spot, strike));
+        }
+    }
+    values[0]
+}
+
+/// Theoretical price per share in USD. European options and calls on non-dividend stocks (never
+/// worth exercising early) use Black–Scholes; American puts are priced on a binomial tree.
+pub fn option_price_usd(opt_type: OptionType, style: ExerciseStyle, spot: f64, strike: f64, years: f64, inputs: PricingInputs) -> f64 {
+    match (opt_type, style) {
+        (OptionType::Put, ExerciseStyle::American) => {
+            binomial_american(opt_type, spot, strike, years, inputs.risk_free_rate, inputs.volatility, BINOMIAL_STEPS)
+        }
+        _ => black_scholes(opt_type, spot, strike, years, inputs.risk_free_rate, inputs.volatility),
+    }
+}
+
+/// Option sensitivities per share in USD: theta per calendar day, vega and rho per
+/// percentage point of volatility and rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    pub theta: f64,
+    pub vega: f64,
+    pub rho: f64,
+}
+
+impl Greeks {
+    /// Every sensitivity multiplied by `factor` (e.g. shares held, negative when short).
+    pub fn scaled(self, factor: f64) -> Self {
+        Self {
+            delta: self.delta * factor,
+            gamma: self.gamma * factor,
+            theta: self.theta * factor,
+            vega: self.vega * factor,
+            rho: self.rho * factor,
+        }
+    }
+
+    pub fn add(self, other: Self) -> Self {
+        Self {
+            delta: self.delta + other.delta,
+            gamma: self.gamma + other.gamma,
+            theta: self.theta + other.theta,
+            vega: self.vega + other.vega,
+            rho: self.rho + other.rho,
+        }
+    }
+}
+
+/// Black–Scholes Greeks per share. American puts use the European sensitivities as an approximation.
+/// Expired contracts have a delta of 1/0 (ITM/OTM) and no other exposure.
+pub fn option_greeks(opt_type: OptionType, spot: f64, strike: f64, years: f64, inputs: PricingInputs) -> Greeks {
+    let PricingInputs { volatility: vol, risk_free_rate: rate } = inputs;
+    if years <= 0.0 || vol <= 0.0 || spot <= 0.0 || strike <= 0.0 {
+        let itm = option_intrinsic(opt_type, spot, strike) > 0.0;
+        let delta = match (opt_type, itm) {
+            (_, false) => 0.0,
+            (OptionType::Call, true) => 1.0,
+            (OptionType::Put, true) => -1.0,
+        };
+        return Greeks { delta, ..Greeks::default() };
+    }
+
+    let (d1, d2) = d1_d2(spot, strike, years, rate, vol);
+    let pdf_d1 = norm_pdf(d1);
+    let sqrt_t = years.sqrt();
+    let discounted_strike = strike * (-rate * years).exp();
+    let decay = -spot * pdf_d1 * vol / (2.0 * sqrt_t);
+
+    let (delta, theta_year, rho) = match opt_type {
+        OptionType::Call => (
+            norm_cdf(d1),
+            (-rate * discounted_strike).mul_add(norm_cdf(d2), decay),
+            discounted_strike * years * norm_cdf(d2),
+        ),
+        OptionType::Put => (
+            norm_cdf(d1) - 1.0,
+            (rate * discounted_strike).mul_add(norm_cdf(-d2), decay),
+            -discounted_strike * years * norm_cdf(-d2),
+        ),
+    };
+
+    Greeks {
+        delta,
+        gamma: pdf_d1 / (spot * vol * sqrt_t),
+        theta: theta_year / 365.0,
+        vega: spot * pdf_d1 * sqrt_t / 100.0,
+        rho: rho / 100.0,
+    }
+}
+
+/// Greeks of a whole position in share-equivalents: stock contributes its (signed) share count
+/// as delta; option contracts are scaled by `SHARES_PER_CONTRACT` and negated when written.
+pub fn position_greeks(pos: &Position, spot: f64, inputs: PricingInputs, now: DateTime<Utc>) -> Greeks {
+    match &pos.asset_type {
+        AssetType::Option(c) => {
+            let years = years_to_expiry(&c.expiry, now);
+            let sign = if c.side == OptionSide::Short { -1.0 } else { 1.0 };
+            option_greeks(c.option_type, spot, c.strike, years, inputs)
+                .scaled(sign * SHARES_PER_CONTRACT * f64::from(c.contracts))
+        }
+        _ => Greeks { delta: pos.quantity, ..Greeks::default() },
+    }
+}
+
+/// True when `ticker` lists European-style contracts.
+pub fn is_index_proxy(ticker: &str) -> bool {
+    INDEX_PROXIES.contains(&ticker)
+}
+
+/// Premium in creds for `contracts` contracts, floored at `MIN_PREMIUM_USD` per share.
+pub fn option_premium_creds(
+    opt_type: OptionType,
+    style: ExerciseStyle,
+    spot: f64,
+    strike: f64,
+    expiry: &DateTime<Utc>,
+    contracts: u32,
+    inputs: PricingInputs,
+) -> f64 {
+    let years = years_to_expiry(expiry, Utc::now());
+    let per_share_usd = option_price_usd(opt_type, style, spot, strike, years, inputs).max(MIN_PREMIUM_USD);
+    price_to_creds(per_share_usd * f64::from(contracts) * SHARES_PER_CONTRACT)
+}
+
+/// Annualised volatility of daily log returns, or `None` with fewer than two returns.
+pub fn historical_volatility(closes: &[f64]) -> Option<f64> {
+    let returns: Vec<f64> = closes.windows(2)
+        .filter(|w| w[0] > 0.0 && w[1] > 0.0)
+        .map(|w| (w[1] / w[0]).ln())
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Some((variance * TRADING_DAYS_PER_YEAR).sqrt())
 }
 
 pub fn naked_margin_usd(opt_type: OptionType, price_usd: f64, strike: f64, contracts: u32, premium_usd: f64) -> f64 {
@@ -39,25 +271,73 @@ pub fn naked_margin_usd(opt_type: OptionType, price_usd: f64, strike: f64, contr
     )
 }
 
-pub fn find_option_idx(
-    positions: &[Position],
-    ticker: &str,
-    strike: f64,
-    expiry: DateTime<Utc>,
-    opt_type: OptionType,
-    side: &OptionSide,
-) -> Option<usize> {
-    positions.iter().position(|p| {
-        if p.ticker != ticker {
-            return false;
-        }
-        #[expect(clippy::float_cmp, reason = "strike prices are stored/compared as exact values we set")]
-        if let AssetType::Option(c) = &p.asset_type {
-            c.strike == strike && c.expiry == expiry && c.option_type == opt_type && c.side == *side
-        } else {
-            false
+/// Strike spacing for an underlying trading at `spot` USD.
+pub fn strike_increment(spot: f64) -> f64 {
+    match spot {
+        s if s < 5.0 => 0.5,
+        s if s < 25.0 => 1.0,
+        s if s < 100.0 => 2.5,
+        s if s < 500.0 => 5.0,
+        _ => 10.0,
+    }
+}
+
+/// Listed strikes around the at-the-money strike, `each_side` increments up and down, ascending.
+pub fn listed_strikes(spot: f64, each_side: usize) -> Vec<f64> {
+    let inc = strike_increment(spot);
+    let atm_steps = (spot / inc).round() as i64;
+    let each_side = each_side as i64;
+    ((atm_steps - each_side).max(1)..=atm_steps + each_side)
+        .map(|n| n as f64 * inc)
+        .collect()
+}
+
+/// Whether `strike` is one of the strikes listed for an underlying at `spot`.
+pub fn is_listed_strike(spot: f64, strike: f64) -> bool {
+    listed_strikes(spot, LISTED_STRIKES_EACH_SIDE).iter().any(|s| (s - strike).abs() < 1e-6)
+}
+
+/// Third Friday of the given month.
+pub fn third_friday(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Fri, 3).expect("every month has a third Friday")
+}
+
+/// Listed expiries on or after `today`: the next weekly Fridays plus the next monthly
+/// third Fridays, ascending and de-duplicated.
+pub fn listed_expiries(today: NaiveDate) -> Vec<NaiveDate> {
+    let days_to_friday = (7 + Weekday::Fri.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    let first_friday = today + chrono::Duration::days(i64::from(days_to_friday));
+    let mut dates: Vec<NaiveDate> = (0..CHAIN_WEEKLY_EXPIRIES)
+        .map(|w| first_friday + chrono::Duration::weeks(w as i64))
+        .collect();
+
+    let mut month = today.with_day(1).expect("day 1 always exists");
+    let mut monthlies = 0;
+    while monthlies < CHAIN_MONTHLY_EXPIRIES {
+        let expiry = third_friday(month.year(), month.month());
+        if expiry >= today {
+            dates.push(expiry);
+            monthlies += 1;
         }
-    })
+        month = month + Months::new(1);
+    }
+
+    dates.sort_unstable();
+    dates.dedup();
+    dates
+}
+
+/// Whether `expiry` is a listed expiry as of `today`.
+pub fn is_listed_expiry(expiry: NaiveDate, today: NaiveDate) -> bool {
+    listed_expiries(today).contains(&expiry)
+}
+
+/// Error text for a strike that isn't listed, naming the spacing to use.
+pub fn unlisted_strike_err(spot: f64, strike: f64) -> String {
+    format!(
+        "**${strike:.2}** isn't a listed strike. Strikes near **${spot:.2}** are spaced **${:.2}** apart — see `/options_chain`.",
+        strike_increment(spot),
+    )
 }
 
 pub fn parse_expiry(date_str: &str) -> Option<DateTime<Utc>> {
@@ -84,19 +364,139 @@ mod tests {
         assert!(parse_expiry("not-a-date").is_none());
     }
 
+    const INPUTS: PricingInputs = PricingInputs { volatility: 0.20, risk_free_rate: 0.05 };
+
     #[test]
     fn option_premium_creds_minimum() {
-        // 0 intrinsic, expired — should still give minimum premium
+        // Far OTM, expired — should still give minimum premium
         let past = Utc::now() - Duration::days(1);
-        let result = option_premium_creds(0.0, &past, 1);
-        assert!(result > 0.0);
+        let result = option_premium_creds(OptionType::Call, ExerciseStyle::American, 5.0, 100.0, &past, 1, INPUTS);
+        assert!((result - price_to_creds(MIN_PREMIUM_USD * SHARES_PER_CONTRACT)).abs() < 1e-9);
     }
 
     #[test]
     fn option_premium_scales_with_contracts() {
         let expiry = Utc::now() + Duration::days(30);
-        let one   = option_premium_creds(5.0, &expiry, 1);
-        let three = option_premium_creds(5.0, &expiry, 3);
+        let one   = option_premium_creds(OptionType::Call, ExerciseStyle::American, 100.0, 95.0, &expiry, 1, INPUTS);
+        let three = option_premium_creds(OptionType::Call, ExerciseStyle::American, 100.0, 95.0, &expiry, 3, INPUTS);
         assert!((three - one * 3.0).abs() < 1.0);
     }
+
+    #[test]
+    fn black_scholes_reference_values() {
+        // Hull, Options Futures & Other Derivatives: S=K=100, T=1, r=5%, σ=20%
+        assert!((black_scholes(OptionType::Call, 100.0, 100.0, 1.0, 0.05, 0.20) - 10.4506).abs() < 1e-3);
+        assert!((black_scholes(OptionType::Put,  100.0, 100.0, 1.0, 0.05, 0.20) - 5.5735).abs() < 1e-3);
+        // Hull example 15.6: S=42, K=40, T=0.5, r=10%, σ=20%
+        assert!((black_scholes(OptionType::Call, 42.0, 40.0, 0.5, 0.10, 0.20) - 4.76).abs() < 5e-3);
+        assert!((black_scholes(OptionType::Put,  42.0, 40.0, 0.5, 0.10, 0.20) - 0.81).abs() < 5e-3);
+    }
+
+    #[test]
+    fn american_put_carries_early_exercise_premium() {
+        // Reference American put for S=K=100, T=1, r=5%, σ=20% is ≈ 6.09
+        let american = binomial_american(OptionType::Put, 100.0, 100.0, 1.0, 0.05, 0.20, BINOMIAL_STEPS);
+        assert!((american - 6.09).abs() < 0.01);
+        assert!(american > black_scholes(OptionType::Put, 100.0, 100.0, 1.0, 0.05, 0.20));
+        // Without dividends an American call equals the European one
+        let call = binomial_american(OptionType::Call, 100.0, 100.0, 1.0, 0.05, 0.20, BINOMIAL_STEPS);
+        assert!((call - 10.4506).abs() < 0.02);
+    }
+
+    #[test]
+    fn european_put_skips_early_exercise_premium() {
+        let inputs = PricingInputs { volatility: 0.20, risk_free_rate: 0.05 };
+        let european = option_price_usd(OptionType::Put, ExerciseStyle::European, 100.0, 100.0, 1.0, inputs);
+        let american = option_price_usd(OptionType::Put, ExerciseStyle::American, 100.0, 100.0, 1.0, inputs);
+        assert!((european - 5.5735).abs() < 1e-3);
+        assert!(american > european);
+        // Deep ITM a European put trades below intrinsic; the American one never does
+        assert!(option_price_usd(OptionType::Put, ExerciseStyle::European, 50.0, 100.0, 1.0, inputs) < 50.0);
+        assert!(option_price_usd(OptionType::Put, ExerciseStyle::American, 50.0, 100.0, 1.0, inputs) >= 50.0 - 1e-9);
+        assert!(is_index_proxy("SPY") && !is_index_proxy("AAPL"));
+    }
+
+    #[test]
+    fn time_value_scales_with_underlying_and_volatility() {
+        // A 1-year 20% OTM call is worth ~180× more on a $900 stock than on a $5 stock
+        let cheap = black_scholes(OptionType::Call, 5.0, 6.0, 1.0, 0.05, 0.30);
+        let pricey = black_scholes(OptionType::Call, 900.0, 1080.0, 1.0, 0.05, 0.30);
+        assert!((pricey / cheap - 180.0).abs() < 1e-6);
+        assert!(black_scholes(OptionType::Call, 100.0, 100.0, 1.0, 0.05, 0.60) > black_scholes(OptionType::Call, 100.0, 100.0, 1.0, 0.05, 0.20));
+    }
+
+    #[test]
+    fn historical_volatility_of_alternating_returns() {
+        // Log returns alternate ±0.01 → sample stdev ≈ 0.01 per day
+        let closes: Vec<f64> = (0..21).map(|i| if i % 2 == 0 { 100.0 } else { 100.0 * 0.01_f64.exp() }).collect();
+        let vol = historical_volatility(&closes).unwrap();
+        let expected = 0.01 * (20.0_f64 / 19.0).sqrt() * TRADING_DAYS_PER_YEAR.sqrt();
+        assert!((vol - expected).abs() < 1e-9);
+        assert!(historical_volatility(&[100.0, 101.0]).is_none());
+    }
+
+    #[test]
+    fn greeks_match_reference_values() {
+        // Hull: S=49, K=50, T=0.3846, r=5%, σ=20% call — Δ 0.522, Γ 0.066, ν 0.121/pt, Θ −4.31/yr, ρ 0.0891/pt
+        let inputs = PricingInputs { volatility: 0.20, risk_free_rate: 0.05 };
+        let g = option_greeks(OptionType::Call, 49.0, 50.0, 0.3846, inputs);
+        assert!((g.delta - 0.522).abs() < 1e-3);
+        assert!((g.gamma - 0.066).abs() < 1e-3);
+        assert!((g.vega - 0.121).abs() < 1e-3);
+        assert!((g.theta * 365.0 + 4.31).abs() < 1e-2);
+        assert!((g.rho - 0.0891).abs() < 1e-3);
+        // Put–call parity: Δput = Δcall − 1, equal gamma and vega
+        let p = option_greeks(OptionType::Put, 49.0, 50.0, 0.3846, inputs);
+        assert!((p.delta - (g.delta - 1.0)).abs() < 1e-9);
+        assert!((p.gamma - g.gamma).abs() < 1e-12);
+        assert!((p.vega - g.vega).abs() < 1e-12);
+    }
+
+    #[test]
+    fn position_greeks_scale_by_contracts_and_side() {
+        use crate::data::{OptionContract, SettlementMode};
+        let now = Utc::now();
+        let contract = |side| Position {
+            ticker: "AAPL".to_string(),
+            asset_type: AssetType::Option(OptionContract {
+                option_type: OptionType::Call, strike: 100.0, expiry: now + Duration::days(30),
+                contracts: 2, side, collateral: 0.0, strategy: None, assignment_checked: None,
+                settlement: SettlementMode::Cash, style: ExerciseStyle::American, id: String::new(),
+            }),
+            quantity: 2.0,
+            avg_cost: 0.0,
+            lots: Vec::new(),
+        };
+        let long = position_greeks(&contract(OptionSide::Long), 100.0, INPUTS, now);
+        let short = position_greeks(&contract(OptionSide::Short), 100.0, INPUTS, now);
+        assert!(long.delta > 100.0 && long.delta < 200.0); // ~0.5 × 200 shares
+        assert!((long.delta + short.delta).abs() < 1e-9);
+        let stock = Position { ticker: "AAPL".to_string(), asset_type: AssetType::Stock, quantity: -30.0, avg_cost: 0.0, lots: Vec::new() };
+        assert_eq!(position_greeks(&stock, 100.0, INPUTS, now), Greeks { delta: -30.0, ..Greeks::default() });
+    }
+
+    #[test]
+    fn strikes_follow_price_tiers() {
+        assert!((strike_increment(3.0) - 0.5).abs() < f64::EPSILON);
+        assert!((strike_increment(137.42) - 5.0).abs() < f64::EPSILON);
+        let strikes = listed_strikes(137.42, 2);
+        assert_eq!(strikes, vec![125.0, 130.0, 135.0, 140.0, 145.0]);
+        assert!(is_listed_strike(137.42, 135.0));
+        assert!(!is_listed_strike(137.42, 137.42));
+        // Strikes never go to zero or below on cheap underlyings
+        assert!(listed_strikes(1.0, 5).iter().all(|&s| s > 0.0));
+    }
+
+    #[test]
+    fn expiries_are_weekly_and_monthly_fridays() {
+        // Sunday 2026-03-01: weeklies Mar 6/13/20/27, monthlies Mar 20 … Aug 21
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let dates = listed_expiries(today);
+        assert!(dates.iter().all(|d| d.weekday() == Weekday::Fri));
+        assert_eq!(dates.first(), NaiveDate::from_ymd_opt(2026, 3, 6).as_ref());
+        assert_eq!(dates.last(), NaiveDate::from_ymd_opt(2026, 8, 21).as_ref());
+        assert_eq!(dates.len(), CHAIN_WEEKLY_EXPIRIES + CHAIN_MONTHLY_EXPIRIES - 1); // Mar 20 is both
+        assert!(is_listed_expiry(third_friday(2026, 6), today));
+        assert!(!is_listed_expiry(NaiveDate::from_ymd_opt(2026, 3, 8).unwrap(), today)); // a Sunday
+    }
 }
@@ -0,0 +1,142 @@
+//! `/options_exercise` — exercise long American-style options early into shares.
+//! European contracts only settle at expiry and are rejected.
+
+use super::engine::{option_premium_creds, ERR_EXPIRY_PAST, ERR_MIN_CONTRACTS, SHARES_PER_CONTRACT};
+use super::held::{autocomplete_long_option, held_option, HeldOption};
+use super::settlement::exercise_position;
+use crate::api::{fetch_price, market_data_err, option_pricing_inputs};
+use crate::data::{self, AssetType, ExerciseStyle, OptionSide, OptionType};
+use crate::helper::{creds_to_price, default_footer, fmt_pnl, option_intrinsic, option_type_str, price_to_creds};
+use crate::trader::COST_MODEL;
+use crate::{serenity, Context, Error};
+use chrono::Utc;
+
+/// Exercise a long option early — calls buy shares at the strike, puts deliver them
+#[poise::command(slash_command)]
+pub async fn options_exercise(
+    ctx: Context<'_>,
+    #[description = "Contract ID shown in /portfolio (e.g. AAPL260116C00150000)"]
+    #[autocomplete = "autocomplete_long_option"]
+    contract: String,
+    #[description = "Number of contracts to exercise"] contracts: u32,
+    #[description = "Portfolio holding the contracts"] portfolio: String,
+) -> Result<(), Error> {
+    let fail = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Options Exercise").description(desc).color(data::EMBED_ERROR),
+    );
+
+    if contracts == 0 {
+        ctx.send(fail(ERR_MIN_CONTRACTS.to_string())).await?;
+        return Ok(());
+    }
+    let HeldOption { ticker, contract: held } = match held_option(ctx, &portfolio, &contract, &OptionSide::Long).await {
+        Ok(h) => h,
+        Err(e) => {
+            ctx.send(fail(e)).await?;
+            return Ok(());
+        }
+    };
+    if held.style == ExerciseStyle::European {
+        ctx.send(fail("European-style contracts can't be exercised early — they cash-settle at expiry. Sell with `/options_sell` instead.".to_string())).await?;
+        return Ok(());
+    }
+    if held.expiry < Utc::now() {
+        ctx.send(fail(ERR_EXPIRY_PAST.to_string())).await?;
+        return Ok(());
+    }
+
+    let Some(price_usd) = fetch_price(&ticker).await else {
+        ctx.send(fail(market_data_err(&ticker))).await?;
+        return Ok(());
+    };
+    let (option_type, strike) = (held.option_type, held.strike);
+    let expiry = held.expiry.format("%Y-%m-%d");
+    let type_str = option_type_str(option_type);
+    if option_intrinsic(option_type, price_usd, strike) <= 0.0 {
+        ctx.send(fail(format!(
+            "**{ticker} {type_str} ${strike:.2}** is out of the money (underlying **${price_usd:.2}**) — sell it with `/options_sell` instead.",
+        ))).await?;
+        return Ok(());
+    }
+
+    // Time value given up by exercising instead of selling at the model price
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+    let market_value = option_premium_creds(option_type, held.style, price_usd, strike, &held.expiry, contracts, inputs);
+    let intrinsic_value = price_to_creds(option_intrinsic(option_type, price_usd, strike) * SHARES_PER_CONTRACT * f64::from(contracts));
+    let forfeited = (market_value - intrinsic_value).max(0.0);
+
+    let data_ref = &ctx.data().users;
+    let u = data_ref.get(&ctx.author().id).unwrap();
+    let mut user_data = u.write().await;
+
+    // Re-find by id: the position may have moved or closed while the price was fetched
+    let located = user_data.stock.find_portfolio_idx(&portfolio)
+        .and_then(|pi| user_data.stock.portfolios[pi].option_idx(&held.id).map(|i| (pi, i)));
+    let Some((port_idx, pos_idx)) = located else {
+        drop(user_data);
+        ctx.send(fail(format!("**{}** is no longer held in portfolio **{portfolio}**.", held.id))).await?;
+        return Ok(());
+    };
+
+    let held_contracts = if let AssetType::Option(c) = &user_data.stock.portfolios[port_idx].positions[pos_idx].asset_type { c.contracts } else { 0 };
+    if contracts > held_contracts {
+        drop(user_data);
+        ctx.send(fail(format!("You only hold **{held_contracts}** contracts but tried to exercise **{contracts}**."))).await?;
+        return Ok(());
+    }
+
+    let shares = f64::from(contracts) * SHARES_PER_CONTRACT;
+    if option_type == OptionType::Call {
+        let cost = price_to_creds(strike) * shares;
+        let fees = COST_MODEL.fees(&AssetType::Stock, cost);
+        let port = &user_data.stock.portfolios[port_idx];
+        let available = port.cash - port.locked_cash();
+        if available < cost + fees.total() {
+            drop(user_data);
+            ctx.send(fail(format!(
+                "Exercising needs **${:.2}** to buy **{shares:.0}** shares at the strike, but **{portfolio}** has **${:.2}** free.",
+                creds_to_price(cost + fees.total()), creds_to_price(available),
+            ))).await?;
+            return Ok(());
+        }
+    }
+
+    let stock = &mut user_data.stock;
+    let settlement = match exercise_position(&mut stock.portfolios[port_idx], &mut stock.trade_history, pos_idx, contracts, price_usd, &COST_MODEL) {
+        Ok(s) => s,
+        Err(e) => {
+            drop(user_data);
+            ctx.send(fail(e)).await?;
+            return Ok(());
+        }
+    };
+    drop(user_data);
+
+    let (result, color) = match settlement.realized_pnl {
+        Some(pnl) => (
+            format!(
+                "Delivered **{shares:.0}** shares at **${strike:.2}** for **${:.2}** | Realized P&L: **{}**",
+                creds_to_price(settlement.cash_delta), fmt_pnl(pnl),
+            ),
+            if pnl >= 0.0 { data::EMBED_SUCCESS } else { data::EMBED_FAIL },
+        ),
+        None => (
+            format!(
+                "Bought **{shares:.0}** shares at **${strike:.2}** for **${:.2}** — premium paid is added to their cost basis.",
+                creds_to_price(-settlement.cash_delta),
+            ),
+            data::EMBED_SUCCESS,
+        ),
+    };
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("Options Exercise")
+            .description(format!(
+                "Exercised **{contracts}** × **{ticker} {type_str} ${strike:.2}** exp {expiry}\n{result}\nTime value forfeited: **${:.2}**",
+                creds_to_price(forfeited),
+            ))
+            .color(color)
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}
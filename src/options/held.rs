@@ -0,0 +1,68 @@
+//! Looking up held option positions by contract id, and the autocomplete that offers them.
+
+use crate::data::{AssetType, OptionContract, OptionSide};
+use crate::helper::option_type_str;
+use crate::{serenity, Context};
+
+/// Discord caps autocomplete lists at 25 entries.
+const MAX_CHOICES: usize = 25;
+
+/// Snapshot of a standalone option position, taken under a read lock before pricing.
+pub(crate) struct HeldOption {
+    pub ticker: String,
+    pub contract: OptionContract,
+}
+
+/// Finds the standalone `side` option with contract id `id` in `portfolio`, or the message explaining why not.
+pub(crate) async fn held_option(ctx: Context<'_>, portfolio: &str, id: &str, side: &OptionSide) -> Result<HeldOption, String> {
+    let u = ctx.data().users.get(&ctx.author().id).unwrap();
+    let user_data = u.read().await;
+    let Some(port) = user_data.stock.find_portfolio_idx(portfolio).map(|i| &user_data.stock.portfolios[i]) else {
+        return Err(format!("No portfolio named **{portfolio}** found."));
+    };
+    let pos = port.option_idx(id).map(|i| &port.positions[i]);
+    let Some((pos, AssetType::Option(c))) = pos.map(|p| (p, &p.asset_type)) else {
+        return Err(format!("No contract **{id}** in portfolio **{portfolio}** — pick one from the list or check `/portfolio`."));
+    };
+    if let Some(strategy) = c.strategy {
+        return Err(format!("**{}** is a leg of strategy **#{strategy}** — close it with `/options_close_strategy`.", c.id));
+    }
+    if c.side != *side {
+        return Err(match side {
+            OptionSide::Long => format!("**{}** is a written contract — close it with `/options_cover`.", c.id),
+            OptionSide::Short => format!("**{}** is a long contract — close it with `/options_sell`.", c.id),
+        });
+    }
+    Ok(HeldOption { ticker: pos.ticker.clone(), contract: c.clone() })
+}
+
+/// Autocomplete for long contract ids across the caller's portfolios.
+pub(crate) async fn autocomplete_long_option(ctx: Context<'_>, partial: &str) -> Vec<serenity::AutocompleteChoice> {
+    option_choices(ctx, partial, &OptionSide::Long).await
+}
+
+/// Autocomplete for written contract ids across the caller's portfolios.
+pub(crate) async fn autocomplete_short_option(ctx: Context<'_>, partial: &str) -> Vec<serenity::AutocompleteChoice> {
+    option_choices(ctx, partial, &OptionSide::Short).await
+}
+
+async fn option_choices(ctx: Context<'_>, partial: &str, side: &OptionSide) -> Vec<serenity::AutocompleteChoice> {
+    let Some(u) = ctx.data().users.get(&ctx.author().id).map(|u| u.clone()) else { return Vec::new() };
+    let user_data = u.read().await;
+    let partial = partial.to_uppercase();
+    user_data.stock.portfolios.iter()
+        .flat_map(|port| port.positions.iter().map(move |p| (port, p)))
+        .filter_map(|(port, p)| match &p.asset_type {
+            AssetType::Option(c) if c.side == *side && c.strategy.is_none() => Some((port, p, c)),
+            _ => None,
+        })
+        .filter_map(|(port, p, c)| {
+            let label = format!(
+                "{} {} ${:.2} {} ×{} · {} — {}",
+                p.ticker, option_type_str(c.option_type), c.strike, c.expiry.format("%Y-%m-%d"), c.contracts, port.name, c.id,
+            );
+            label.to_uppercase().contains(&partial).then(|| serenity::AutocompleteChoice::new(label.chars().take(100).collect::<String>(), c.id.clone()))
+        })
+        .take(MAX_CHOICES)
+        .collect()
+}
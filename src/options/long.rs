@@ -1,13 +1,16 @@
 //! `/options_buy` and `/options_sell` — long-side options commands.
 
-use super::engine::{find_option_idx, option_premium_creds, parse_expiry, ERR_EXPIRY_PAST, ERR_INVALID_EXPIRY, ERR_MIN_CONTRACTS};
-use crate::api::{fetch_price, market_data_err};
-use crate::data::{self, AssetType, OptionContract, OptionSide, OptionType, TradeAction, TradeRecord, Position};
-use crate::helper::{creds_to_price, default_footer, option_intrinsic, option_type_str};
+use super::engine::{is_index_proxy, is_listed_expiry, is_listed_strike, unlisted_strike_err, ERR_CLUB_OPTIONS, ERR_UNLISTED_EXPIRY, option_premium_creds, parse_expiry, ERR_EUROPEAN_UNDERLYING, ERR_EXPIRY_PAST, ERR_INVALID_EXPIRY, ERR_MIN_CONTRACTS};
+use super::held::{autocomplete_long_option, held_option, HeldOption};
+use crate::api::{fetch_price, market_data_err, option_pricing_inputs};
+use crate::data::{self, AssetType, ExerciseStyle, OptionContract, OptionSide, OptionType, TradeAction, TradeRecord, SettlementMode};
+use crate::helper::{creds_to_price, default_footer, option_type_str};
+use crate::trader::COST_MODEL;
 use crate::{serenity, Context, Error};
 use chrono::Utc;
 
 /// Buy an options contract
+#[allow(clippy::too_many_arguments)] // each slash-command option is its own argument
 #[poise::command(slash_command)]
 pub async fn options_buy(
     ctx: Context<'_>,
@@ -17,6 +20,22 @@ pub async fn options_buy(
     #[description = "Call or Put"] option_type: OptionType,
     #[description = "Number of contracts (1 contract = 100 shares)"] contracts: u32,
     #[description = "Portfolio to buy from"] portfolio: String,
+    #[description = "American (default) or European — European is listed on index ETFs only"] style: Option<ExerciseStyle>,
+) -> Result<(), Error> {
+    buy_to_open(ctx, ticker, strike, expiry, option_type, contracts, portfolio, style.unwrap_or_default()).await
+}
+
+/// Opens (or adds to) a long option position. Shared by `/options_buy` and `/options_chain`.
+#[expect(clippy::too_many_arguments, reason = "mirrors the /options_buy parameters")]
+pub(crate) async fn buy_to_open(
+    ctx: Context<'_>,
+    ticker: String,
+    strike: f64,
+    expiry: String,
+    option_type: OptionType,
+    contracts: u32,
+    portfolio: String,
+    style: ExerciseStyle,
 ) -> Result<(), Error> {
     if contracts == 0 {
         ctx.send(poise::CreateReply::default().embed(
@@ -40,7 +59,21 @@ pub async fn options_buy(
         return Ok(());
     }
 
+    if !is_listed_expiry(expiry_dt.date_naive(), Utc::now().date_naive()) {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Buy").description(ERR_UNLISTED_EXPIRY).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
     let ticker = ticker.to_uppercase();
+    if style == ExerciseStyle::European && !is_index_proxy(&ticker) {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Buy").description(ERR_EUROPEAN_UNDERLYING).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
     let price_usd = if let Some(p) = fetch_price(&ticker).await { p } else {
         ctx.send(poise::CreateReply::default().embed(
             serenity::CreateEmbed::new().title("Options Buy").description(market_data_err(&ticker)).color(data::EMBED_ERROR),
@@ -48,8 +81,15 @@ pub async fn options_buy(
         return Ok(());
     };
 
-    let intrinsic = option_intrinsic(opt_type, price_usd, strike);
-    let total_cost = option_premium_creds(intrinsic, &expiry_dt, contracts);
+    if !is_listed_strike(price_usd, strike) {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Buy").description(unlisted_strike_err(price_usd, strike)).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+    let total_cost = option_premium_creds(opt_type, style, price_usd, strike, &expiry_dt, contracts, inputs);
     let cost_per_contract = total_cost / f64::from(contracts);
 
     let data_ref = &ctx.data().users;
@@ -66,52 +106,48 @@ pub async fn options_buy(
             return Ok(());
         }
     };
+    if user_data.stock.portfolios[port_idx].club.is_some() {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Buy").description(ERR_CLUB_OPTIONS).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
 
-    if user_data.stock.portfolios[port_idx].cash < total_cost {
+    let fees = COST_MODEL.option_fees(total_cost);
+    if user_data.stock.portfolios[port_idx].cash < total_cost + fees.total() {
         let cash = user_data.stock.portfolios[port_idx].cash;
         drop(user_data);
         ctx.send(poise::CreateReply::default().embed(
             serenity::CreateEmbed::new()
                 .title("Options Buy")
                 .description(format!(
-                    "Insufficient cash. Need **${:.2}** ({:.0} creds) but **{}** has **${:.2}** ({:.0} creds).",
-                    creds_to_price(total_cost), total_cost, portfolio, creds_to_price(cash), cash
+                    "Insufficient cash. Need **${:.2}** ({:.0} creds, incl. fees) but **{}** has **${:.2}** ({:.0} creds).",
+                    creds_to_price(total_cost + fees.total()), total_cost + fees.total(), portfolio, creds_to_price(cash), cash
                 ))
                 .color(data::EMBED_ERROR),
         )).await?;
         return Ok(());
     }
 
-    {
+    // Each purchase is its own position with its own id, so lots bought at different prices stay apart
+    let contract_id = {
         let port = &mut user_data.stock.portfolios[port_idx];
-        port.cash -= total_cost;
-        let quantity = f64::from(contracts);
-        let existing_idx = find_option_idx(&port.positions, &ticker, strike, expiry_dt, opt_type, &OptionSide::Long);
-
-        if let Some(idx) = existing_idx {
-            let pos = &mut port.positions[idx];
-            let total_q = pos.quantity + quantity;
-            pos.avg_cost = pos.avg_cost.mul_add(pos.quantity, cost_per_contract * quantity) / total_q;
-            pos.quantity = total_q;
-            if let AssetType::Option(c) = &mut pos.asset_type {
-                c.contracts += contracts;
-            }
-        } else {
-            port.positions.push(Position {
-                ticker: ticker.clone(),
-                asset_type: AssetType::Option(OptionContract {
-                    strike,
-                    expiry: expiry_dt,
-                    option_type: opt_type,
-                    contracts,
-                    side: OptionSide::Long,
-                    collateral: 0.0,
-                }),
-                quantity,
-                avg_cost: cost_per_contract,
-            });
-        }
-    }
+        port.cash -= total_cost + fees.total();
+        port.push_option(&ticker, OptionContract {
+            strike,
+            expiry: expiry_dt,
+            option_type: opt_type,
+            contracts,
+            side: OptionSide::Long,
+            collateral: 0.0,
+            strategy: None,
+            assignment_checked: None,
+            settlement: SettlementMode::Cash,
+            style,
+            id: String::new(),
+        }, f64::from(contracts), cost_per_contract)
+    };
 
     let type_str = option_type_str(opt_type);
     user_data.stock.push_trade(TradeRecord {
@@ -124,6 +160,9 @@ pub async fn options_buy(
         total_creds: total_cost,
         realized_pnl: None,
         timestamp: Utc::now(),
+        fees,
+        lots: Vec::new(),
+        option_leg: true,
     });
     drop(user_data);
 
@@ -131,8 +170,9 @@ pub async fn options_buy(
         serenity::CreateEmbed::new()
             .title("Options Buy")
             .description(format!(
-                "Bought **{} {} ${:.2}** exp {} — {} contracts\nCost: **${:.2}** ({:.0} creds)",
-                ticker, type_str, strike, expiry, contracts, creds_to_price(total_cost), total_cost
+                "Bought **{} {} ${:.2}** exp {} ({}) — {} contracts\nCost: **${:.2}** ({:.0} creds) | Fees: **${:.2}**\nContract ID: `{}`",
+                ticker, type_str, strike, expiry, style.label(), contracts, creds_to_price(total_cost), total_cost,
+                creds_to_price(fees.total()), contract_id
             ))
             .color(data::EMBED_SUCCESS)
             .footer(default_footer()),
@@ -144,10 +184,9 @@ pub async fn options_buy(
 #[poise::command(slash_command)]
 pub async fn options_sell(
     ctx: Context<'_>,
-    #[description = "Underlying ticker (e.g. AAPL)"] ticker: String,
-    #[description = "Strike price in USD"] strike: f64,
-    #[description = "Expiry date (YYYY-MM-DD)"] expiry: String,
-    #[description = "Call or Put"] option_type: OptionType,
+    #[description = "Contract ID shown in /portfolio (e.g. AAPL260116C00150000)"]
+    #[autocomplete = "autocomplete_long_option"]
+    contract: String,
     #[description = "Number of contracts to sell"] contracts: u32,
     #[description = "Portfolio to sell from"] portfolio: String,
 ) -> Result<(), Error> {
@@ -158,15 +197,16 @@ pub async fn options_sell(
         return Ok(());
     }
 
-    let opt_type = option_type;
-    let expiry_dt = if let Some(d) = parse_expiry(&expiry) { d } else {
-        ctx.send(poise::CreateReply::default().embed(
-            serenity::CreateEmbed::new().title("Options Sell").description(ERR_INVALID_EXPIRY).color(data::EMBED_ERROR),
-        )).await?;
-        return Ok(());
+    let HeldOption { ticker, contract: held } = match held_option(ctx, &portfolio, &contract, &OptionSide::Long).await {
+        Ok(h) => h,
+        Err(e) => {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Options Sell").description(e).color(data::EMBED_ERROR),
+            )).await?;
+            return Ok(());
+        }
     };
 
-    let ticker = ticker.to_uppercase();
     let price_usd = if let Some(p) = fetch_price(&ticker).await { p } else {
         ctx.send(poise::CreateReply::default().embed(
             serenity::CreateEmbed::new().title("Options Sell").description(market_data_err(&ticker)).color(data::EMBED_ERROR),
@@ -174,48 +214,36 @@ pub async fn options_sell(
         return Ok(());
     };
 
-    let intrinsic = option_intrinsic(opt_type, price_usd, strike);
-    let total_proceeds = option_premium_creds(intrinsic, &expiry_dt, contracts);
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+    let total_proceeds = option_premium_creds(held.option_type, held.style, price_usd, held.strike, &held.expiry, contracts, inputs);
     let proceeds_per_contract = total_proceeds / f64::from(contracts);
 
     let data_ref = &ctx.data().users;
     let u = data_ref.get(&ctx.author().id).unwrap();
     let mut user_data = u.write().await;
 
-    let port_idx = match user_data.stock.find_portfolio_idx(&portfolio) {
-        Some(i) => i,
-        None => {
-            drop(user_data);
-            ctx.send(poise::CreateReply::default().embed(
-                serenity::CreateEmbed::new().title("Options Sell").description(format!("No portfolio named **{portfolio}** found.")).color(data::EMBED_ERROR),
-            )).await?;
-            return Ok(());
-        }
-    };
-
-    let pos_idx = match find_option_idx(&user_data.stock.portfolios[port_idx].positions, &ticker, strike, expiry_dt, opt_type, &OptionSide::Long) {
-        Some(i) => i,
-        None => {
-            let type_str = option_type_str(opt_type);
-            drop(user_data);
-            ctx.send(poise::CreateReply::default().embed(
-                serenity::CreateEmbed::new()
-                    .title("Options Sell")
-                    .description(format!("No **{ticker} {type_str} ${strike:.2}** exp {expiry} in portfolio **{portfolio}**."))
-                    .color(data::EMBED_ERROR),
-            )).await?;
-            return Ok(());
-        }
+    // Re-find by id: the position may have moved or closed while the price was fetched
+    let located = user_data.stock.find_portfolio_idx(&portfolio)
+        .and_then(|pi| user_data.stock.portfolios[pi].option_idx(&held.id).map(|i| (pi, i)));
+    let Some((port_idx, pos_idx)) = located else {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title("Options Sell")
+                .description(format!("**{}** is no longer held in portfolio **{portfolio}**.", held.id))
+                .color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
     };
 
-    let held = if let AssetType::Option(c) = &user_data.stock.portfolios[port_idx].positions[pos_idx].asset_type { c.contracts } else { 0 };
+    let held_contracts = if let AssetType::Option(c) = &user_data.stock.portfolios[port_idx].positions[pos_idx].asset_type { c.contracts } else { 0 };
 
-    if contracts > held {
+    if contracts > held_contracts {
         drop(user_data);
         ctx.send(poise::CreateReply::default().embed(
             serenity::CreateEmbed::new()
                 .title("Options Sell")
-                .description(format!("You only hold **{held}** contracts but tried to sell **{contracts}**."))
+                .description(format!("You only hold **{held_contracts}** contracts but tried to sell **{contracts}**."))
                 .color(data::EMBED_ERROR),
         )).await?;
         return Ok(());
@@ -224,11 +252,12 @@ pub async fn options_sell(
     let avg_cost = user_data.stock.portfolios[port_idx].positions[pos_idx].avg_cost;
     let pnl = avg_cost.mul_add(-f64::from(contracts), total_proceeds);
 
+    let fees = COST_MODEL.option_fees(total_proceeds);
     {
         let port = &mut user_data.stock.portfolios[port_idx];
-        port.cash += total_proceeds;
+        port.cash += total_proceeds - fees.total();
 
-        if contracts == held {
+        if contracts == held_contracts {
             port.positions.remove(pos_idx);
         } else {
             let pos = &mut port.positions[pos_idx];
@@ -239,7 +268,9 @@ pub async fn options_sell(
         }
     }
 
-    let type_str = option_type_str(opt_type);
+    let type_str = option_type_str(held.option_type);
+    let strike = held.strike;
+    let expiry = held.expiry.format("%Y-%m-%d");
     user_data.stock.push_trade(TradeRecord {
         portfolio: portfolio.clone(),
         ticker: ticker.clone(),
@@ -250,6 +281,9 @@ pub async fn options_sell(
         total_creds: total_proceeds,
         realized_pnl: Some(pnl),
         timestamp: Utc::now(),
+        fees,
+        lots: Vec::new(),
+        option_leg: true,
     });
 
     let pnl_str = crate::helper::fmt_pnl(pnl);
@@ -260,8 +294,8 @@ pub async fn options_sell(
         serenity::CreateEmbed::new()
             .title("Options Sell")
             .description(format!(
-                "Sold **{} {} ${:.2}** exp {} — {} contracts\nProceeds: **${:.2}** | P&L: **{}**",
-                ticker, type_str, strike, expiry, contracts, creds_to_price(total_proceeds), pnl_str
+                "Sold **{} {} ${:.2}** exp {} — {} contracts\nProceeds: **${:.2}** | Fees: **${:.2}** | P&L: **{}**",
+                ticker, type_str, strike, expiry, contracts, creds_to_price(total_proceeds), creds_to_price(fees.total()), pnl_str
             ))
             .color(color)
             .footer(default_footer()),
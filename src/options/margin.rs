@@ -0,0 +1,218 @@
+//! Mark-to-market margin for naked short options — pure functions, no Discord concerns.
+//! Requirements are re-evaluated from live prices with `naked_margin_usd`; locked collateral
+//! follows them down (released) and up (topped up from free cash). Whatever free cash can't
+//! cover is the shortfall behind a margin call.
+
+use super::engine::{
+    naked_margin_usd, option_premium_creds, option_price_usd, years_to_expiry, PricingInputs, OPTION_MAINTENANCE_RATIO,
+    SHARES_PER_CONTRACT,
+};
+use crate::data::{AssetType, OptionSide, Portfolio, TradeAction, TradeRecord, TRADE_HISTORY_LIMIT};
+use crate::helper::{option_type_str, price_to_creds};
+use crate::trader::CostModel;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+
+/// Outcome of re-marking a portfolio's naked shorts, amounts in creds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarginReview {
+    /// Collateral unlocked because requirements fell.
+    pub released: f64,
+    /// Free cash newly locked because requirements rose.
+    pub topped_up: f64,
+    /// Requirement left uncovered after topping up.
+    pub shortfall: f64,
+    /// Positions still short of their requirement.
+    pub underfunded: Vec<usize>,
+    /// Positions whose collateral covers less than `OPTION_MAINTENANCE_RATIO` of the requirement.
+    pub breached: Vec<usize>,
+}
+
+/// True for standalone short options carrying margin collateral.
+pub fn is_naked_short(pos: &crate::data::Position) -> bool {
+    matches!(&pos.asset_type, AssetType::Option(c) if c.side == OptionSide::Short && c.strategy.is_none() && c.collateral > 0.0)
+}
+
+/// Re-evaluates every naked short in `port` at `marks` (ticker → spot and pricing inputs)
+/// and moves collateral to match. Returns `None`, changing nothing, if any can't be priced.
+pub fn review_naked_margin(
+    port: &mut Portfolio,
+    marks: &HashMap<String, (f64, PricingInputs)>,
+    now: DateTime<Utc>,
+) -> Option<MarginReview> {
+    let mut requirements = Vec::new();
+    for (idx, pos) in port.positions.iter().enumerate() {
+        if !is_naked_short(pos) {
+            continue;
+        }
+        let AssetType::Option(c) = &pos.asset_type else { continue };
+        let &(spot, inputs) = marks.get(&pos.ticker)?;
+        let premium_usd = option_price_usd(c.option_type, c.style, spot, c.strike, years_to_expiry(&c.expiry, now), inputs)
+            * SHARES_PER_CONTRACT * f64::from(c.contracts);
+        let required = price_to_creds(naked_margin_usd(c.option_type, spot, c.strike, c.contracts, premium_usd));
+        requirements.push((idx, required));
+    }
+
+    let mut review = MarginReview::default();
+    for &(idx, required) in &requirements {
+        if let AssetType::Option(c) = &mut port.positions[idx].asset_type {
+            if c.collateral > required {
+                review.released += c.collateral - required;
+                c.collateral = required;
+            }
+        }
+    }
+
+    let mut free = (port.cash - port.locked_cash()).max(0.0);
+    for &(idx, required) in &requirements {
+        let AssetType::Option(c) = &mut port.positions[idx].asset_type else { continue };
+        let add = (required - c.collateral).min(free).max(0.0);
+        c.collateral += add;
+        free -= add;
+        review.topped_up += add;
+
+        let missing = required - c.collateral;
+        if missing > 1e-6 {
+            review.shortfall += missing;
+            review.underfunded.push(idx);
+        }
+        if c.collateral < required * OPTION_MAINTENANCE_RATIO {
+            review.breached.push(idx);
+        }
+    }
+    Some(review)
+}
+
+/// Buys back the short option at `pos_idx` at its model price plus `costs`, releasing its
+/// collateral. Returns the gross realized P&L in creds.
+pub(crate) fn force_close_short(
+    port: &mut Portfolio,
+    history: &mut VecDeque<TradeRecord>,
+    pos_idx: usize,
+    spot: f64,
+    inputs: PricingInputs,
+    costs: &CostModel,
+) -> f64 {
+    let pos = port.positions.remove(pos_idx);
+    let AssetType::Option(c) = &pos.asset_type else { return 0.0 };
+    let cost_to_close = option_premium_creds(c.option_type, c.style, spot, c.strike, &c.expiry, c.contracts, inputs);
+    let pnl = pos.avg_cost.mul_add(pos.quantity, -cost_to_close);
+    let fees = costs.option_fees(cost_to_close);
+    port.cash -= cost_to_close + fees.total();
+
+    history.push_back(TradeRecord {
+        portfolio: port.name.clone(),
+        ticker: pos.ticker.clone(),
+        asset_name: format!(
+            "SHORT {} {} ${:.2} {} FORCED CLOSE",
+            pos.ticker, option_type_str(c.option_type), c.strike, c.expiry.format("%Y-%m-%d"),
+        ),
+        action: TradeAction::Buy,
+        quantity: pos.quantity,
+        price_per_unit: cost_to_close / pos.quantity.max(1.0),
+        total_creds: cost_to_close,
+        realized_pnl: Some(pnl),
+        timestamp: Utc::now(),
+        fees,
+        lots: Vec::new(),
+        option_leg: true,
+    });
+    if history.len() > TRADE_HISTORY_LIMIT {
+        history.pop_front();
+    }
+    pnl
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{ExerciseStyle, OptionContract, OptionType, Position, SettlementMode};
+    use crate::helper::creds_to_price;
+    use chrono::Duration;
+
+    const INPUTS: PricingInputs = PricingInputs { volatility: 0.3, risk_free_rate: 0.04 };
+
+    fn naked_put_port(cash_usd: f64, collateral_usd: f64) -> Portfolio {
+        let mut port = Portfolio::new("TestPort".to_string());
+        port.cash = price_to_creds(cash_usd);
+        port.positions.push(Position {
+            ticker: "AAPL".to_string(),
+            asset_type: AssetType::Option(OptionContract {
+                strike: 100.0,
+                expiry: Utc::now() + Duration::days(30),
+                option_type: OptionType::Put,
+                contracts: 1,
+                side: OptionSide::Short,
+                collateral: price_to_creds(collateral_usd),
+                strategy: None,
+                assignment_checked: None,
+                settlement: SettlementMode::Cash,
+                style: ExerciseStyle::American,
+                id: String::new(),
+            }),
+            quantity: 1.0,
+            avg_cost: price_to_creds(200.0),
+            lots: Vec::new(),
+        });
+        port
+    }
+
+    fn marks(spot: f64) -> HashMap<String, (f64, PricingInputs)> {
+        HashMap::from([("AAPL".to_string(), (spot, INPUTS))])
+    }
+
+    fn collateral(port: &Portfolio) -> f64 {
+        match &port.positions[0].asset_type {
+            AssetType::Option(c) => c.collateral,
+            _ => 0.0,
+        }
+    }
+
+    #[test]
+    fn rally_releases_collateral() {
+        let mut port = naked_put_port(5_000.0, 2_000.0);
+        let review = review_naked_margin(&mut port, &marks(130.0), Utc::now()).unwrap();
+        assert!(review.released > 0.0);
+        assert!(review.underfunded.is_empty());
+        assert!(collateral(&port) < price_to_creds(2_000.0));
+    }
+
+    #[test]
+    fn selloff_tops_up_from_free_cash() {
+        let mut port = naked_put_port(20_000.0, 1_000.0);
+        let review = review_naked_margin(&mut port, &marks(85.0), Utc::now()).unwrap();
+        assert!(review.topped_up > 0.0);
+        assert!(review.shortfall.abs() < 1e-6);
+        let spot_premium = option_price_usd(OptionType::Put, ExerciseStyle::American, 85.0, 100.0, 30.0 / 365.0, INPUTS) * SHARES_PER_CONTRACT;
+        let required = naked_margin_usd(OptionType::Put, 85.0, 100.0, 1, spot_premium);
+        assert!((creds_to_price(collateral(&port)) - required).abs() < 0.01);
+    }
+
+    #[test]
+    fn selloff_without_cash_is_a_breach() {
+        // All cash already locked as collateral
+        let mut port = naked_put_port(1_000.0, 1_000.0);
+        let review = review_naked_margin(&mut port, &marks(70.0), Utc::now()).unwrap();
+        assert!(review.shortfall > 0.0);
+        assert_eq!(review.underfunded, vec![0]);
+        assert_eq!(review.breached, vec![0]);
+
+        let cash_before = port.cash;
+        let mut history = VecDeque::new();
+        let pnl = force_close_short(&mut port, &mut history, 0, 70.0, INPUTS, &CostModel::DEFAULT);
+        assert!(pnl < 0.0);
+        assert!(port.positions.is_empty());
+        assert_eq!(history.len(), 1);
+        // The buy-back pays the option spread and commission on top of its premium
+        let fees = history[0].fees.total();
+        assert!(fees > CostModel::DEFAULT.commission_creds);
+        assert!((port.cash - (cash_before - history[0].total_creds - fees)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn unpriced_ticker_changes_nothing() {
+        let mut port = naked_put_port(5_000.0, 2_000.0);
+        assert!(review_naked_margin(&mut port, &HashMap::new(), Utc::now()).is_none());
+        assert!((collateral(&port) - price_to_creds(2_000.0)).abs() < 1e-9);
+    }
+}
@@ -1,15 +1,33 @@
-//! Options trading module — quote, long positions, and short positions.
+//! Options trading module — quote, chain, long and short positions, multi-leg strategies, and exercise.
 
+mod chain;
+mod combo;
 mod engine;
+mod exercise;
+mod held;
 mod long;
+mod margin;
+mod payoff;
 mod quote;
+mod settlement;
 mod short;
+mod strategy;
 
 // Re-export commands for main.rs registration
+#[doc(inline)] pub use chain::options_chain;
+#[doc(inline)] pub use combo::{options_close_strategy, options_strategy};
+#[doc(inline)] pub use exercise::options_exercise;
 #[doc(inline)] pub use long::{options_buy, options_sell};
 #[doc(inline)] pub use quote::options_quote;
 #[doc(inline)] pub use short::{options_cover, options_write};
 
-// Re-export engine functions used externally (trader/portfolio.rs)
+// Re-export engine functions used externally (trader/portfolio.rs, api.rs)
 #[expect(unused_imports, reason = "option_premium_creds used by trader/portfolio.rs; others exported for completeness")]
-#[doc(inline)] pub use engine::{find_option_idx, naked_margin_usd, option_premium_creds, parse_expiry};
+#[doc(inline)] pub use engine::{naked_margin_usd, option_premium_creds, parse_expiry};
+#[doc(inline)] pub use engine::{historical_volatility, position_greeks, years_to_expiry, Greeks, PricingInputs, TRADING_DAYS_PER_YEAR};
+#[doc(inline)] pub use settlement::{early_assignment_probability, next_ex_dividend, EARLY_ASSIGNMENT_WINDOW_DAYS};
+#[doc(inline)] pub(crate) use settlement::exercise_position;
+#[doc(inline)] pub use payoff::{payoff_summary, render_payoff_png, PayoffLeg, PAYOFF_FILENAME};
+#[doc(inline)] pub use margin::{is_naked_short, review_naked_margin};
+#[doc(inline)] pub(crate) use margin::force_close_short;
+#[doc(inline)] pub(crate) use combo::{fmt_bound, fmt_leg};
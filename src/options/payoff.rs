@@ -0,0 +1,173 @@
+//! Payoff diagrams — P&L at expiry for one or more option legs, rendered to PNG.
+//! Pure functions, no Discord concerns. The image carries no text; prices and
+//! breakevens go in the embed alongside it.
+
+use super::engine::SHARES_PER_CONTRACT;
+use crate::data::{AssetType, OptionSide, OptionType, Position};
+use crate::helper::{creds_to_price, option_intrinsic, Canvas};
+
+/// Attachment name the embed image points at (`attachment://payoff.png`).
+pub const PAYOFF_FILENAME: &str = "payoff.png";
+const WIDTH: usize = 800;
+const HEIGHT: usize = 400;
+const PAD: usize = 24;
+
+const BACKGROUND: [u8; 3] = [0x2b, 0x2d, 0x31];
+const GRID: [u8; 3] = [0x3f, 0x42, 0x48];
+const AXIS: [u8; 3] = [0x9a, 0x9c, 0xa0];
+const STRIKE: [u8; 3] = [0x6d, 0x6f, 0x78];
+const SPOT: [u8; 3] = [0x00, 0xb0, 0xf4];
+const PROFIT: [u8; 3] = [0x57, 0xf2, 0x87];
+const LOSS: [u8; 3] = [0xed, 0x42, 0x45];
+
+/// One option leg as it pays at expiry. `premium_usd` is the total paid (long) or received (short).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PayoffLeg {
+    pub option_type: OptionType,
+    pub strike: f64,
+    pub contracts: u32,
+    pub long: bool,
+    pub premium_usd: f64,
+}
+
+impl PayoffLeg {
+    /// The leg held by an option position, with its cost basis as the premium.
+    pub fn from_position(pos: &Position) -> Option<Self> {
+        let AssetType::Option(c) = &pos.asset_type else { return None };
+        Some(Self {
+            option_type: c.option_type,
+            strike: c.strike,
+            contracts: c.contracts,
+            long: c.side == OptionSide::Long,
+            premium_usd: creds_to_price(pos.avg_cost * pos.quantity),
+        })
+    }
+
+    fn pnl_at(&self, spot: f64) -> f64 {
+        let value = option_intrinsic(self.option_type, spot, self.strike) * SHARES_PER_CONTRACT * f64::from(self.contracts);
+        if self.long { value - self.premium_usd } else { self.premium_usd - value }
+    }
+}
+
+/// Combined P&L in USD at expiry with the underlying at `spot`.
+pub fn payoff_usd(legs: &[PayoffLeg], spot: f64) -> f64 {
+    legs.iter().map(|l| l.pnl_at(spot)).sum()
+}
+
+/// Underlying price range the chart spans: every strike and `spot`, with a 25% margin either side.
+pub fn chart_range(legs: &[PayoffLeg], spot: f64) -> (f64, f64) {
+    let prices = legs.iter().map(|l| l.strike).chain(std::iter::once(spot));
+    let lo = prices.clone().fold(f64::INFINITY, f64::min);
+    let hi = prices.fold(f64::NEG_INFINITY, f64::max);
+    ((lo * 0.75).max(0.0), hi * 1.25)
+}
+
+/// Underlying prices between `lo` and `hi` where the combined P&L crosses zero.
+pub fn breakevens(legs: &[PayoffLeg], lo: f64, hi: f64) -> Vec<f64> {
+    const STEPS: usize = 2_000;
+    let price = |i: usize| (hi - lo).mul_add(i as f64 / STEPS as f64, lo);
+    let mut found: Vec<f64> = Vec::new();
+    let mut prev = (price(0), payoff_usd(legs, price(0)));
+    for i in 1..=STEPS {
+        let cur = (price(i), payoff_usd(legs, price(i)));
+        if (prev.1 < 0.0) != (cur.1 < 0.0) {
+            let x = (cur.0 - prev.0).mul_add(-prev.1 / (cur.1 - prev.1), prev.0);
+            if found.last().is_none_or(|f| (x - f).abs() > (hi - lo) / 100.0) {
+                found.push(x);
+            }
+        }
+        prev = cur;
+    }
+    found
+}
+
+/// One-line caption for the chart: breakevens and the P&L if expiry were at `spot`.
+pub fn payoff_summary(legs: &[PayoffLeg], spot: f64) -> String {
+    let (lo, hi) = chart_range(legs, spot);
+    let be: Vec<String> = breakevens(legs, lo, hi).iter().map(|b| format!("${b:.2}")).collect();
+    let at_spot = payoff_usd(legs, spot);
+    format!(
+        "Breakeven: **{}** | At ${spot:.2}: **{}${:.2}**",
+        if be.is_empty() { "none".to_string() } else { be.join(", ") },
+        if at_spot < 0.0 { "-" } else { "+" },
+        at_spot.abs(),
+    )
+}
+
+/// Renders the P&L-at-expiry curve of `legs` with strike and spot markers as PNG bytes.
+pub fn render_payoff_png(legs: &[PayoffLeg], spot: f64) -> Option<Vec<u8>> {
+    let (lo, hi) = chart_range(legs, spot);
+    let plot_w = WIDTH - 2 * PAD;
+    let plot_h = HEIGHT - 2 * PAD;
+    let price_at = |x: usize| (hi - lo).mul_add((x - PAD) as f64 / (plot_w - 1) as f64, lo);
+    let x_at = |price: f64| PAD + (((price - lo) / (hi - lo)) * (plot_w - 1) as f64).round() as usize;
+
+    let pnl: Vec<f64> = (PAD..PAD + plot_w).map(|x| payoff_usd(legs, price_at(x))).collect();
+    let mut y_min = pnl.iter().copied().fold(0.0, f64::min);
+    let mut y_max = pnl.iter().copied().fold(0.0, f64::max);
+    let span = (y_max - y_min).max(1.0);
+    y_min -= span * 0.1;
+    y_max += span * 0.1;
+    let y_at = |v: f64| PAD + (((y_max - v) / (y_max - y_min)) * (plot_h - 1) as f64).round() as usize;
+
+    let mut canvas = Canvas::new(WIDTH, HEIGHT, BACKGROUND);
+    for i in 0..=4 {
+        canvas.hline(PAD, PAD + plot_w, PAD + i * (plot_h - 1) / 4, GRID);
+    }
+    for leg in legs {
+        canvas.dashed_vline(x_at(leg.strike), PAD, PAD + plot_h, STRIKE);
+    }
+    let zero_y = y_at(0.0);
+
+    // Shade between the curve and zero, then draw the curve itself
+    for (i, &v) in pnl.iter().enumerate() {
+        let color = if v >= 0.0 { PROFIT } else { LOSS };
+        canvas.vline_blend(PAD + i, zero_y, y_at(v), color, 0.18);
+    }
+    canvas.hline(PAD, PAD + plot_w, zero_y, AXIS);
+    canvas.vline(x_at(spot), PAD, PAD + plot_h, SPOT);
+    let mut prev_y = y_at(pnl[0]);
+    for (i, &v) in pnl.iter().enumerate() {
+        let y = y_at(v);
+        let color = if v >= 0.0 { PROFIT } else { LOSS };
+        canvas.vline(PAD + i, prev_y.min(y).saturating_sub(1), prev_y.max(y) + 1, color);
+        prev_y = y;
+    }
+
+    canvas.encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(strike: f64, long: bool, premium_usd: f64) -> PayoffLeg {
+        PayoffLeg { option_type: OptionType::Call, strike, contracts: 1, long, premium_usd }
+    }
+
+    #[test]
+    fn long_call_payoff_and_breakeven() {
+        let legs = [call(100.0, true, 500.0)];
+        assert!((payoff_usd(&legs, 90.0) + 500.0).abs() < 1e-9);
+        assert!((payoff_usd(&legs, 120.0) - 1_500.0).abs() < 1e-9);
+        let (lo, hi) = chart_range(&legs, 100.0);
+        let be = breakevens(&legs, lo, hi);
+        assert_eq!(be.len(), 1);
+        assert!((be[0] - 105.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn spread_has_two_sided_payoff() {
+        // Bull call 100/110 for $4 debit, net of the short leg's premium
+        let legs = [call(100.0, true, 600.0), call(110.0, false, 200.0)];
+        assert!((payoff_usd(&legs, 80.0) + 400.0).abs() < 1e-9);
+        assert!((payoff_usd(&legs, 150.0) - 600.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn renders_png() {
+        let legs = [call(100.0, true, 500.0), call(110.0, false, 200.0)];
+        let png = render_payoff_png(&legs, 104.0).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}
@@ -1,13 +1,17 @@
-//! `/options_quote` command
+//! `/options_quote` command — theoretical price, Greeks, and payoff diagram
 
-use super::engine::{parse_expiry, ERR_EXPIRY_PAST, ERR_INVALID_EXPIRY, SHARES_PER_CONTRACT, TIME_VALUE_PER_DTE};
-use crate::api::{fetch_price, market_data_err};
-use crate::data::{self, OptionType};
+use super::engine::{
+    is_index_proxy, option_greeks, option_premium_creds, parse_expiry, years_to_expiry, ERR_EUROPEAN_UNDERLYING, ERR_EXPIRY_PAST, ERR_INVALID_EXPIRY,
+    SHARES_PER_CONTRACT,
+};
+use super::payoff::{payoff_summary, render_payoff_png, PayoffLeg, PAYOFF_FILENAME};
+use crate::api::{fetch_price, market_data_err, option_pricing_inputs};
+use crate::data::{self, ExerciseStyle, OptionType};
 use crate::{serenity, Context, Error};
-use crate::helper::{default_footer, option_intrinsic, option_type_str, price_to_creds};
+use crate::helper::{creds_to_price, default_footer, option_intrinsic, option_type_str};
 use chrono::Utc;
 
-/// Get the intrinsic value of an options contract
+/// Get the theoretical price and Greeks of an options contract
 #[poise::command(slash_command)]
 pub async fn options_quote(
     ctx: Context<'_>,
@@ -15,8 +19,10 @@ pub async fn options_quote(
     #[description = "Strike price in USD"] strike: f64,
     #[description = "Expiry date (YYYY-MM-DD)"] expiry: String,
     #[description = "Call or Put"] option_type: OptionType,
+    #[description = "American (default) or European — European is listed on index ETFs only"] style: Option<ExerciseStyle>,
 ) -> Result<(), Error> {
     let opt_type = option_type;
+    let style = style.unwrap_or_default();
 
     let expiry_dt = if let Some(d) = parse_expiry(&expiry) { d } else {
         ctx.send(poise::CreateReply::default().embed(
@@ -41,27 +47,46 @@ pub async fn options_quote(
         return Ok(());
     };
 
+    if style == ExerciseStyle::European && !is_index_proxy(&ticker) {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Quote").description(ERR_EUROPEAN_UNDERLYING).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
     let intrinsic = option_intrinsic(opt_type, price_usd, strike);
     let dte = (expiry_dt - Utc::now()).num_days().max(0);
-    let time_value_usd = dte as f64 * TIME_VALUE_PER_DTE;
-    let premium_per_contract_usd = (intrinsic + time_value_usd).max(0.01) * SHARES_PER_CONTRACT;
-    let premium_creds = price_to_creds(premium_per_contract_usd);
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+    let premium_creds = option_premium_creds(opt_type, style, price_usd, strike, &expiry_dt, 1, inputs);
+    let premium_per_contract_usd = creds_to_price(premium_creds);
+    let time_value_usd = (premium_per_contract_usd / SHARES_PER_CONTRACT - intrinsic).max(0.0);
+    let greeks = option_greeks(opt_type, price_usd, strike, years_to_expiry(&expiry_dt, Utc::now()), inputs);
     let itm = intrinsic > 0.0;
     let type_str = option_type_str(opt_type);
 
-    ctx.send(poise::CreateReply::default().embed(
-        serenity::CreateEmbed::new()
-            .title("Options Quote")
-            .description(format!(
-                "**{} {} ${:.2}** exp {} ({} DTE)\n\nUnderlying: **${:.2}**\nIntrinsic: **${:.2}/contract** | Time value: **${:.2}/contract**\nPremium: **${:.2}/contract** ({:.0} creds)\nStatus: **{}**",
-                ticker, type_str, strike, expiry, dte,
-                price_usd,
-                intrinsic * SHARES_PER_CONTRACT, time_value_usd * SHARES_PER_CONTRACT,
-                premium_per_contract_usd, premium_creds,
-                if itm { "In The Money (ITM)" } else { "Out of The Money (OTM)" }
-            ))
-            .color(if itm { data::EMBED_SUCCESS } else { data::EMBED_ERROR })
-            .footer(default_footer()),
-    )).await?;
+    let leg = PayoffLeg { option_type: opt_type, strike, contracts: 1, long: true, premium_usd: premium_per_contract_usd };
+    let mut embed = serenity::CreateEmbed::new()
+        .title("Options Quote")
+        .description(format!(
+            "**{} {} ${:.2}** exp {} ({} DTE, {})\n\nUnderlying: **${:.2}**\nIntrinsic: **${:.2}/contract** | Time value: **${:.2}/contract**\nPremium: **${:.2}/contract** ({:.0} creds)\nVolatility: **{:.1}%** | Risk-free rate: **{:.2}%**\n\n**Greeks** (per contract)\nΔ **{:+.3}** ({:+.0} sh) | Γ **{:.4}** | Θ **${:+.2}**/day | ν **${:.2}**/vol pt | ρ **${:+.2}**/rate pt\n\nStatus: **{}**\n\n**Payoff at expiry** (1 long contract)\n{}",
+            ticker, type_str, strike, expiry, dte, style.label(),
+            price_usd,
+            intrinsic * SHARES_PER_CONTRACT, time_value_usd * SHARES_PER_CONTRACT,
+            premium_per_contract_usd, premium_creds,
+            inputs.volatility * 100.0, inputs.risk_free_rate * 100.0,
+            greeks.delta, greeks.delta * SHARES_PER_CONTRACT, greeks.gamma,
+            greeks.theta * SHARES_PER_CONTRACT, greeks.vega * SHARES_PER_CONTRACT, greeks.rho * SHARES_PER_CONTRACT,
+            if itm { "In The Money (ITM)" } else { "Out of The Money (OTM)" },
+            payoff_summary(&[leg], price_usd),
+        ))
+        .color(if itm { data::EMBED_SUCCESS } else { data::EMBED_ERROR })
+        .footer(default_footer());
+
+    let mut reply = poise::CreateReply::default();
+    if let Some(png) = render_payoff_png(&[leg], price_usd) {
+        embed = embed.image(format!("attachment://{PAYOFF_FILENAME}"));
+        reply = reply.attachment(serenity::CreateAttachment::bytes(png, PAYOFF_FILENAME));
+    }
+    ctx.send(reply.embed(embed)).await?;
     Ok(())
 }
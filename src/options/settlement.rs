@@ -0,0 +1,355 @@
+//! Physical settlement of exercised and assigned options, and the early-assignment model.
+//! Pure functions over `Portfolio` state, no Discord or async concerns.
+//! Shares move through `apply_buy`/`apply_sell`; the option premium folds into the
+//! stock's cost basis (or sale proceeds) rather than being booked as a separate P&L.
+
+use super::engine::{option_price_usd, PricingInputs, SHARES_PER_CONTRACT};
+use crate::data::{AssetType, ExerciseStyle, OptionContract, OptionSide, OptionType, Portfolio, TradeAction, TradeFees, TradeRecord};
+use crate::helper::{option_intrinsic, option_type_str, price_to_creds};
+use crate::trader::{apply_buy, apply_sell, CostModel};
+use chrono::{Duration, NaiveDate, Utc};
+use std::collections::VecDeque;
+
+/// Short calls are at risk of early assignment when an ex-dividend date falls within this many days.
+pub const EARLY_ASSIGNMENT_WINDOW_DAYS: i64 = 1;
+/// Highest chance a short call is assigned ahead of any one ex-dividend date.
+pub const MAX_EARLY_ASSIGNMENT_PROB: f64 = 0.9;
+/// Dividend spacing assumed when only one past payment is known (quarterly).
+const DEFAULT_DIVIDEND_INTERVAL_DAYS: i64 = 91;
+/// Payers whose last ex-dividend date is older than this are treated as having stopped.
+const STALE_DIVIDEND_DAYS: i64 = 400;
+
+/// What a physical settlement did to the portfolio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlement {
+    /// Shares received (positive) or delivered (negative).
+    pub shares: f64,
+    /// Net change in cash, in creds, including execution fees.
+    pub cash_delta: f64,
+    /// Realized P&L on shares delivered, premium included. `None` when shares were received.
+    pub realized_pnl: Option<f64>,
+}
+
+/// Chance a deep-ITM short call is assigned ahead of an ex-dividend date. Holders exercise
+/// when the dividend they'd collect is worth more than the time value they'd give up.
+pub fn early_assignment_probability(spot: f64, strike: f64, years: f64, inputs: PricingInputs, dividend_usd: f64) -> f64 {
+    let intrinsic = option_intrinsic(OptionType::Call, spot, strike);
+    if intrinsic <= 0.0 || dividend_usd <= 0.0 {
+        return 0.0;
+    }
+    let time_value = (option_price_usd(OptionType::Call, ExerciseStyle::American, spot, strike, years, inputs) - intrinsic).max(0.0);
+    if time_value >= dividend_usd {
+        return 0.0;
+    }
+    MAX_EARLY_ASSIGNMENT_PROB * (1.0 - time_value / dividend_usd)
+}
+
+/// Projects the next ex-dividend date on or after `today` from past ex-dates (any order),
+/// assuming the most recent spacing repeats. `None` for non-payers and stale payers.
+pub fn next_ex_dividend(past: &[NaiveDate], today: NaiveDate) -> Option<NaiveDate> {
+    let mut dates = past.to_vec();
+    dates.sort_unstable();
+    let last = *dates.last()?;
+    if (today - last).num_days() > STALE_DIVIDEND_DAYS {
+        return None;
+    }
+    let interval = match dates.as_slice() {
+        [.., prev, last] if last > prev => *last - *prev,
+        _ => Duration::days(DEFAULT_DIVIDEND_INTERVAL_DAYS),
+    };
+    let mut next = last;
+    while next < today {
+        next += interval;
+    }
+    Some(next)
+}
+
+/// Exercises (long) or assigns (short) `contracts` of the option at `pos_idx` into shares at
+/// the strike, removing the contracts, releasing their collateral, and logging the trades.
+/// Fails without changes if a long put can't deliver the shares it needs.
+pub(crate) fn exercise_position(
+    port: &mut Portfolio,
+    history: &mut VecDeque<TradeRecord>,
+    pos_idx: usize,
+    contracts: u32,
+    spot_usd: f64,
+    costs: &CostModel,
+) -> Result<Settlement, String> {
+    let pos = &port.positions[pos_idx];
+    let AssetType::Option(held) = &pos.asset_type else {
+        return Err("That position isn't an option.".to_string());
+    };
+    let ticker = pos.ticker.clone();
+    let mut contract = held.clone();
+    contract.contracts = contracts.min(held.contracts);
+    let premium = pos.avg_cost * f64::from(contract.contracts);
+
+    let settlement = settle_physical(port, history, &ticker, &contract, spot_usd, premium, costs)?;
+
+    // Locate the option again — settling may have added or removed stock positions
+    if let Some(idx) = port.option_idx(&contract.id) {
+        let remaining = match &mut port.positions[idx].asset_type {
+            AssetType::Option(c) => {
+                let fraction = f64::from(contract.contracts) / f64::from(c.contracts);
+                c.collateral -= c.collateral * fraction;
+                c.contracts -= contract.contracts;
+                c.contracts
+            }
+            _ => 0,
+        };
+        if remaining == 0 {
+            port.positions.remove(idx);
+        } else {
+            port.positions[idx].quantity = f64::from(remaining);
+        }
+    }
+
+    // The contract itself retires at no cost; the share legs above carry the execution costs
+    let is_short = contract.side == OptionSide::Short;
+    history.push_back(TradeRecord {
+        portfolio: port.name.clone(),
+        ticker: ticker.clone(),
+        asset_name: format!(
+            "{}{ticker} {} ${:.2} {} {}",
+            if is_short { "SHORT " } else { "" },
+            option_type_str(contract.option_type),
+            contract.strike,
+            contract.expiry.format("%Y-%m-%d"),
+            if is_short { "ASSIGNED" } else { "EXERCISED" },
+        ),
+        action: if is_short { TradeAction::Buy } else { TradeAction::Sell },
+        quantity: f64::from(contract.contracts),
+        price_per_unit: 0.0,
+        total_creds: 0.0,
+        realized_pnl: None,
+        timestamp: Utc::now(),
+        fees: TradeFees::default(),
+        lots: Vec::new(),
+        option_leg: true,
+    });
+    if history.len() > crate::data::TRADE_HISTORY_LIMIT {
+        history.pop_front();
+    }
+    Ok(settlement)
+}
+
+/// Moves shares for `contract` at its strike. `premium` is the creds paid (long) or
+/// received (short) for these contracts. A short call without enough shares buys the
+/// shortfall at `spot_usd` before delivering.
+fn settle_physical(
+    port: &mut Portfolio,
+    history: &mut VecDeque<TradeRecord>,
+    ticker: &str,
+    contract: &OptionContract,
+    spot_usd: f64,
+    premium: f64,
+    costs: &CostModel,
+) -> Result<Settlement, String> {
+    let shares = f64::from(contract.contracts) * SHARES_PER_CONTRACT;
+    let strike = price_to_creds(contract.strike);
+    let name = port.name.clone();
+    let held = port.positions.iter()
+        .filter(|p| p.ticker == ticker && p.is_long_stock())
+        .map(|p| p.quantity)
+        .sum::<f64>();
+    let stock_type = port.positions.iter()
+        .find(|p| p.ticker == ticker && p.is_long_stock())
+        .map_or(AssetType::Stock, |p| p.asset_type.clone());
+    let cash_before = port.cash;
+    let is_long = contract.side == OptionSide::Long;
+
+    match contract.option_type {
+        // Long call exercised or short put assigned: receive shares at the strike
+        OptionType::Call if is_long => {
+            apply_buy(port, history, ticker, ticker, stock_type, shares, strike, strike * shares, &name, costs);
+            mark_option_leg(history);
+            adjust_new_lot_cost(port, ticker, premium / shares);
+            Ok(Settlement { shares, cash_delta: port.cash - cash_before, realized_pnl: None })
+        }
+        OptionType::Put if !is_long => {
+            apply_buy(port, history, ticker, ticker, stock_type, shares, strike, strike * shares, &name, costs);
+            mark_option_leg(history);
+            adjust_new_lot_cost(port, ticker, -premium / shares);
+            Ok(Settlement { shares, cash_delta: port.cash - cash_before, realized_pnl: None })
+        }
+        // Long put exercised or short call assigned: deliver shares at the strike
+        _ => {
+            if is_long && held + 5e-5 < shares {
+                return Err(format!(
+                    "Exercising needs **{shares:.0}** shares of **{ticker}** to deliver, but you hold **{held:.0}**.",
+                ));
+            }
+            let shortfall = shares - held;
+            if shortfall > 5e-5 {
+                let spot = price_to_creds(spot_usd);
+                apply_buy(port, history, ticker, ticker, stock_type, shortfall, spot, spot * shortfall, &name, costs);
+                mark_option_leg(history);
+            }
+            let gross = apply_sell(port, history, ticker, ticker, shares, strike, &name, costs).unwrap_or(0.0);
+            let pnl = if is_long { gross - premium } else { gross + premium };
+            if let Some(record) = history.back_mut() {
+                record.realized_pnl = Some(pnl);
+                record.option_leg = true;
+            }
+            Ok(Settlement { shares: -shares, cash_delta: port.cash - cash_before, realized_pnl: Some(pnl) })
+        }
+    }
+}
+
+/// Tags the share trade just recorded as part of an option settlement.
+fn mark_option_leg(history: &mut VecDeque<TradeRecord>) {
+    if let Some(record) = history.back_mut() {
+        record.option_leg = true;
+    }
+}
+
+/// Shifts the newest lot of the long `ticker` position by `per_share` creds and refreshes `avg_cost`.
+fn adjust_new_lot_cost(port: &mut Portfolio, ticker: &str, per_share: f64) {
+    let Some(pos) = port.positions.iter_mut().find(|p| p.ticker == ticker && p.is_long_stock()) else {
+        return;
+    };
+    if let Some(lot) = pos.lots.last_mut() {
+        lot.cost += per_share;
+    }
+    let lot_qty: f64 = pos.lots.iter().map(|l| l.quantity).sum();
+    if lot_qty > 0.0 {
+        pos.avg_cost = pos.lots.iter().map(|l| l.quantity * l.cost).sum::<f64>() / lot_qty;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Position, SettlementMode};
+
+    fn option_port(opt_type: OptionType, side: OptionSide, contracts: u32, premium_per_contract: f64) -> Portfolio {
+        let mut port = Portfolio::new("TestPort".to_string());
+        port.cash = price_to_creds(50_000.0);
+        port.positions.push(Position {
+            ticker: "AAPL".to_string(),
+            asset_type: AssetType::Option(OptionContract {
+                strike: 100.0,
+                expiry: Utc::now() + Duration::days(30),
+                option_type: opt_type,
+                contracts,
+                side,
+                collateral: 0.0,
+                strategy: None,
+                assignment_checked: None,
+                settlement: SettlementMode::Cash,
+                style: ExerciseStyle::American,
+                id: "AAPL-TEST".to_string(),
+            }),
+            quantity: f64::from(contracts),
+            avg_cost: premium_per_contract,
+            lots: Vec::new(),
+        });
+        port
+    }
+
+    fn stock(port: &Portfolio) -> Option<&Position> {
+        port.positions.iter().find(|p| p.is_long_stock())
+    }
+
+    #[test]
+    fn exercising_call_buys_shares_with_premium_in_basis() {
+        // 1 call, $5 premium paid (500 USD = 50,000 creds)
+        let mut port = option_port(OptionType::Call, OptionSide::Long, 1, 50_000.0);
+        let mut history = VecDeque::new();
+        let s = exercise_position(&mut port, &mut history, 0, 1, 120.0, &CostModel::FREE).unwrap();
+        assert_eq!(s.shares, 100.0);
+        assert!((s.cash_delta + price_to_creds(10_000.0)).abs() < 1e-6);
+        let pos = stock(&port).unwrap();
+        assert!((pos.avg_cost - price_to_creds(105.0)).abs() < 1e-6);
+        assert_eq!(port.positions.len(), 1, "option is gone");
+    }
+
+    #[test]
+    fn exercising_put_needs_shares_to_deliver() {
+        let mut port = option_port(OptionType::Put, OptionSide::Long, 1, 30_000.0);
+        let mut history = VecDeque::new();
+        assert!(exercise_position(&mut port, &mut history, 0, 1, 80.0, &CostModel::FREE).is_err());
+        assert_eq!(port.positions.len(), 1);
+        assert!(history.is_empty());
+
+        apply_buy(&mut port, &mut history, "AAPL", "AAPL", AssetType::Stock, 100.0, 9_000.0, 900_000.0, "TestPort", &CostModel::FREE);
+        let s = exercise_position(&mut port, &mut history, 0, 1, 80.0, &CostModel::FREE).unwrap();
+        // Sold at $100 against a $90 basis, less the $3 premium
+        assert!((s.realized_pnl.unwrap() - price_to_creds(700.0)).abs() < 1e-6);
+        assert!(port.positions.is_empty());
+    }
+
+    #[test]
+    fn assigned_naked_call_buys_then_delivers() {
+        // 2 short calls, $2 premium received each
+        let mut port = option_port(OptionType::Call, OptionSide::Short, 2, 20_000.0);
+        let mut history = VecDeque::new();
+        let s = exercise_position(&mut port, &mut history, 0, 1, 110.0, &CostModel::FREE).unwrap();
+        // Bought 100 at $110, delivered at $100, kept $200 premium
+        assert!((s.realized_pnl.unwrap() - price_to_creds(-800.0)).abs() < 1e-6);
+        assert!((s.cash_delta + price_to_creds(1_000.0)).abs() < 1e-6);
+        assert!(stock(&port).is_none());
+        let AssetType::Option(c) = &port.positions[0].asset_type else { panic!("option kept") };
+        assert_eq!(c.contracts, 1);
+    }
+
+    #[test]
+    fn assignment_retires_only_the_assigned_position() {
+        // Two separately written lots of the same contract
+        let mut port = option_port(OptionType::Call, OptionSide::Short, 1, 20_000.0);
+        let mut second = option_port(OptionType::Call, OptionSide::Short, 3, 20_000.0).positions;
+        if let AssetType::Option(c) = &mut second[0].asset_type {
+            c.id = "AAPL-TEST-2".to_string();
+        }
+        port.positions.extend(second);
+        let mut history = VecDeque::new();
+        exercise_position(&mut port, &mut history, 1, 3, 110.0, &CostModel::FREE).unwrap();
+        assert_eq!(port.positions.len(), 1);
+        let AssetType::Option(c) = &port.positions[0].asset_type else { panic!("option kept") };
+        assert_eq!((c.id.as_str(), c.contracts), ("AAPL-TEST", 1));
+    }
+
+    #[test]
+    fn assigned_put_lowers_basis_by_premium() {
+        let mut port = option_port(OptionType::Put, OptionSide::Short, 1, 40_000.0);
+        let mut history = VecDeque::new();
+        exercise_position(&mut port, &mut history, 0, 1, 90.0, &CostModel::FREE).unwrap();
+        assert!((stock(&port).unwrap().avg_cost - price_to_creds(96.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn wheel_put_then_covered_call_round_trip() {
+        // Assigned on a $100 put with $4 premium → basis $96
+        let mut port = option_port(OptionType::Put, OptionSide::Short, 1, 40_000.0);
+        let mut history = VecDeque::new();
+        exercise_position(&mut port, &mut history, 0, 1, 95.0, &CostModel::FREE).unwrap();
+
+        // Then called away on a $100 covered call with $2 premium
+        port.positions.extend(option_port(OptionType::Call, OptionSide::Short, 1, 20_000.0).positions);
+        let idx = port.positions.iter().position(|p| !p.is_long_stock()).unwrap();
+        let s = exercise_position(&mut port, &mut history, idx, 1, 104.0, &CostModel::FREE).unwrap();
+        // Delivered at $100 against $96 basis, plus $2 call premium → $6/share
+        assert!((s.realized_pnl.unwrap() - price_to_creds(600.0)).abs() < 1e-6);
+        assert!(port.positions.is_empty());
+    }
+
+    #[test]
+    fn early_assignment_only_when_dividend_beats_time_value() {
+        let inputs = PricingInputs { volatility: 0.25, risk_free_rate: 0.04 };
+        let years = 20.0 / 365.0;
+        assert_eq!(early_assignment_probability(95.0, 100.0, years, inputs, 1.0), 0.0); // OTM
+        assert_eq!(early_assignment_probability(102.0, 100.0, years, inputs, 0.25), 0.0); // time value > dividend
+        let deep = early_assignment_probability(150.0, 100.0, years, inputs, 1.0);
+        assert!(deep > 0.5 && deep <= MAX_EARLY_ASSIGNMENT_PROB);
+    }
+
+    #[test]
+    fn ex_dividend_projection() {
+        let d = |m, day| NaiveDate::from_ymd_opt(2026, m, day).unwrap();
+        let past = [d(5, 11), d(2, 9), d(8, 10)];
+        assert_eq!(next_ex_dividend(&past, d(8, 20)), Some(d(11, 9)));
+        assert_eq!(next_ex_dividend(&past, d(8, 10)), Some(d(8, 10)));
+        assert_eq!(next_ex_dividend(&[], d(8, 20)), None);
+        assert_eq!(next_ex_dividend(&[d(1, 5)], d(12, 1)), Some(d(1, 5) + Duration::days(91 * 4)));
+    }
+}
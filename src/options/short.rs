@@ -1,13 +1,16 @@
 //! `/options_write` and `/options_cover` — short-side (sell-to-open) options commands.
 
-use super::engine::{find_option_idx, naked_margin_usd, option_premium_creds, parse_expiry, ERR_EXPIRY_PAST, ERR_INVALID_EXPIRY, ERR_MIN_CONTRACTS, SHARES_PER_CONTRACT};
-use crate::api::{fetch_price, market_data_err};
-use crate::data::{self, AssetType, OptionContract, OptionSide, OptionType, TradeAction, TradeRecord, Position};
-use crate::helper::{creds_to_price, default_footer, option_intrinsic, option_type_str, price_to_creds};
+use super::engine::{is_index_proxy, is_listed_expiry, is_listed_strike, unlisted_strike_err, ERR_CLUB_OPTIONS, ERR_UNLISTED_EXPIRY, naked_margin_usd, option_premium_creds, parse_expiry, ERR_EUROPEAN_PHYSICAL, ERR_EUROPEAN_UNDERLYING, ERR_EXPIRY_PAST, ERR_INVALID_EXPIRY, ERR_MIN_CONTRACTS, SHARES_PER_CONTRACT};
+use super::held::{autocomplete_short_option, held_option, HeldOption};
+use crate::api::{fetch_price, market_data_err, option_pricing_inputs};
+use crate::data::{self, AssetType, ExerciseStyle, OptionContract, OptionSide, OptionType, TradeAction, TradeRecord, SettlementMode};
+use crate::helper::{creds_to_price, default_footer, option_type_str, price_to_creds};
+use crate::trader::COST_MODEL;
 use crate::{serenity, Context, Error};
 use chrono::Utc;
 
 /// Write (sell to open) a covered call or cash-secured put
+#[allow(clippy::too_many_arguments)] // each slash-command option is its own argument
 #[poise::command(slash_command)]
 pub async fn options_write(
     ctx: Context<'_>,
@@ -17,6 +20,24 @@ pub async fn options_write(
     #[description = "Call or Put"] option_type: OptionType,
     #[description = "Number of contracts to write (1 contract = 100 shares)"] contracts: u32,
     #[description = "Portfolio to write from"] portfolio: String,
+    #[description = "Settle ITM at expiry in cash (default) or by moving shares"] settlement: Option<SettlementMode>,
+    #[description = "American (default) or European — European is listed on index ETFs only"] style: Option<ExerciseStyle>,
+) -> Result<(), Error> {
+    write_to_open(ctx, ticker, strike, expiry, option_type, contracts, portfolio, settlement.unwrap_or_default(), style.unwrap_or_default()).await
+}
+
+/// Writes (sells to open) an option. Shared by `/options_write` and `/options_chain`.
+#[expect(clippy::too_many_arguments, reason = "mirrors the /options_write parameters")]
+pub(crate) async fn write_to_open(
+    ctx: Context<'_>,
+    ticker: String,
+    strike: f64,
+    expiry: String,
+    option_type: OptionType,
+    contracts: u32,
+    portfolio: String,
+    settlement: SettlementMode,
+    style: ExerciseStyle,
 ) -> Result<(), Error> {
     if contracts == 0 {
         ctx.send(poise::CreateReply::default().embed(
@@ -40,7 +61,28 @@ pub async fn options_write(
         return Ok(());
     }
 
+    if !is_listed_expiry(expiry_dt.date_naive(), Utc::now().date_naive()) {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Write").description(ERR_UNLISTED_EXPIRY).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
     let ticker = ticker.to_uppercase();
+    if style == ExerciseStyle::European {
+        let err = if is_index_proxy(&ticker) {
+            (settlement == SettlementMode::Physical).then_some(ERR_EUROPEAN_PHYSICAL)
+        } else {
+            Some(ERR_EUROPEAN_UNDERLYING)
+        };
+        if let Some(err) = err {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Options Write").description(err).color(data::EMBED_ERROR),
+            )).await?;
+            return Ok(());
+        }
+    }
+
     let price_usd = if let Some(p) = fetch_price(&ticker).await { p } else {
         ctx.send(poise::CreateReply::default().embed(
             serenity::CreateEmbed::new().title("Options Write").description(market_data_err(&ticker)).color(data::EMBED_ERROR),
@@ -48,8 +90,15 @@ pub async fn options_write(
         return Ok(());
     };
 
-    let intrinsic = option_intrinsic(opt_type, price_usd, strike);
-    let premium = option_premium_creds(intrinsic, &expiry_dt, contracts);
+    if !is_listed_strike(price_usd, strike) {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Write").description(unlisted_strike_err(price_usd, strike)).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+    let premium = option_premium_creds(opt_type, style, price_usd, strike, &expiry_dt, contracts, inputs);
     let premium_per_contract = premium / f64::from(contracts);
 
     let data_ref = &ctx.data().users;
@@ -66,6 +115,13 @@ pub async fn options_write(
             return Ok(());
         }
     };
+    if user_data.stock.portfolios[port_idx].club.is_some() {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Options Write").description(ERR_CLUB_OPTIONS).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
 
     let premium_usd = creds_to_price(premium);
     let mut collateral_locked = 0.0f64;
@@ -77,7 +133,7 @@ pub async fn options_write(
     match opt_type {
         OptionType::Call => {
             let shares_held = user_data.stock.portfolios[port_idx].positions.iter()
-                .filter(|p| p.ticker == ticker && !matches!(&p.asset_type, AssetType::Option(_)))
+                .filter(|p| p.ticker == ticker && p.is_long_stock())
                 .map(|p| p.quantity)
                 .sum::<f64>();
             let required = f64::from(contracts) * SHARES_PER_CONTRACT;
@@ -127,37 +183,37 @@ pub async fn options_write(
         }
     }
 
-    {
-        let port = &mut user_data.stock.portfolios[port_idx];
-        port.cash += premium;
-        let existing_idx = find_option_idx(&port.positions, &ticker, strike, expiry_dt, opt_type, &OptionSide::Short);
-
-        if let Some(idx) = existing_idx {
-            let pos = &mut port.positions[idx];
-            let total_q = pos.quantity + f64::from(contracts);
-            pos.avg_cost = pos.avg_cost.mul_add(pos.quantity, premium_per_contract * f64::from(contracts)) / total_q;
-            pos.quantity = total_q;
-            if let AssetType::Option(c) = &mut pos.asset_type {
-                c.contracts += contracts;
-                c.collateral += collateral_locked;
-            }
-        } else {
-            port.positions.push(Position {
-                ticker: ticker.clone(),
-                asset_type: AssetType::Option(OptionContract {
-                    strike,
-                    expiry: expiry_dt,
-                    option_type: opt_type,
-                    contracts,
-                    side: OptionSide::Short,
-                    collateral: collateral_locked,
-                }),
-                quantity: f64::from(contracts),
-                avg_cost: premium_per_contract,
-            });
-        }
+    if settlement == SettlementMode::Physical && collateral_locked > 0.0 {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title("Options Write")
+                .description("Physical settlement is only available for covered calls and cash-secured puts.")
+                .color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
     }
 
+    // Each write is its own position with its own id, so lots written at different prices stay apart
+    let fees = COST_MODEL.option_fees(premium);
+    let contract_id = {
+        let port = &mut user_data.stock.portfolios[port_idx];
+        port.cash += premium - fees.total();
+        port.push_option(&ticker, OptionContract {
+            strike,
+            expiry: expiry_dt,
+            option_type: opt_type,
+            contracts,
+            side: OptionSide::Short,
+            collateral: collateral_locked,
+            strategy: None,
+            assignment_checked: None,
+            settlement,
+            style,
+            id: String::new(),
+        }, f64::from(contracts), premium_per_contract)
+    };
+
     let type_str = option_type_str(opt_type);
     user_data.stock.push_trade(TradeRecord {
         portfolio: portfolio.clone(),
@@ -169,6 +225,9 @@ pub async fn options_write(
         total_creds: premium,
         realized_pnl: None,
         timestamp: Utc::now(),
+        fees,
+        lots: Vec::new(),
+        option_leg: true,
     });
     drop(user_data);
 
@@ -176,9 +235,9 @@ pub async fn options_write(
         serenity::CreateEmbed::new()
             .title("Options Write")
             .description(format!(
-                "Written **{}× {} {} ${:.2}** exp {}\nCollected **${:.2}** ({:.0} creds)",
-                contracts, ticker, type_str, strike, expiry,
-                creds_to_price(premium), premium
+                "Written **{}× {} {} ${:.2}** exp {} ({}, {})\nCollected **${:.2}** ({:.0} creds) | Fees: **${:.2}**\nContract ID: `{}`",
+                contracts, ticker, type_str, strike, expiry, style.label(), settlement.label(),
+                creds_to_price(premium), premium, creds_to_price(fees.total()), contract_id
             ))
             .color(data::EMBED_CYAN)
             .footer(default_footer()),
@@ -190,10 +249,9 @@ pub async fn options_write(
 #[poise::command(slash_command)]
 pub async fn options_cover(
     ctx: Context<'_>,
-    #[description = "Underlying ticker (e.g. AAPL)"] ticker: String,
-    #[description = "Strike price in USD"] strike: f64,
-    #[description = "Expiry date (YYYY-MM-DD)"] expiry: String,
-    #[description = "Call or Put"] option_type: OptionType,
+    #[description = "Contract ID shown in /portfolio (e.g. AAPL260116P00150000)"]
+    #[autocomplete = "autocomplete_short_option"]
+    contract: String,
     #[description = "Number of contracts to cover"] contracts: u32,
     #[description = "Portfolio to cover from"] portfolio: String,
 ) -> Result<(), Error> {
@@ -204,15 +262,16 @@ pub async fn options_cover(
         return Ok(());
     }
 
-    let opt_type = option_type;
-    let expiry_dt = if let Some(d) = parse_expiry(&expiry) { d } else {
-        ctx.send(poise::CreateReply::default().embed(
-            serenity::CreateEmbed::new().title("Options Cover").description(ERR_INVALID_EXPIRY).color(data::EMBED_ERROR),
-        )).await?;
-        return Ok(());
+    let HeldOption { ticker, contract: written } = match held_option(ctx, &portfolio, &contract, &OptionSide::Short).await {
+        Ok(h) => h,
+        Err(e) => {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Options Cover").description(e).color(data::EMBED_ERROR),
+            )).await?;
+            return Ok(());
+        }
     };
 
-    let ticker = ticker.to_uppercase();
     let price_usd = if let Some(p) = fetch_price(&ticker).await { p } else {
         ctx.send(poise::CreateReply::default().embed(
             serenity::CreateEmbed::new().title("Options Cover").description(market_data_err(&ticker)).color(data::EMBED_ERROR),
@@ -220,39 +279,29 @@ pub async fn options_cover(
         return Ok(());
     };
 
-    let intrinsic = option_intrinsic(opt_type, price_usd, strike);
-    let cost_to_close = option_premium_creds(intrinsic, &expiry_dt, contracts);
+    let inputs = option_pricing_inputs(&ticker, &ctx.data().hysa_fed_rate).await;
+    let cost_to_close = option_premium_creds(written.option_type, written.style, price_usd, written.strike, &written.expiry, contracts, inputs);
     let cost_per_contract = cost_to_close / f64::from(contracts);
+    let type_str = option_type_str(written.option_type);
+    let strike = written.strike;
+    let expiry = written.expiry.format("%Y-%m-%d");
 
     let data_ref = &ctx.data().users;
     let u = data_ref.get(&ctx.author().id).unwrap();
     let mut user_data = u.write().await;
 
-    let type_str = option_type_str(opt_type);
-
-    let port_idx = match user_data.stock.find_portfolio_idx(&portfolio) {
-        Some(i) => i,
-        None => {
-            drop(user_data);
-            ctx.send(poise::CreateReply::default().embed(
-                serenity::CreateEmbed::new().title("Options Cover").description(format!("No portfolio named **{portfolio}** found.")).color(data::EMBED_ERROR),
-            )).await?;
-            return Ok(());
-        }
-    };
-
-    let pos_idx = match find_option_idx(&user_data.stock.portfolios[port_idx].positions, &ticker, strike, expiry_dt, opt_type, &OptionSide::Short) {
-        Some(i) => i,
-        None => {
-            drop(user_data);
-            ctx.send(poise::CreateReply::default().embed(
-                serenity::CreateEmbed::new()
-                    .title("Options Cover")
-                    .description(format!("No SHORT **{ticker} {type_str} ${strike:.2}** exp {expiry} in portfolio **{portfolio}**."))
-                    .color(data::EMBED_ERROR),
-            )).await?;
-            return Ok(());
-        }
+    // Re-find by id: the position may have moved or closed while the price was fetched
+    let located = user_data.stock.find_portfolio_idx(&portfolio)
+        .and_then(|pi| user_data.stock.portfolios[pi].option_idx(&written.id).map(|i| (pi, i)));
+    let Some((port_idx, pos_idx)) = located else {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title("Options Cover")
+                .description(format!("**{}** is no longer held in portfolio **{portfolio}**.", written.id))
+                .color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
     };
 
     let (held, collateral_total) = if let AssetType::Option(c) = &user_data.stock.portfolios[port_idx].positions[pos_idx].asset_type {
@@ -278,14 +327,15 @@ pub async fn options_cover(
         port.cash - port.locked_cash() + collateral_to_release
     };
 
-    if available < cost_to_close {
+    let fees = COST_MODEL.option_fees(cost_to_close);
+    if available < cost_to_close + fees.total() {
         drop(user_data);
         ctx.send(poise::CreateReply::default().embed(
             serenity::CreateEmbed::new()
                 .title("Options Cover")
                 .description(format!(
-                    "Insufficient cash. Need **${:.2}** ({:.0} creds) but **{}** only has **${:.2}** ({:.0} creds) available.",
-                    creds_to_price(cost_to_close), cost_to_close,
+                    "Insufficient cash. Need **${:.2}** ({:.0} creds, incl. fees) but **{}** only has **${:.2}** ({:.0} creds) available.",
+                    creds_to_price(cost_to_close + fees.total()), cost_to_close + fees.total(),
                     portfolio, creds_to_price(available), available,
                 ))
                 .color(data::EMBED_ERROR),
@@ -299,7 +349,7 @@ pub async fn options_cover(
 
     {
         let port = &mut user_data.stock.portfolios[port_idx];
-        port.cash -= cost_to_close;
+        port.cash -= cost_to_close + fees.total();
 
         if contracts == held {
             port.positions.remove(pos_idx);
@@ -322,6 +372,9 @@ pub async fn options_cover(
         total_creds: cost_to_close,
         realized_pnl: Some(pnl),
         timestamp: Utc::now(),
+        fees,
+        lots: Vec::new(),
+        option_leg: true,
     });
 
     let pnl_str = crate::helper::fmt_pnl(pnl);
@@ -332,9 +385,9 @@ pub async fn options_cover(
         serenity::CreateEmbed::new()
             .title("Options Cover")
             .description(format!(
-                "Covered **{}× {} {} ${:.2}** exp {}\nCost to close: **${:.2}** | P&L: **{}**{}",
+                "Covered **{}× {} {} ${:.2}** exp {}\nCost to close: **${:.2}** | Fees: **${:.2}** | P&L: **{}**{}",
                 contracts, ticker, type_str, strike, expiry,
-                creds_to_price(cost_to_close), pnl_str,
+                creds_to_price(cost_to_close), creds_to_price(fees.total()), pnl_str,
                 crate::helper::fmt_pct_change(pnl, premium_received)
             ))
             .color(color)
@@ -0,0 +1,215 @@
+//! Multi-leg option strategies — leg construction, expiry payoff, and combo margin.
+//! Pure functions, no Discord concerns.
+
+use super::engine::{naked_margin_usd, SHARES_PER_CONTRACT};
+use crate::data::{OptionSide, OptionType, StrategyKind};
+use crate::helper::{creds_to_price, option_intrinsic, price_to_creds};
+use chrono::{DateTime, Utc};
+
+/// One leg of a strategy, one contract per strategy unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyLeg {
+    pub option_type: OptionType,
+    pub strike: f64,
+    pub expiry: DateTime<Utc>,
+    pub side: OptionSide,
+}
+
+impl StrategyLeg {
+    const fn new(option_type: OptionType, strike: f64, expiry: DateTime<Utc>, side: OptionSide) -> Self {
+        Self { option_type, strike, expiry, side }
+    }
+
+    const fn sign(&self) -> f64 {
+        match self.side {
+            OptionSide::Long => 1.0,
+            OptionSide::Short => -1.0,
+        }
+    }
+}
+
+/// Number of strikes each strategy takes, ascending.
+pub const fn strikes_required(kind: StrategyKind) -> usize {
+    match kind {
+        StrategyKind::LongStraddle | StrategyKind::ShortStraddle | StrategyKind::CallCalendar | StrategyKind::PutCalendar => 1,
+        StrategyKind::IronCondor => 4,
+        _ => 2,
+    }
+}
+
+/// Builds the legs of `kind` from ascending `strikes`. Calendars sell `expiry` and buy `far_expiry`.
+pub fn strategy_legs(
+    kind: StrategyKind,
+    strikes: &[f64],
+    expiry: DateTime<Utc>,
+    far_expiry: Option<DateTime<Utc>>,
+) -> Result<Vec<StrategyLeg>, String> {
+    use OptionSide::{Long, Short};
+    use OptionType::{Call, Put};
+
+    let needed = strikes_required(kind);
+    if strikes.len() != needed {
+        return Err(format!("**{}** needs {} strike{}.", kind.label(), needed, if needed == 1 { "" } else { "s" }));
+    }
+    if strikes.windows(2).any(|w| w[0] >= w[1]) {
+        return Err("Strikes must be different and listed low to high.".to_string());
+    }
+
+    let k = strikes;
+    let leg = StrategyLeg::new;
+    Ok(match kind {
+        StrategyKind::CallDebitSpread  => vec![leg(Call, k[0], expiry, Long), leg(Call, k[1], expiry, Short)],
+        StrategyKind::PutDebitSpread   => vec![leg(Put, k[1], expiry, Long), leg(Put, k[0], expiry, Short)],
+        StrategyKind::CallCreditSpread => vec![leg(Call, k[0], expiry, Short), leg(Call, k[1], expiry, Long)],
+        StrategyKind::PutCreditSpread  => vec![leg(Put, k[1], expiry, Short), leg(Put, k[0], expiry, Long)],
+        StrategyKind::LongStraddle     => vec![leg(Call, k[0], expiry, Long), leg(Put, k[0], expiry, Long)],
+        StrategyKind::ShortStraddle    => vec![leg(Call, k[0], expiry, Short), leg(Put, k[0], expiry, Short)],
+        StrategyKind::LongStrangle     => vec![leg(Put, k[0], expiry, Long), leg(Call, k[1], expiry, Long)],
+        StrategyKind::ShortStrangle    => vec![leg(Put, k[0], expiry, Short), leg(Call, k[1], expiry, Short)],
+        StrategyKind::IronCondor => vec![
+            leg(Put, k[0], expiry, Long), leg(Put, k[1], expiry, Short),
+            leg(Call, k[2], expiry, Short), leg(Call, k[3], expiry, Long),
+        ],
+        StrategyKind::CallCalendar | StrategyKind::PutCalendar => {
+            let far = far_expiry
+                .filter(|f| *f > expiry)
+                .ok_or_else(|| "Calendars need a **far_expiry** later than **expiry**.".to_string())?;
+            let t = if kind == StrategyKind::CallCalendar { Call } else { Put };
+            vec![leg(t, k[0], expiry, Short), leg(t, k[0], far, Long)]
+        }
+    })
+}
+
+/// Value of all legs at expiry with the underlying at `spot`, in creds, before premium.
+pub fn expiry_payoff(legs: &[StrategyLeg], spot: f64, contracts: u32) -> f64 {
+    let per_unit: f64 = legs.iter()
+        .map(|l| l.sign() * option_intrinsic(l.option_type, spot, l.strike))
+        .sum();
+    price_to_creds(per_unit * SHARES_PER_CONTRACT * f64::from(contracts))
+}
+
+/// Max profit and max loss at expiry in creds, net of `net_premium` (positive = debit).
+/// `None` means unlimited; legs with different expiries have no fixed max profit.
+pub fn max_profit_loss(legs: &[StrategyLeg], contracts: u32, net_premium: f64) -> (Option<f64>, Option<f64>) {
+    if legs.windows(2).any(|w| w[0].expiry != w[1].expiry) {
+        return (None, Some(net_premium.max(0.0)));
+    }
+    // Payoff is piecewise linear with kinks at the strikes, so its extremes lie at 0, a strike, or ∞.
+    let mut points = vec![0.0];
+    points.extend(legs.iter().map(|l| l.strike));
+    let outcomes: Vec<f64> = points.iter().map(|&s| expiry_payoff(legs, s, contracts) - net_premium).collect();
+    let slope_above: f64 = legs.iter().filter(|l| l.option_type == OptionType::Call).map(StrategyLeg::sign).sum();
+
+    let best = outcomes.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let worst = outcomes.iter().copied().fold(f64::INFINITY, f64::min);
+    (
+        (slope_above <= 0.0).then_some(best),
+        (slope_above >= 0.0).then_some((-worst).max(0.0)),
+    )
+}
+
+/// Margin to lock for a strategy, in creds. Defined-risk combos lock only their worst-case
+/// obligation; calendars are covered by the later-dated long leg; short straddles and strangles
+/// lock the larger naked requirement plus the other side's premium. `leg_premiums` are each
+/// leg's premium across all `contracts`, in creds, as charged at open.
+pub fn strategy_margin_creds(
+    kind: StrategyKind,
+    legs: &[StrategyLeg],
+    spot: f64,
+    contracts: u32,
+    leg_premiums: &[f64],
+) -> f64 {
+    match kind {
+        StrategyKind::CallCalendar | StrategyKind::PutCalendar => 0.0,
+        StrategyKind::ShortStraddle | StrategyKind::ShortStrangle => {
+            let naked: Vec<(f64, f64)> = legs.iter().zip(leg_premiums)
+                .map(|(l, &prem)| {
+                    let prem = creds_to_price(prem);
+                    (naked_margin_usd(l.option_type, spot, l.strike, contracts, prem), prem)
+                })
+                .collect();
+            let (larger, other) = if naked[0].0 >= naked[1].0 { (naked[0], naked[1]) } else { (naked[1], naked[0]) };
+            price_to_creds(larger.0 + other.1)
+        }
+        _ => {
+            let mut points = vec![0.0];
+            points.extend(legs.iter().map(|l| l.strike));
+            let worst = points.iter().map(|&s| expiry_payoff(legs, s, contracts)).fold(0.0, f64::min);
+            -worst
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expiry() -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::days(30)
+    }
+
+    #[test]
+    fn legs_validate_strike_count_and_order() {
+        assert!(strategy_legs(StrategyKind::IronCondor, &[95.0, 100.0, 110.0], expiry(), None).is_err());
+        assert!(strategy_legs(StrategyKind::CallDebitSpread, &[105.0, 100.0], expiry(), None).is_err());
+        assert!(strategy_legs(StrategyKind::CallCalendar, &[100.0], expiry(), None).is_err());
+        let legs = strategy_legs(StrategyKind::PutCreditSpread, &[95.0, 100.0], expiry(), None).unwrap();
+        assert_eq!(legs[0], StrategyLeg::new(OptionType::Put, 100.0, legs[0].expiry, OptionSide::Short));
+    }
+
+    #[test]
+    fn vertical_spread_has_defined_risk() {
+        // Bull call 100/105, 1 contract, $2 debit (200 USD = 20,000 creds)
+        let legs = strategy_legs(StrategyKind::CallDebitSpread, &[100.0, 105.0], expiry(), None).unwrap();
+        let (profit, loss) = max_profit_loss(&legs, 1, 20_000.0);
+        assert_eq!(profit, Some(30_000.0)); // $5 width − $2 debit
+        assert_eq!(loss, Some(20_000.0));
+        assert!(strategy_margin_creds(StrategyKind::CallDebitSpread, &legs, 102.0, 1, &[300.0, 100.0]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn iron_condor_margin_is_widest_wing() {
+        let legs = strategy_legs(StrategyKind::IronCondor, &[90.0, 95.0, 105.0, 115.0], expiry(), None).unwrap();
+        // Call wing is $10 wide → $1,000 per contract
+        let margin = strategy_margin_creds(StrategyKind::IronCondor, &legs, 100.0, 2, &[50.0, 100.0, 100.0, 50.0]);
+        assert!((margin - price_to_creds(2_000.0)).abs() < 1e-6);
+        let (profit, loss) = max_profit_loss(&legs, 2, -20_000.0); // $1 credit × 2
+        assert_eq!(profit, Some(20_000.0));
+        assert_eq!(loss, Some(price_to_creds(2_000.0) - 20_000.0));
+    }
+
+    #[test]
+    fn naked_combos_report_unlimited_sides() {
+        let long = strategy_legs(StrategyKind::LongStraddle, &[100.0], expiry(), None).unwrap();
+        assert_eq!(max_profit_loss(&long, 1, 50_000.0), (None, Some(50_000.0)));
+        let short = strategy_legs(StrategyKind::ShortStrangle, &[95.0, 105.0], expiry(), None).unwrap();
+        assert_eq!(max_profit_loss(&short, 1, -30_000.0), (Some(30_000.0), None));
+        // Reduced margin: one naked side plus the other premium, less than both sides naked
+        let prems = [150.0, 150.0];
+        let combo = strategy_margin_creds(StrategyKind::ShortStrangle, &short, 100.0, 1, &prems);
+        let separate = price_to_creds(
+            naked_margin_usd(OptionType::Put, 100.0, 95.0, 1, 1.5) + naked_margin_usd(OptionType::Call, 100.0, 105.0, 1, 1.5),
+        );
+        assert!(combo < separate);
+    }
+
+    #[test]
+    fn short_straddle_margin_scales_with_contracts() {
+        // $3 a share per leg: 30,000 creds a contract, so 60,000 creds a leg for two
+        let legs = strategy_legs(StrategyKind::ShortStraddle, &[100.0], expiry(), None).unwrap();
+        let one = strategy_margin_creds(StrategyKind::ShortStraddle, &legs, 100.0, 1, &[30_000.0, 30_000.0]);
+        let two = strategy_margin_creds(StrategyKind::ShortStraddle, &legs, 100.0, 2, &[60_000.0, 60_000.0]);
+        assert!((two - 2.0 * one).abs() < 1e-6);
+        let expected = naked_margin_usd(OptionType::Call, 100.0, 100.0, 2, 600.0) + 600.0;
+        assert!((two - price_to_creds(expected)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn calendar_needs_no_margin() {
+        let far = expiry() + chrono::Duration::days(28);
+        let legs = strategy_legs(StrategyKind::PutCalendar, &[100.0], expiry(), Some(far)).unwrap();
+        assert_eq!(legs[1].expiry, far);
+        assert!(strategy_margin_creds(StrategyKind::PutCalendar, &legs, 100.0, 1, &[200.0, 300.0]).abs() < 1e-9);
+        assert_eq!(max_profit_loss(&legs, 1, 10_000.0), (None, Some(10_000.0)));
+    }
+}
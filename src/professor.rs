@@ -4,8 +4,8 @@
 
 use crate::api::{fetch_quote_detail, UsersMap, HTTP_CLIENT};
 use crate::data::{self, AssetType, MemoryEntry, ProfessorMemory, TradeAction};
-use crate::helper::{creds_to_price, default_footer, fmt_qty, price_to_creds};
-use crate::trader::{apply_buy, apply_sell};
+use crate::helper::{creds_to_price, default_footer, fmt_pnl, fmt_qty, price_to_creds};
+use crate::trader::{apply_buy, apply_sell, COST_MODEL};
 use crate::{serenity, Context, Error};
 use chrono::Utc;
 use poise::serenity_prelude::futures;
@@ -310,6 +310,7 @@ pub(crate) async fn professor_daily_session(
             ud.sub_creds(wallet);
             let mut port = data::Portfolio::new(PROFESSOR_PORT.to_string());
             port.cash = f64::from(wallet);
+            port.copyable = true;
             ud.stock.portfolios.push(port);
             tracing::info!(wallet = wallet, "Professor: created missing ProfessorPort");
         }
@@ -469,12 +470,13 @@ pub(crate) async fn professor_daily_session(
                         continue;
                     }
                     let cost_creds = price_to_creds(amount_usd);
-                    if port.cash < cost_creds || cost_creds > cash_limit_creds {
+                    let fees = COST_MODEL.fees(&asset_type, cost_creds);
+                    if port.cash < cost_creds + fees.total() || cost_creds > cash_limit_creds {
                         tracing::warn!(ticker = %trade.ticker, cost_creds = cost_creds, cash = port.cash, limit = cash_limit_creds, "[Professor] BUY skipped — insufficient funds");
                         continue;
                     }
                     let quantity = amount_usd / price_usd;
-                    apply_buy(port, history, &trade.ticker, &asset_name, asset_type, quantity, price_creds, cost_creds, PROFESSOR_PORT);
+                    apply_buy(port, history, &trade.ticker, &asset_name, asset_type, quantity, price_creds, cost_creds, PROFESSOR_PORT, &COST_MODEL);
                     tracing::info!(ticker = %trade.ticker, shares = quantity, price_usd = price_usd, "[Professor] BUY executed");
                     executed.push(ExecutedTrade { action: TradeAction::Buy, ticker: trade.ticker.clone(), amount_usd, price_usd, pnl: None });
                 }
@@ -484,11 +486,11 @@ pub(crate) async fn professor_daily_session(
                         tracing::warn!(ticker = %trade.ticker, "[Professor] SELL skipped — sell_pct is 0");
                         continue;
                     }
-                    let qty = if let Some(p) = port.positions.iter().find(|p| p.ticker == trade.ticker && !matches!(&p.asset_type, AssetType::Option(_))) { p.quantity * sell_pct } else {
+                    let qty = if let Some(p) = port.positions.iter().find(|p| p.ticker == trade.ticker && p.is_long_stock()) { p.quantity * sell_pct } else {
                         tracing::warn!(ticker = %trade.ticker, "[Professor] SELL skipped — position not found");
                         continue;
                     };
-                    let Some(pnl) = apply_sell(port, history, &trade.ticker, &asset_name, qty, price_creds, PROFESSOR_PORT) else {
+                    let Some(pnl) = apply_sell(port, history, &trade.ticker, &asset_name, qty, price_creds, PROFESSOR_PORT, &COST_MODEL) else {
                         tracing::warn!(ticker = %trade.ticker, "[Professor] SELL skipped — apply_sell returned None");
                         continue;
                     };
@@ -612,6 +614,11 @@ pub async fn professor(ctx: Context<'_>) -> Result<(), Error> {
                     let pct = if cost_basis > 0.0 { pnl / cost_basis * 100.0 } else { 0.0 };
                     format!("Sold **{}** shares of **{}** worth **${:.2}** ({:+.1}%)", qty, t.ticker, value, pct)
                 }
+                data::TradeAction::Short => format!("Shorted **{}** shares of **{}** worth **${:.2}**", qty, t.ticker, value),
+                data::TradeAction::Cover => format!("Covered **{}** shares of **{}** ({})", qty, t.ticker, fmt_pnl(t.realized_pnl.unwrap_or(0.0))),
+                data::TradeAction::TransferIn => format!("Received **{}** of **{}** from another portfolio", qty, t.ticker),
+                data::TradeAction::TransferOut => format!("Moved **{}** of **{}** to another portfolio", qty, t.ticker),
+                data::TradeAction::Interest => format!("Earned **${:.2}** of HYSA interest", value),
             }
         })
         .collect();
@@ -657,6 +664,7 @@ pub async fn test_professor(ctx: Context<'_>) -> Result<(), Error> {
 
     tokio::spawn(async move {
         professor_daily_session(&users, &http, &bot_chat, bot_user_id).await;
+        crate::api::sweep_copy_trades(&users, &http, &bot_chat).await;
     });
 
     Ok(())
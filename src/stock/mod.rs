@@ -1,9 +1,13 @@
-//! Stock trading module — search, buy, sell, and trade modals.
+//! Stock trading module — search, buy, sell, short, recurring buys, and trade modals.
 
 mod modals;
 mod orders;
+mod recurring;
 mod search;
+mod short;
 
 #[expect(unused_imports, reason = "buy/sell are registered via main.rs when uncommented; kept for re-export path stability")]
 #[doc(inline)] pub use orders::{buy, sell};
+#[doc(inline)] pub use recurring::recurring;
 #[doc(inline)] pub use search::search;
+#[doc(inline)] pub use short::{cover, short};
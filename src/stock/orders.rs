@@ -1,9 +1,9 @@
 //! Hidden /buy and /sell slash commands (users enter trades through /search).
 
-use crate::api::{is_market_hours, market_data_err, order_expiry, resolve_ticker, with_logo};
-use crate::data::{self, AssetType, OrderSide, PendingOrder, MAX_PENDING_ORDERS};
+use crate::api::{cached_buying_power, is_market_hours, market_data_err, order_expiry, resolve_ticker, with_logo};
+use crate::data::{self, OrderSide, PendingOrder, MAX_PENDING_ORDERS};
 use crate::helper::{creds_to_price, default_footer, fmt_limit_tag, fmt_qty, price_to_creds};
-use crate::trader::{apply_buy, apply_sell};
+use crate::trader::{apply_buy, apply_sell, COST_MODEL};
 use crate::{serenity, Context, Error};
 use std::time::Duration;
 
@@ -173,15 +173,16 @@ pub async fn buy(
         return Ok(());
     };
 
-    if user_data.stock.portfolios[port_idx].cash < total_cost {
-        let cash = user_data.stock.portfolios[port_idx].cash;
+    let fees = COST_MODEL.fees(&asset_type, total_cost);
+    let buying_power = cached_buying_power(&user_data.stock.portfolios[port_idx]);
+    if buying_power < total_cost + fees.total() {
         drop(user_data);
         ctx.send(poise::CreateReply::default().embed(
             serenity::CreateEmbed::new().title("Buy")
                 .description(format!(
-                    "Insufficient cash. Need **${:.2}** ({:.0} creds) but **{}** has **${:.2}** ({:.0} creds).",
-                    creds_to_price(total_cost), total_cost, portfolio,
-                    creds_to_price(cash), cash
+                    "Insufficient buying power. Need **${:.2}** ({:.0} creds, incl. fees) but **{}** has **${:.2}** ({:.0} creds).",
+                    creds_to_price(total_cost + fees.total()), total_cost + fees.total(), portfolio,
+                    creds_to_price(buying_power), buying_power
                 ))
                 .color(data::EMBED_ERROR),
         )).await?;
@@ -190,7 +191,7 @@ pub async fn buy(
 
     {
         let stock = &mut user_data.stock;
-        apply_buy(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &asset_name, asset_type, quantity, price_per_unit, total_cost, &portfolio);
+        apply_buy(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &asset_name, asset_type, quantity, price_per_unit, total_cost, &portfolio, &COST_MODEL);
     }
     drop(user_data);
 
@@ -198,8 +199,8 @@ pub async fn buy(
         with_logo(
             serenity::CreateEmbed::new().title("Buy")
                 .description(format!(
-                    "Bought **{} {}** ({}) for **${:.2}** ({:.0} creds)\n${:.2}/unit | Portfolio: **{}**",
-                    fmt_qty(quantity), ticker, asset_name, creds_to_price(total_cost), total_cost, price_usd, portfolio
+                    "Bought **{} {}** ({}) for **${:.2}** ({:.0} creds)\n${:.2}/unit | Fees: **${:.2}** | Portfolio: **{}**",
+                    fmt_qty(quantity), ticker, asset_name, creds_to_price(total_cost), total_cost, price_usd, creds_to_price(fees.total()), portfolio
                 ))
                 .color(data::EMBED_SUCCESS).footer(default_footer()),
             &ticker,
@@ -266,7 +267,7 @@ pub async fn sell(
         };
 
         let pos = user_data.stock.portfolios[port_idx].positions.iter()
-            .find(|p| p.ticker == ticker && !matches!(&p.asset_type, AssetType::Option(_)));
+            .find(|p| p.ticker == ticker && p.is_long_stock());
 
         if let Some(p) = pos {
             (p.quantity, p.asset_type.clone(), user_data.stock.portfolios[port_idx].name.clone())
@@ -383,10 +384,11 @@ pub async fn sell(
 
     let port_idx = user_data.stock.find_portfolio_idx(&port_name_normalized).expect("portfolio validated earlier in sell flow");
 
-    let (proceeds, pnl) = {
+    let (proceeds, pnl, fees) = {
         let stock = &mut user_data.stock;
-        let pnl = apply_sell(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &asset_name, quantity, price_per_unit, &portfolio).unwrap_or(0.0);
-        (price_per_unit * quantity, pnl)
+        let pnl = apply_sell(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &asset_name, quantity, price_per_unit, &portfolio, &COST_MODEL);
+        let fees = pnl.and(stock.trade_history.back()).map_or(0.0, |t| t.fees.total());
+        (price_per_unit * quantity, pnl.unwrap_or(0.0), fees)
     };
 
     let pnl_str = crate::helper::fmt_pnl(pnl);
@@ -397,8 +399,8 @@ pub async fn sell(
         with_logo(
             serenity::CreateEmbed::new().title("Sell")
                 .description(format!(
-                    "Sold **{} {}** for **${:.2}** ({:.0} creds)\n${:.2}/unit | Realized P&L: **{}**",
-                    fmt_qty(quantity), ticker, creds_to_price(proceeds), proceeds, price_usd, pnl_str
+                    "Sold **{} {}** for **${:.2}** ({:.0} creds)\n${:.2}/unit | Fees: **${:.2}** | Realized P&L: **{}**",
+                    fmt_qty(quantity), ticker, creds_to_price(proceeds), proceeds, price_usd, creds_to_price(fees), pnl_str
                 ))
                 .color(color).footer(default_footer()),
             &ticker,
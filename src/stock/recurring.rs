@@ -0,0 +1,157 @@
+//! /recurring command — create, list, pause and cancel dollar-cost-averaging plans.
+
+use crate::api::{market_data_err, resolve_ticker};
+use crate::data::{self, RecurringFrequency, RecurringPlan, StockProfile, MAX_RECURRING_PLANS};
+use crate::helper::default_footer;
+use crate::{serenity, Context, Error};
+use chrono::Utc;
+use poise::serenity_prelude::EditMessage;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn build_recurring_view(stock: &StockProfile) -> (serenity::CreateEmbed, Vec<serenity::CreateActionRow>) {
+    let description = if stock.recurring.is_empty() {
+        "*No recurring buys. Run `/recurring` with a ticker, amount, frequency and portfolio to start one.*".to_string()
+    } else {
+        stock.recurring.iter().map(|p| {
+            let status = if p.paused {
+                "⏸ paused".to_string()
+            } else {
+                format!("next <t:{}:R>", p.next_run.timestamp())
+            };
+            let skipped = if p.skipped > 0 { format!(" | ⚠ {} skipped", p.skipped) } else { String::new() };
+            format!(
+                "`#{}` **${:.2}** of **{}** {} → **{}** | {}{}",
+                p.id, p.amount_usd, p.ticker, p.frequency.label(), p.portfolio_name, status, skipped,
+            )
+        }).collect::<Vec<_>>().join("\n")
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Recurring Buys")
+        .description(description)
+        .color(data::EMBED_CYAN)
+        .footer(default_footer());
+
+    let components = stock.recurring.iter().map(|p| {
+        serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(format!("rec_toggle_{}", p.id))
+                .label(format!("{} #{} {}", if p.paused { "Resume" } else { "Pause" }, p.id, p.ticker))
+                .style(if p.paused { serenity::ButtonStyle::Success } else { serenity::ButtonStyle::Secondary }),
+            serenity::CreateButton::new(format!("rec_cancel_{}", p.id))
+                .label(format!("Cancel #{}", p.id))
+                .style(serenity::ButtonStyle::Danger),
+        ])
+    }).collect();
+
+    (embed, components)
+}
+
+/// Set up or manage recurring (dollar-cost-averaging) buys
+#[poise::command(slash_command)]
+pub async fn recurring(
+    ctx: Context<'_>,
+    #[description = "Ticker to buy on a schedule (leave empty to manage existing plans)"] ticker_query: Option<String>,
+    #[description = "Dollar amount to buy each time"] amount: Option<f64>,
+    #[description = "How often to buy"] frequency: Option<RecurringFrequency>,
+    #[description = "Portfolio to buy into"] portfolio: Option<String>,
+) -> Result<(), Error> {
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let serenity_ctx = ctx.serenity_context().clone();
+
+    if let Some(ticker_query) = ticker_query {
+        let (Some(amount), Some(frequency), Some(portfolio)) = (amount, frequency, portfolio) else {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Recurring Buys")
+                    .description("A new plan needs **amount**, **frequency** and **portfolio**.")
+                    .color(data::EMBED_ERROR),
+            )).await?;
+            return Ok(());
+        };
+        if amount < 1.0 {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Recurring Buys")
+                    .description("Recurring buys must be at least **$1.00**.")
+                    .color(data::EMBED_ERROR),
+            )).await?;
+            return Ok(());
+        }
+
+        ctx.defer().await?;
+        let Some(quote) = resolve_ticker(&ticker_query).await else {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Recurring Buys").description(market_data_err(&ticker_query)).color(data::EMBED_ERROR),
+            )).await?;
+            return Ok(());
+        };
+
+        let err = {
+            let mut ud = u.write().await;
+            match ud.stock.find_portfolio_idx(&portfolio) {
+                None => Some(format!("No portfolio named **{portfolio}** found.")),
+                Some(idx) => {
+                    let plan = RecurringPlan {
+                        id: 0,
+                        ticker: quote.symbol.clone(),
+                        asset_name: quote.display_name(),
+                        asset_type: quote.asset_type(),
+                        portfolio_name: ud.stock.portfolios[idx].name.clone(),
+                        amount_usd: amount,
+                        frequency,
+                        // First buy goes out on the next sweep during market hours
+                        next_run: Utc::now(),
+                        paused: false,
+                        skipped: 0,
+                    };
+                    (!ud.stock.add_recurring(plan))
+                        .then(|| format!("You already have the maximum of {MAX_RECURRING_PLANS} recurring buys."))
+                }
+            }
+        };
+        if let Some(desc) = err {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Recurring Buys").description(desc).color(data::EMBED_ERROR),
+            )).await?;
+            return Ok(());
+        }
+    }
+
+    let (embed, components) = build_recurring_view(&u.read().await.stock);
+    let reply = ctx.send(poise::CreateReply::default().embed(embed).components(components)).await?;
+    let mut msg = reply.into_message().await?;
+
+    loop {
+        let Some(press) = msg
+            .await_component_interaction(&serenity_ctx)
+            .author_id(ctx.author().id)
+            .timeout(Duration::from_secs(60))
+            .await
+        else {
+            msg.edit(&serenity_ctx, EditMessage::default().components(vec![])).await.ok();
+            break;
+        };
+        press.create_response(&serenity_ctx, serenity::CreateInteractionResponse::Acknowledge).await.ok();
+
+        let id = press.data.custom_id.as_str();
+        {
+            let mut ud = u.write().await;
+            let plans = &mut ud.stock.recurring;
+            if let Some(plan_id) = id.strip_prefix("rec_toggle_").and_then(|s| s.parse::<u32>().ok()) {
+                if let Some(plan) = plans.iter_mut().find(|p| p.id == plan_id) {
+                    plan.paused = !plan.paused;
+                    // Resuming never back-fills missed runs
+                    if !plan.paused && plan.next_run < Utc::now() {
+                        plan.next_run = Utc::now();
+                    }
+                }
+            } else if let Some(plan_id) = id.strip_prefix("rec_cancel_").and_then(|s| s.parse::<u32>().ok()) {
+                plans.retain(|p| p.id != plan_id);
+            }
+        }
+
+        let (embed, components) = build_recurring_view(&u.read().await.stock);
+        msg.edit(&serenity_ctx, EditMessage::default().embed(embed).components(components)).await.ok();
+    }
+
+    Ok(())
+}
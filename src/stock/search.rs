@@ -1,12 +1,12 @@
 //! /search command — single detailed view and compact multi-asset view.
 
 use crate::api::{self, market_data_err, resolve_ticker, with_logo, FmpProfile, FmpRatios};
-use crate::data::{self, AssetType, OrderSide, PendingOrder, MAX_PENDING_ORDERS};
+use crate::data::{self, OrderSide, PendingOrder, MAX_PENDING_ORDERS};
 use crate::helper::{creds_to_price, default_footer, fmt_limit_tag, fmt_qty, format_large_num, price_to_creds};
 use crate::stock::modals::{BuyModal, SellModal};
-use crate::trader::{apply_buy, apply_sell};
+use crate::trader::{apply_buy, apply_sell, COST_MODEL};
 use crate::{serenity, Context, Error};
-use crate::api::{is_market_hours, order_expiry};
+use crate::api::{cached_buying_power, is_market_hours, order_expiry};
 use poise::serenity_prelude::futures;
 use std::time::Duration;
 
@@ -97,7 +97,7 @@ pub async fn search(
         let has_position = {
             let user_data = u.read().await;
             user_data.stock.portfolios.iter().any(|p| {
-                p.positions.iter().any(|pos| pos.ticker == ticker && !matches!(&pos.asset_type, AssetType::Option(_)))
+                p.positions.iter().any(|pos| pos.ticker == ticker && pos.is_long_stock())
             })
         };
 
@@ -135,7 +135,7 @@ pub async fn search(
                     (ports[0].name.clone(), true)
                 } else {
                     let default = ports.iter()
-                        .find(|p| p.positions.iter().any(|pos| pos.ticker == ticker && !matches!(&pos.asset_type, AssetType::Option(_))))
+                        .find(|p| p.positions.iter().any(|pos| pos.ticker == ticker && pos.is_long_stock()))
                         .or_else(|| ports.first())
                         .map(|p| p.name.clone())
                         .unwrap_or_default();
@@ -177,7 +177,7 @@ pub async fn search(
                     let user_data = u.read().await;
                     user_data.stock.portfolios.iter()
                         .find(|p| p.name == default_port)
-                        .and_then(|p| p.positions.iter().find(|pos| pos.ticker == ticker && !matches!(&pos.asset_type, AssetType::Option(_))))
+                        .and_then(|p| p.positions.iter().find(|pos| pos.ticker == ticker && pos.is_long_stock()))
                         .map(|pos| format!("{} shares (${:.2})", fmt_qty(pos.quantity), pos.quantity * price_usd))
                         .unwrap_or_default()
                 };
@@ -288,15 +288,16 @@ pub async fn search(
                 return Ok(());
             }
 
-            if user_data.stock.portfolios[port_idx].cash < total_cost {
-                let cash = user_data.stock.portfolios[port_idx].cash;
+            let fees = COST_MODEL.fees(&asset_type, total_cost);
+            let buying_power = cached_buying_power(&user_data.stock.portfolios[port_idx]);
+            if buying_power < total_cost + fees.total() {
                 drop(user_data);
                 ctx.send(poise::CreateReply::default().embed(
                     serenity::CreateEmbed::new().title("Buy")
                         .description(format!(
-                            "Insufficient cash. Need **${:.2}** ({:.0} creds) but **{}** has **${:.2}** ({:.0} creds).",
-                            creds_to_price(total_cost), total_cost, port_name,
-                            creds_to_price(cash), cash,
+                            "Insufficient buying power. Need **${:.2}** ({:.0} creds, incl. fees) but **{}** has **${:.2}** ({:.0} creds).",
+                            creds_to_price(total_cost + fees.total()), total_cost + fees.total(), port_name,
+                            creds_to_price(buying_power), buying_power,
                         ))
                         .color(data::EMBED_ERROR),
                 )).await?;
@@ -305,15 +306,15 @@ pub async fn search(
 
             {
                 let stock = &mut user_data.stock;
-                apply_buy(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &display_name, asset_type, qty, price_per_unit, total_cost, &port_name);
+                apply_buy(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &display_name, asset_type, qty, price_per_unit, total_cost, &port_name, &COST_MODEL);
             }
             drop(user_data);
             ctx.send(poise::CreateReply::default().embed(
                 with_logo(
                     serenity::CreateEmbed::new().title("Buy")
                         .description(format!(
-                            "Bought **{} {}** ({}) for **${:.2}** ({:.0} creds)\n${:.2}/unit | Portfolio: **{}**",
-                            fmt_qty(qty), ticker, display_name, creds_to_price(total_cost), total_cost, price_usd, port_name,
+                            "Bought **{} {}** ({}) for **${:.2}** ({:.0} creds)\n${:.2}/unit | Fees: **${:.2}** | Portfolio: **{}**",
+                            fmt_qty(qty), ticker, display_name, creds_to_price(total_cost), total_cost, price_usd, creds_to_price(fees.total()), port_name,
                         ))
                         .color(data::EMBED_SUCCESS).footer(default_footer()),
                     &ticker,
@@ -332,7 +333,7 @@ pub async fn search(
             };
 
             let pos_idx = if let Some(i) = user_data.stock.portfolios[port_idx].positions.iter()
-                .position(|p| p.ticker == ticker && !matches!(&p.asset_type, AssetType::Option(_))) { i } else {
+                .position(|p| p.ticker == ticker && p.is_long_stock()) { i } else {
                 drop(user_data);
                 ctx.send(poise::CreateReply::default().embed(
                     serenity::CreateEmbed::new().title("Sell")
@@ -389,10 +390,11 @@ pub async fn search(
                 return Ok(());
             }
 
-            let (proceeds, pnl) = {
+            let (proceeds, pnl, fees) = {
                 let stock = &mut user_data.stock;
-                let pnl = apply_sell(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &display_name, qty, price_per_unit, &port_name).unwrap_or(0.0);
-                (price_per_unit * qty, pnl)
+                let pnl = apply_sell(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &display_name, qty, price_per_unit, &port_name, &COST_MODEL);
+                let fees = pnl.and(stock.trade_history.back()).map_or(0.0, |t| t.fees.total());
+                (price_per_unit * qty, pnl.unwrap_or(0.0), fees)
             };
             let pnl_str = crate::helper::fmt_pnl(pnl);
             let pnl_color = if pnl >= 0.0 { data::EMBED_SUCCESS } else { data::EMBED_FAIL };
@@ -401,8 +403,8 @@ pub async fn search(
                 with_logo(
                     serenity::CreateEmbed::new().title("Sell")
                         .description(format!(
-                            "Sold **{} {}** for **${:.2}** ({:.0} creds)\n${:.2}/unit | Realized P&L: **{}**",
-                            fmt_qty(qty), ticker, creds_to_price(proceeds), proceeds, price_usd, pnl_str,
+                            "Sold **{} {}** for **${:.2}** ({:.0} creds)\n${:.2}/unit | Fees: **${:.2}** | Realized P&L: **{}**",
+                            fmt_qty(qty), ticker, creds_to_price(proceeds), proceeds, price_usd, creds_to_price(fees), pnl_str,
                         ))
                         .color(pnl_color).footer(default_footer()),
                     &ticker,
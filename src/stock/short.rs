@@ -0,0 +1,206 @@
+//! `/short` and `/cover` — sell-to-open and buy-to-close stock shorts.
+
+use crate::api::{is_market_hours, market_data_err, resolve_ticker, with_logo};
+use crate::data::{self, AssetType, SHORT_BORROW_RATE, SHORT_INITIAL_MARGIN_RATIO};
+use crate::helper::{creds_to_price, default_footer, fmt_pnl, fmt_qty, price_to_creds};
+use crate::trader::{apply_cover, apply_short, short_margin_usd, COST_MODEL};
+use crate::{serenity, Context, Error};
+
+const ERR_MARKET_CLOSED: &str = "Shorts can only be opened or covered during market hours.";
+
+/// Short a stock or ETF (sell borrowed shares)
+#[poise::command(slash_command)]
+pub async fn short(
+    ctx: Context<'_>,
+    #[description = "Ticker symbol (e.g. TSLA)"] ticker_query: String,
+    #[description = "Number of shares to short (fractional ok)"] quantity: f64,
+    #[description = "Portfolio to short in"] portfolio: String,
+) -> Result<(), Error> {
+    if quantity <= 0.0 {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Short").description("Quantity must be positive.").color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+    if !is_market_hours() {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Short").description(ERR_MARKET_CLOSED).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let Some(quote) = resolve_ticker(&ticker_query).await else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Short").description(market_data_err(&ticker_query)).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+    let ticker = quote.symbol.clone();
+    let asset_name = quote.display_name();
+    let asset_type = quote.asset_type();
+    if matches!(asset_type, AssetType::Crypto) {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Short").description("Crypto cannot be borrowed for shorting.").color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+    let Some(price_usd) = quote.regular_market_price else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Short").description(market_data_err(&ticker)).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+
+    let price_per_unit = price_to_creds(price_usd);
+    let proceeds = price_per_unit * quantity;
+    let fees = COST_MODEL.fees(&asset_type, proceeds);
+    let margin_creds = price_to_creds(short_margin_usd(price_usd, quantity)) + fees.total();
+
+    let data_ref = &ctx.data().users;
+    let u = data_ref.get(&ctx.author().id).unwrap();
+    let mut user_data = u.write().await;
+
+    let Some(port_idx) = user_data.stock.find_portfolio_idx(&portfolio) else {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Short").description(format!("No portfolio named **{portfolio}** found.")).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+
+    let port = &user_data.stock.portfolios[port_idx];
+    if port.positions.iter().any(|p| p.ticker == ticker && p.is_long_stock()) {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Short")
+                .description(format!("You hold a long **{ticker}** position in **{portfolio}**. Sell it before shorting."))
+                .color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
+    let available = port.cash - port.locked_cash();
+    if available < margin_creds {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Short")
+                .description(format!(
+                    "Shorting requires **${:.2}** ({:.0} creds) free margin — {:.0}% of the position plus fees — but **{}** has only **${:.2}** ({:.0} creds) free.",
+                    creds_to_price(margin_creds), margin_creds, SHORT_INITIAL_MARGIN_RATIO * 100.0, portfolio,
+                    creds_to_price(available), available,
+                ))
+                .color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
+    let port_name = port.name.clone();
+    {
+        let stock = &mut user_data.stock;
+        apply_short(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &asset_name, asset_type, quantity, price_per_unit, &port_name, &COST_MODEL);
+    }
+    drop(user_data);
+
+    ctx.send(poise::CreateReply::default().embed(
+        with_logo(
+            serenity::CreateEmbed::new().title("Short")
+                .description(format!(
+                    "Shorted **{} {}** ({}) for **${:.2}** ({:.0} creds)\n${:.2}/unit | Fees: **${:.2}** | Portfolio: **{}**\n\n*Proceeds plus {:.0}% margin are locked until covered. Borrow fee: {:.1}%/yr, charged daily.*",
+                    fmt_qty(quantity), ticker, asset_name, creds_to_price(proceeds), proceeds, price_usd,
+                    creds_to_price(fees.total()), port_name, SHORT_INITIAL_MARGIN_RATIO * 100.0, SHORT_BORROW_RATE,
+                ))
+                .color(data::EMBED_SUCCESS).footer(default_footer()),
+            &ticker,
+        )
+    )).await?;
+    Ok(())
+}
+
+/// Cover (buy back) a short stock position
+#[poise::command(slash_command)]
+pub async fn cover(
+    ctx: Context<'_>,
+    #[description = "Ticker symbol (e.g. TSLA)"] ticker_query: String,
+    #[description = "Portfolio holding the short"] portfolio: String,
+    #[description = "Number of shares to cover (defaults to the whole position)"] quantity: Option<f64>,
+) -> Result<(), Error> {
+    if !is_market_hours() {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Cover").description(ERR_MARKET_CLOSED).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let Some(quote) = resolve_ticker(&ticker_query).await else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Cover").description(market_data_err(&ticker_query)).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+    let ticker = quote.symbol.clone();
+    let asset_name = quote.display_name();
+    let Some(price_usd) = quote.regular_market_price else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Cover").description(market_data_err(&ticker)).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+    let price_per_unit = price_to_creds(price_usd);
+
+    let data_ref = &ctx.data().users;
+    let u = data_ref.get(&ctx.author().id).unwrap();
+    let mut user_data = u.write().await;
+
+    let Some(port_idx) = user_data.stock.find_portfolio_idx(&portfolio) else {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Cover").description(format!("No portfolio named **{portfolio}** found.")).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+
+    let port = &user_data.stock.portfolios[port_idx];
+    let Some(short_qty) = port.positions.iter().find(|p| p.ticker == ticker && p.is_short_stock()).map(|p| -p.quantity) else {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Cover").description(format!("No short **{ticker}** position in **{portfolio}**.")).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+
+    let qty = quantity.map_or(short_qty, |q| if (q - short_qty).abs() < 5e-5 { short_qty } else { q });
+    if qty <= 0.0 || qty > short_qty + 1e-9 {
+        drop(user_data);
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Cover")
+                .description(format!("You are short **{}** of **{}** but tried to cover **{}**.", fmt_qty(short_qty), ticker, fmt_qty(qty)))
+                .color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    }
+
+    let port_name = port.name.clone();
+    let (cost, pnl, fees) = {
+        let stock = &mut user_data.stock;
+        let pnl = apply_cover(&mut stock.portfolios[port_idx], &mut stock.trade_history, &ticker, &asset_name, qty, price_per_unit, &port_name, &COST_MODEL);
+        let fees = pnl.and(stock.trade_history.back()).map_or(0.0, |t| t.fees.total());
+        (price_per_unit * qty, pnl.unwrap_or(0.0), fees)
+    };
+    drop(user_data);
+
+    let color = if pnl >= 0.0 { data::EMBED_SUCCESS } else { data::EMBED_FAIL };
+    ctx.send(poise::CreateReply::default().embed(
+        with_logo(
+            serenity::CreateEmbed::new().title("Cover")
+                .description(format!(
+                    "Covered **{} {}** for **${:.2}** ({:.0} creds)\n${:.2}/unit | Fees: **${:.2}** | Realized P&L: **{}**",
+                    fmt_qty(qty), ticker, creds_to_price(cost), cost, price_usd, creds_to_price(fees), fmt_pnl(pnl),
+                ))
+                .color(color).footer(default_footer()),
+            &ticker,
+        )
+    )).await?;
+    Ok(())
+}
@@ -0,0 +1,586 @@
+//! Club portfolios — a portfolio in its manager's profile that other members buy units of at NAV.
+//! The manager trades it like any other portfolio; members deposit, redeem, and vote on trade
+//! proposals, which fill through the rebalance executor once a unit-weighted majority approves.
+
+use crate::api::{fetch_prices_map, market_data_err, resolve_ticker};
+use crate::data::{self, Club, ClubRole, OrderSide, Portfolio, UserData};
+use crate::helper::{creds_to_price, default_footer, fmt_qty, price_to_creds};
+use crate::{serenity, Context, Error};
+use poise::serenity_prelude::UserId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use super::rebalance::{RebalanceOrder, RebalancePlan};
+use super::{execute_rebalance, portfolio_equity};
+
+/// How long members have to vote on a trade proposal.
+const PROPOSAL_VOTE_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum MemberAction {
+    Add,
+    Remove,
+}
+
+/// The owner's data and the exact portfolio name of the club called `name`, restricted to clubs
+/// `member` belongs to when given.
+async fn find_club(ctx: Context<'_>, name: &str, member: Option<UserId>) -> Option<(Arc<RwLock<UserData>>, String)> {
+    // Snapshot the Arcs so no DashMap shard lock is held across the awaits below
+    let owners: Vec<_> = ctx.data().users.iter().map(|e| Arc::clone(e.value())).collect();
+    for u in owners {
+        let found = u.read().await.stock.portfolios.iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name) && p.club.as_ref().is_some_and(|c| member.is_none_or(|m| c.member(m).is_some())))
+            .map(|p| p.name.clone());
+        if let Some(port_name) = found {
+            return Some((u, port_name));
+        }
+    }
+    None
+}
+
+/// Creds per unit of `port` at `prices`, or an error if a holding couldn't be priced — a missing
+/// price (absent, or 0.0 from a failed fetch) would understate the NAV and hand out units too cheaply —
+/// or if the club is worth nothing per unit.
+fn nav_at(port: &Portfolio, club: &Club, prices: &HashMap<String, f64>) -> Result<f64, String> {
+    let missing: Vec<&str> = port.positions.iter()
+        .map(|p| p.ticker.as_str())
+        .filter(|t| !prices.get(*t).is_some_and(|p| *p > 0.0))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Couldn't price **{}** right now — try again shortly.", missing.join(", ")));
+    }
+    let nav = club.nav(club_equity(port, prices));
+    if !(nav.is_finite() && nav > 0.0) {
+        return Err(data::NAV_UNUSABLE_ERR.to_string());
+    }
+    Ok(nav)
+}
+
+/// The club's equity including HYSA interest accrued but not yet posted, which the members
+/// already own.
+fn club_equity(port: &Portfolio, prices: &HashMap<String, f64>) -> f64 {
+    portfolio_equity(port, prices) + port.accrued_interest
+}
+
+/// Current prices for everything the club named `port_name` holds.
+async fn club_prices(u: &RwLock<UserData>, port_name: &str) -> HashMap<String, f64> {
+    let tickers: Vec<String> = u.read().await.stock.portfolios.iter()
+        .find(|p| p.name == port_name)
+        .map(|p| p.positions.iter().map(|pos| pos.ticker.clone()).collect())
+        .unwrap_or_default();
+    fetch_prices_map(&tickers).await
+}
+
+fn fmt_pnl_pct(pnl: f64, basis: f64) -> String {
+    let pct = if basis > 0.0 { format!(" ({:+.1}%)", pnl / basis * 100.0) } else { String::new() };
+    format!("{}${:.2}{pct}", if pnl < 0.0 { "-" } else { "+" }, creds_to_price(pnl.abs()))
+}
+
+fn build_club_embed(port: &Portfolio, club: &Club, nav: Option<f64>, prices: &HashMap<String, f64>) -> serenity::CreateEmbed {
+    let equity = club_equity(port, prices);
+    let nav_str = nav.map_or_else(|| "—".to_string(), |n| format!("${:.4}", creds_to_price(n)));
+    let mut desc = format!(
+        "**Manager:** {}\n**Value:** ${:.2} | **Cash:** ${:.2}\n**NAV:** {nav_str} per unit | **Units:** {:.4}\n",
+        club.manager().map_or_else(|| "—".to_string(), |m| format!("<@{m}>")),
+        creds_to_price(equity), creds_to_price(port.cash), club.total_units(),
+    );
+
+    desc += "\n**Members:**\n﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\n";
+    let mut members: Vec<_> = club.members.iter().collect();
+    members.sort_by(|a, b| b.units.total_cmp(&a.units));
+    for m in members {
+        let role = if m.role == ClubRole::Manager { " 👑" } else { "" };
+        let stake = nav.map_or_else(String::new, |n| {
+            format!(" | ${:.2} | P&L **{}**", creds_to_price(m.units * n), fmt_pnl_pct(m.pnl(n), m.contributed))
+        });
+        desc += &format!("<@{}>{role} — {:.4} units{stake}\n", m.user_id, m.units);
+    }
+
+    if !port.positions.is_empty() {
+        desc += "\n**Holdings:**\n﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\n";
+        for pos in &port.positions {
+            let value = prices.get(&pos.ticker).map_or_else(|| "unpriced".to_string(), |p| format!("${:.2}", p * pos.quantity));
+            desc += &format!("**{}** — {} | {value}\n", pos.ticker, fmt_qty(pos.quantity));
+        }
+    }
+    serenity::CreateEmbed::new()
+        .title(format!("Club — {}", port.name))
+        .description(desc)
+        .color(data::EMBED_CYAN)
+        .footer(default_footer())
+}
+
+/// Start a club portfolio that other members can buy into
+#[poise::command(slash_command)]
+pub async fn club_create(
+    ctx: Context<'_>,
+    #[description = "Name of the club"] name: String,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Club — Create").description(desc).color(data::EMBED_ERROR),
+    );
+    let name = name.trim().to_string();
+    if name.is_empty() || name.len() > data::MAX_PORTFOLIO_NAME_LEN {
+        ctx.send(err_embed(format!("Club name must be between 1 and {} characters.", data::MAX_PORTFOLIO_NAME_LEN))).await?;
+        return Ok(());
+    }
+    if find_club(ctx, &name, None).await.is_some() {
+        ctx.send(err_embed(format!("A club named **{name}** already exists."))).await?;
+        return Ok(());
+    }
+
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let err = {
+        let mut ud = u.write().await;
+        if ud.stock.portfolios.len() >= data::MAX_PORTFOLIOS {
+            Some(format!("You have reached the maximum of **{}** portfolios.", data::MAX_PORTFOLIOS))
+        } else if ud.stock.find_portfolio_idx(&name).is_some() {
+            Some(format!("A portfolio named **{name}** already exists."))
+        } else {
+            let mut port = Portfolio::new(name.clone());
+            port.club = Some(Club::new(ctx.author().id));
+            ud.stock.portfolios.push(port);
+            None
+        }
+    };
+    if let Some(desc) = err {
+        ctx.send(err_embed(desc)).await?;
+        return Ok(());
+    }
+
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("Club — Create")
+            .description(format!(
+                "Created club **{name}** with you as manager.\n\n\
+                 • Add members with `/club_member`.\n\
+                 • Everyone buys units at NAV with `/club_deposit` and cashes out with `/club_redeem`.\n\
+                 • You trade **{name}** like any of your portfolios; members suggest trades with `/club_propose`.",
+            ))
+            .color(data::EMBED_SUCCESS)
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// Add or remove a club member (managers), or leave a club yourself
+#[poise::command(slash_command, guild_only)]
+pub async fn club_member(
+    ctx: Context<'_>,
+    #[description = "Club name"] club: String,
+    #[description = "Member to add or remove"] user: serenity::User,
+    #[description = "Add or remove"] action: MemberAction,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Club — Members").description(desc).color(data::EMBED_ERROR),
+    );
+    let Some((owner, port_name)) = find_club(ctx, &club, Some(ctx.author().id)).await else {
+        ctx.send(err_embed(format!("You aren't in a club named **{club}**."))).await?;
+        return Ok(());
+    };
+    if action == MemberAction::Add && (user.bot || !ctx.data().users.contains_key(&user.id)) {
+        ctx.send(err_embed(format!("<@{}> hasn't started using ProfessorBot yet.", user.id))).await?;
+        return Ok(());
+    }
+
+    let result = {
+        let mut ud = owner.write().await;
+        match ud.stock.portfolios.iter_mut().find(|p| p.name == port_name).and_then(|p| p.club.as_mut()) {
+            None => Err(format!("Club **{port_name}** no longer exists.")),
+            // Members may only take themselves out
+            Some(c) if c.manager() != Some(ctx.author().id) && (action == MemberAction::Add || user.id != ctx.author().id) => {
+                Err("Only the club's manager can change its members.".to_string())
+            }
+            Some(c) => match action {
+                MemberAction::Add => c.add_member(user.id),
+                MemberAction::Remove => c.remove_member(user.id),
+            },
+        }
+    };
+    let desc = match result {
+        Ok(()) if action == MemberAction::Add => format!("<@{}> joined **{port_name}**. They can buy in with `/club_deposit`.", user.id),
+        Ok(()) => format!("<@{}> left **{port_name}**.", user.id),
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Club — Members").description(desc).color(data::EMBED_SUCCESS).footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// Buy units of a club with creds from your wallet
+#[poise::command(slash_command)]
+pub async fn club_deposit(
+    ctx: Context<'_>,
+    #[description = "Club name"] club: String,
+    #[description = "Dollars to deposit"] amount: f64,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Club — Deposit").description(desc).color(data::EMBED_ERROR),
+    );
+    if !(0.01..=data::MAX_FUND_USD).contains(&amount) {
+        ctx.send(err_embed(format!("Amount must be between $0.01 and ${:.2}.", data::MAX_FUND_USD))).await?;
+        return Ok(());
+    }
+    ctx.defer().await?;
+    let Some((owner, port_name)) = find_club(ctx, &club, Some(ctx.author().id)).await else {
+        ctx.send(err_embed(format!("You aren't in a club named **{club}**."))).await?;
+        return Ok(());
+    };
+    let prices = club_prices(&owner, &port_name).await;
+
+    // Wallet first, then the club — two separate locks, since the depositor may be the manager
+    let creds = price_to_creds(amount) as i32;
+    let me = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    {
+        let mut ud = me.write().await;
+        if ud.get_creds() < creds {
+            drop(ud);
+            ctx.send(err_embed(format!("Insufficient creds. You have **{}** but need **{creds}**.", me.read().await.get_creds()))).await?;
+            return Ok(());
+        }
+        ud.sub_creds(creds);
+    }
+    let issued = {
+        let mut ud = owner.write().await;
+        match ud.stock.portfolios.iter_mut().find(|p| p.name == port_name) {
+            Some(port) if port.club.is_some() => {
+                let club = port.club.as_ref().unwrap();
+                nav_at(port, club, &prices).and_then(|nav| {
+                    let units = port.club.as_mut().unwrap().issue(ctx.author().id, f64::from(creds), nav)?;
+                    port.cash += f64::from(creds);
+                    port.pending_flow += f64::from(creds);
+                    Ok((units, nav))
+                })
+            }
+            _ => Err(format!("Club **{port_name}** no longer exists.")),
+        }
+    };
+    let (units, nav) = match issued {
+        Ok(done) => done,
+        Err(desc) => {
+            me.write().await.add_creds(creds);
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("Club — Deposit")
+            .description(format!(
+                "Deposited **${amount:.2}** into **{port_name}** for **{units:.4}** units at **${:.4}** per unit.",
+                creds_to_price(nav),
+            ))
+            .color(data::EMBED_SUCCESS)
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// Sell club units back for creds at NAV
+#[poise::command(slash_command)]
+pub async fn club_redeem(
+    ctx: Context<'_>,
+    #[description = "Club name"] club: String,
+    #[description = "Dollars to redeem (default: all your units)"] amount: Option<f64>,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Club — Redeem").description(desc).color(data::EMBED_ERROR),
+    );
+    if amount.is_some_and(|a| !(0.01..=data::MAX_FUND_USD).contains(&a)) {
+        ctx.send(err_embed(format!("Amount must be between $0.01 and ${:.2}.", data::MAX_FUND_USD))).await?;
+        return Ok(());
+    }
+    ctx.defer().await?;
+    let Some((owner, port_name)) = find_club(ctx, &club, Some(ctx.author().id)).await else {
+        ctx.send(err_embed(format!("You aren't in a club named **{club}**."))).await?;
+        return Ok(());
+    };
+    let prices = club_prices(&owner, &port_name).await;
+
+    let redeemed = {
+        let mut ud = owner.write().await;
+        match ud.stock.portfolios.iter_mut().find(|p| p.name == port_name) {
+            Some(port) if port.club.is_some() => {
+                let club = port.club.as_ref().unwrap();
+                let held = club.member(ctx.author().id).map_or(0.0, |m| m.units);
+                nav_at(port, club, &prices).and_then(|nav| {
+                    let units = amount.map_or(held, |a| price_to_creds(a) / nav);
+                    // The redeemed units take their share of unposted interest with them
+                    let total_units = port.club.as_ref().map_or(0.0, Club::total_units);
+                    let interest = if total_units > 0.0 { port.accrued_interest * (units / total_units).min(1.0) } else { 0.0 };
+                    let free = port.cash - port.locked_cash() + interest;
+                    if units <= 0.0 {
+                        return Err("You don't hold any units.".to_string());
+                    }
+                    if units * nav > free {
+                        return Err(format!(
+                            "**{port_name}** only has **${:.2}** of free cash. Ask the manager to sell something first.",
+                            creds_to_price(free.max(0.0)),
+                        ));
+                    }
+                    let creds = port.club.as_mut().unwrap().redeem(ctx.author().id, units, nav)?;
+                    port.accrued_interest -= interest;
+                    port.cash -= creds - interest;
+                    port.pending_flow -= creds;
+                    Ok((units, creds, nav))
+                })
+            }
+            _ => Err(format!("Club **{port_name}** no longer exists.")),
+        }
+    };
+    let (units, creds, nav) = match redeemed {
+        Ok(done) => done,
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+    let me = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    me.write().await.add_creds(creds as i32);
+
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("Club — Redeem")
+            .description(format!(
+                "Redeemed **{units:.4}** units of **{port_name}** at **${:.4}** for **${:.2}** to your wallet.",
+                creds_to_price(nav), creds_to_price(creds),
+            ))
+            .color(data::EMBED_SUCCESS)
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// Propose a club trade for members to vote on
+#[poise::command(slash_command, guild_only)]
+pub async fn club_propose(
+    ctx: Context<'_>,
+    #[description = "Club name"] club: String,
+    #[description = "Buy or sell"] side: OrderSide,
+    #[description = "Stock, ETF or crypto ticker"] ticker: String,
+    #[description = "Units to trade"] quantity: f64,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Club — Proposal").description(desc).color(data::EMBED_ERROR),
+    );
+    if quantity <= 0.0 {
+        ctx.send(err_embed("Quantity must be positive.".to_string())).await?;
+        return Ok(());
+    }
+    ctx.defer().await?;
+    let Some((owner, port_name)) = find_club(ctx, &club, Some(ctx.author().id)).await else {
+        ctx.send(err_embed(format!("You aren't in a club named **{club}**."))).await?;
+        return Ok(());
+    };
+    let Some(quote) = resolve_ticker(&ticker).await else {
+        ctx.send(err_embed(market_data_err(&ticker))).await?;
+        return Ok(());
+    };
+    let symbol = quote.symbol.clone();
+    let members: Vec<UserId> = {
+        let ud = owner.read().await;
+        let port = ud.stock.portfolios.iter().find(|p| p.name == port_name);
+        if side == OrderSide::Sell && !port.is_some_and(|p| p.positions.iter().any(|pos| pos.ticker == symbol && pos.is_long_stock())) {
+            drop(ud);
+            ctx.send(err_embed(format!("**{port_name}** doesn't hold any **{symbol}**."))).await?;
+            return Ok(());
+        }
+        port.and_then(|p| p.club.as_ref()).map(|c| c.members.iter().map(|m| m.user_id).collect()).unwrap_or_default()
+    };
+
+    let offer = format!(
+        "<@{}> proposes that **{port_name}** {} **{} {symbol}** ({}) at market.",
+        ctx.author().id, side.label().to_lowercase(), fmt_qty(quantity), quote.display_name(),
+    );
+    let deadline_ts = chrono::Utc::now().timestamp() + PROPOSAL_VOTE_SECS as i64;
+    let mut approve = vec![ctx.author().id];
+    let mut reject: Vec<UserId> = Vec::new();
+    let tally_embed = |approve: &[UserId], reject: &[UserId], club: Option<&Club>| {
+        let share = |ids: &[UserId]| club.map_or(0.0, |c| {
+            let total = c.total_units();
+            if total > 0.0 { ids.iter().filter_map(|id| c.member(*id)).map(|m| m.units).sum::<f64>() / total * 100.0 } else { 0.0 }
+        });
+        serenity::CreateEmbed::new()
+            .title(format!("Club — Proposal for {port_name}"))
+            .description(format!(
+                "{offer}\n\n✅ **{:.1}%** of units approve | ❌ **{:.1}%** reject\nPasses with a majority of units; voting closes <t:{deadline_ts}:R>.",
+                share(approve), share(reject),
+            ))
+            .color(data::EMBED_GOLD)
+            .footer(default_footer())
+    };
+    let club_state = || async { owner.read().await.stock.portfolios.iter().find(|p| p.name == port_name).and_then(|p| p.club.clone()) };
+
+    let mentions: Vec<String> = members.iter().filter(|id| **id != ctx.author().id).map(|id| format!("<@{id}>")).collect();
+    let reply = ctx.send(poise::CreateReply::default()
+        .content(mentions.join(" "))
+        .embed(tally_embed(&approve, &reject, club_state().await.as_ref()))
+        .components(vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new("cp_yes").label("Approve").style(serenity::ButtonStyle::Success),
+            serenity::CreateButton::new("cp_no").label("Reject").style(serenity::ButtonStyle::Danger),
+        ])])
+    ).await?;
+    let msg = reply.message().await?.into_owned();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(PROPOSAL_VOTE_SECS);
+
+    let outcome = loop {
+        let Some(club) = club_state().await else {
+            break Some(format!("{offer}\nThe club no longer exists."));
+        };
+        match club.tally(&approve, &reject) {
+            Some(true) => break None,
+            Some(false) => break Some(format!("{offer}\n❌ Rejected by members.")),
+            None => {}
+        }
+        let Some(press) = msg
+            .await_component_interaction(ctx.serenity_context())
+            .timeout(deadline.saturating_duration_since(tokio::time::Instant::now()))
+            .await
+        else {
+            break Some(format!("{offer}\n⌛ Voting closed without a majority."));
+        };
+        let voter = press.user.id;
+        if club.member(voter).is_none() {
+            press.create_response(ctx.http(), serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new().content("Only club members can vote.").ephemeral(true),
+            )).await?;
+            continue;
+        }
+        press.defer(ctx.http()).await?;
+        approve.retain(|id| *id != voter);
+        reject.retain(|id| *id != voter);
+        if press.data.custom_id == "cp_yes" { approve.push(voter) } else { reject.push(voter) }
+        reply.edit(ctx, poise::CreateReply::default().embed(tally_embed(&approve, &reject, Some(&club)))).await?;
+    };
+
+    let (desc, color) = match outcome {
+        Some(desc) => (desc, data::EMBED_ERROR),
+        None => {
+            let prices = fetch_prices_map(std::slice::from_ref(&symbol)).await;
+            let fills = match prices.get(&symbol) {
+                None => Err(market_data_err(&symbol)),
+                Some(&usd) => {
+                    let mut ud = owner.write().await;
+                    match ud.stock.find_portfolio_idx(&port_name) {
+                        Some(idx) if ud.stock.portfolios[idx].club.is_some() => {
+                            let held: f64 = ud.stock.portfolios[idx].positions.iter()
+                                .filter(|p| p.ticker == symbol && p.is_long_stock()).map(|p| p.quantity).sum();
+                            let quantity = if side == OrderSide::Sell { quantity.min(held) } else { quantity };
+                            let plan = RebalancePlan {
+                                orders: vec![RebalanceOrder {
+                                    ticker: symbol.clone(), asset_name: quote.display_name(), asset_type: quote.asset_type(),
+                                    side: side.clone(), quantity, price: price_to_creds(usd),
+                                }],
+                                ..RebalancePlan::default()
+                            };
+                            Ok(execute_rebalance(&mut ud.stock, idx, &plan))
+                        }
+                        _ => Err(format!("Club **{port_name}** no longer exists.")),
+                    }
+                }
+            };
+            match fills {
+                Ok(fills) if !fills.is_empty() => (format!("{offer}\n✅ Approved and filled:\n{}", fills.join("\n")), data::EMBED_SUCCESS),
+                Ok(_) => (format!("{offer}\n✅ Approved, but there was nothing left to sell."), data::EMBED_ERROR),
+                Err(desc) => (format!("{offer}\n✅ Approved, but the trade failed: {desc}"), data::EMBED_ERROR),
+            }
+        }
+    };
+    reply.edit(ctx, poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title(format!("Club — Proposal for {port_name}")).description(desc).color(color).footer(default_footer()),
+    ).components(vec![])).await?;
+    Ok(())
+}
+
+/// View a club's NAV, holdings and each member's stake and P&L
+#[poise::command(slash_command)]
+pub async fn club(
+    ctx: Context<'_>,
+    #[description = "Club name (default: list your clubs)"] name: Option<String>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let Some(name) = name else {
+        let owners: Vec<_> = ctx.data().users.iter().map(|e| Arc::clone(e.value())).collect();
+        let mut rows = Vec::new();
+        for u in owners {
+            let ud = u.read().await;
+            for p in &ud.stock.portfolios {
+                if let Some(m) = p.club.as_ref().and_then(|c| c.member(ctx.author().id)) {
+                    let role = if m.role == ClubRole::Manager { "Manager" } else { "Member" };
+                    rows.push(format!("**{}** — {role} | {:.4} units", p.name, m.units));
+                }
+            }
+        }
+        let desc = if rows.is_empty() {
+            "*You aren't in any clubs. Start one with `/club_create`.*".to_string()
+        } else {
+            rows.join("\n")
+        };
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Clubs").description(desc).color(data::EMBED_CYAN).footer(default_footer()),
+        )).await?;
+        return Ok(());
+    };
+
+    let Some((owner, port_name)) = find_club(ctx, &name, Some(ctx.author().id)).await else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Club").description(format!("You aren't in a club named **{name}**.")).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+    let prices = club_prices(&owner, &port_name).await;
+    let Some(port) = owner.read().await.stock.portfolios.iter().find(|p| p.name == port_name).cloned() else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Club").description(format!("Club **{port_name}** no longer exists.")).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+    let club = port.club.clone().unwrap_or_else(|| Club::new(ctx.author().id));
+    let nav = nav_at(&port, &club, &prices).ok();
+    ctx.send(poise::CreateReply::default().embed(build_club_embed(&port, &club, nav, &prices))).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{AssetType, Position};
+
+    #[test]
+    fn nav_at_rejects_unpriced_holdings() {
+        let manager = UserId::new(1);
+        let mut club = Club::new(manager);
+        club.issue(manager, 20_000.0, data::CLUB_INITIAL_UNIT_CREDS).unwrap();
+        let mut port = Portfolio::new("Club".to_string());
+        port.cash = 10_000.0;
+        port.positions.push(Position { ticker: "AAPL".to_string(), asset_type: AssetType::Stock, quantity: 1.0, avg_cost: 10_000.0, lots: Vec::new() });
+
+        let priced = HashMap::from([("AAPL".to_string(), 100.0)]);
+        assert!((nav_at(&port, &club, &priced).unwrap() - 100.0).abs() < 1e-9);
+        // fetch_prices_map reports a failed quote as 0.0
+        let failed = HashMap::from([("AAPL".to_string(), 0.0)]);
+        assert!(nav_at(&port, &club, &failed).is_err());
+        assert!(nav_at(&port, &club, &HashMap::new()).is_err());
+
+        // A short that outgrew the club's cash leaves nothing per unit
+        port.positions[0].quantity = -3.0;
+        assert!(nav_at(&port, &club, &priced).is_err());
+    }
+
+    #[test]
+    fn nav_includes_unposted_interest() {
+        let manager = UserId::new(1);
+        let mut club = Club::new(manager);
+        club.issue(manager, 10_000.0, data::CLUB_INITIAL_UNIT_CREDS).unwrap();
+        let mut port = Portfolio::new("Club".to_string());
+        port.cash = 10_000.0;
+        port.accrued_interest = 100.0;
+        assert!((nav_at(&port, &club, &HashMap::new()).unwrap() - 101.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,336 @@
+//! Copy-trading — a follower portfolio mirrors a leader portfolio's stock, ETF and crypto trades,
+//! scaled by the two portfolios' equity. Leaders opt in with /copy_allow; the Professor always
+//! allows it. Mirroring runs in `api::sweep_copy_trades` after a command trades in a copyable
+//! portfolio and after order sweeps, filling at fresh quotes.
+
+use crate::data::{self, CopySettings, OrderSide, Portfolio, StockProfile, TradeAction, TradeRecord};
+use crate::helper::{creds_to_price, default_footer, price_to_creds};
+use crate::professor::PROFESSOR_PORT;
+use crate::{serenity, Context, Error};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::execute_rebalance;
+use super::rebalance::{RebalanceOrder, RebalancePlan};
+
+/// Per-trade cap a subscription gets unless the follower picks one, in dollars.
+const DEFAULT_MAX_TRADE_USD: f64 = 1_000.0;
+
+/// Whether `stock` recorded a trade in a copyable portfolio at or after `since`.
+pub(crate) fn traded_copyable_since(stock: &StockProfile, since: DateTime<Utc>) -> bool {
+    stock.trade_history.iter().rev()
+        .take_while(|t| t.timestamp >= since)
+        .any(|t| stock.portfolios.iter().any(|p| p.copyable && p.name == t.portfolio))
+}
+
+/// The follower's order mirroring the leader's `trade`: the leader's notional scaled by `ratio`
+/// (follower equity over leader equity) and capped at `max_trade_creds`, filled at the current
+/// `prices` quote (USD). `None` for options and their exercise legs, shorts, transfers, unpriced
+/// tickers, and sells of something the follower doesn't hold.
+fn mirror_order(
+    trade: &TradeRecord,
+    leader: &Portfolio,
+    follower: &Portfolio,
+    (ratio, max_trade_creds): (f64, f64),
+    prices: &HashMap<String, f64>,
+) -> Option<RebalanceOrder> {
+    let side = match trade.action {
+        TradeAction::Buy => OrderSide::Buy,
+        TradeAction::Sell => OrderSide::Sell,
+        _ => return None,
+    };
+    let price = prices.get(&trade.ticker).copied().filter(|&usd| usd > 0.0).map(price_to_creds)?;
+    if trade.option_leg || trade.price_per_unit <= 0.0 {
+        return None;
+    }
+    let notional = (trade.quantity * trade.price_per_unit * ratio).min(max_trade_creds);
+    let held = follower.positions.iter().find(|p| p.ticker == trade.ticker && p.is_long_stock());
+    let (asset_type, quantity) = match side {
+        OrderSide::Buy => {
+            let pos = leader.positions.iter().find(|p| p.ticker == trade.ticker && p.is_long_stock()).or(held)?;
+            (pos.asset_type.clone(), notional / price)
+        }
+        OrderSide::Sell => {
+            let pos = held?;
+            (pos.asset_type.clone(), (notional / price).min(pos.quantity))
+        }
+    };
+    (quantity > 0.0).then(|| RebalanceOrder {
+        ticker: trade.ticker.clone(),
+        asset_name: trade.asset_name.clone(),
+        asset_type,
+        side,
+        quantity,
+        price,
+    })
+}
+
+/// Mirror another member's portfolio (or the Professor's) in one of yours
+#[poise::command(slash_command, guild_only)]
+pub async fn copy(
+    ctx: Context<'_>,
+    #[description = "Your portfolio that will mirror the trades"] portfolio: String,
+    #[description = "Member to copy (default: the Professor)"] leader: Option<serenity::User>,
+    #[description = "Their portfolio to copy (default: ProfessorPort)"] leader_portfolio: Option<String>,
+    #[description = "Largest single mirrored trade in dollars (default: $1,000)"] max_trade: Option<f64>,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Copy Trading").description(desc).color(data::EMBED_ERROR),
+    );
+    let max_trade = max_trade.unwrap_or(DEFAULT_MAX_TRADE_USD);
+    if !(1.0..=data::MAX_FUND_USD).contains(&max_trade) {
+        ctx.send(err_embed(format!("Max trade must be between $1.00 and ${:.2}.", data::MAX_FUND_USD))).await?;
+        return Ok(());
+    }
+    let leader_id = leader.as_ref().map_or(ctx.data().bot_user_id, |u| u.id);
+    let Some(leader_port) = leader_portfolio.or_else(|| leader.is_none().then(|| PROFESSOR_PORT.to_string())) else {
+        ctx.send(err_embed("Name the **leader_portfolio** you want to copy.".to_string())).await?;
+        return Ok(());
+    };
+    if leader_id == ctx.author().id && leader_port.eq_ignore_ascii_case(&portfolio) {
+        ctx.send(err_embed("A portfolio can't copy itself.".to_string())).await?;
+        return Ok(());
+    }
+
+    // Check the leader's consent and how many already follow them
+    let Some(leader_user) = ctx.data().users.get(&leader_id).map(|u| Arc::clone(u.value())) else {
+        ctx.send(err_embed(format!("<@{leader_id}> hasn't started using ProfessorBot yet."))).await?;
+        return Ok(());
+    };
+    let leader_check = {
+        let ud = leader_user.read().await;
+        match ud.stock.portfolios.iter().find(|p| p.name.eq_ignore_ascii_case(&leader_port)) {
+            None => Err(format!("<@{leader_id}> has no portfolio named **{leader_port}**.")),
+            Some(p) if !p.copyable => Err(format!("<@{leader_id}> hasn't allowed copying **{}**. They can turn it on with `/copy_allow`.", p.name)),
+            Some(p) if p.copy.is_some() => Err(format!("**{}** copies another portfolio itself, so it can't be copied.", p.name)),
+            Some(p) => Ok(p.name.clone()),
+        }
+    };
+    let leader_port = match leader_check {
+        Ok(name) => name,
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+    let owners: Vec<_> = ctx.data().users.iter().map(|e| Arc::clone(e.value())).collect();
+    let mut followers = 0;
+    for u in owners {
+        followers += u.read().await.stock.portfolios.iter()
+            .filter(|p| p.copy.as_ref().is_some_and(|c| c.leader == leader_id && c.leader_port == leader_port))
+            .count();
+    }
+    if followers >= data::MAX_COPY_FOLLOWERS {
+        ctx.send(err_embed(format!("**{leader_port}** already has the maximum of **{}** followers.", data::MAX_COPY_FOLLOWERS))).await?;
+        return Ok(());
+    }
+
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let result = {
+        let mut ud = u.write().await;
+        match ud.stock.portfolios.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&portfolio)) {
+            None => Err(format!("No portfolio named **{portfolio}** found.")),
+            Some(p) if p.copyable => Err(format!("Others may copy **{}**, so it can't copy anyone itself. Turn that off with `/copy_allow` first.", p.name)),
+            Some(p) => {
+                p.copy = Some(CopySettings {
+                    leader: leader_id,
+                    leader_port: leader_port.clone(),
+                    max_trade_creds: price_to_creds(max_trade),
+                    synced_at: Utc::now(),
+                });
+                Ok(p.name.clone())
+            }
+        }
+    };
+    let port_name = match result {
+        Ok(name) => name,
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("Copy Trading")
+            .description(format!(
+                "**{port_name}** now mirrors <@{leader_id}>'s **{leader_port}**.\n\n\
+                 • Stock, ETF and crypto trades are copied in proportion to the two portfolios' size.\n\
+                 • No single mirrored trade goes over **${max_trade:.2}**, and buys are trimmed to your free cash.\n\
+                 • You'll be pinged whenever a trade is mirrored. Stop any time with `/copy_stop`.",
+            ))
+            .color(data::EMBED_SUCCESS)
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// Stop mirroring trades in one of your portfolios
+#[poise::command(slash_command)]
+pub async fn copy_stop(
+    ctx: Context<'_>,
+    #[description = "Portfolio that copies another"] portfolio: String,
+) -> Result<(), Error> {
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let stopped = {
+        let mut ud = u.write().await;
+        ud.stock.portfolios.iter_mut()
+            .find(|p| p.name.eq_ignore_ascii_case(&portfolio))
+            .map(|p| (p.name.clone(), p.copy.take()))
+    };
+    let (desc, color) = match stopped {
+        None => (format!("No portfolio named **{portfolio}** found."), data::EMBED_ERROR),
+        Some((name, None)) => (format!("**{name}** isn't copying anyone."), data::EMBED_ERROR),
+        Some((name, Some(c))) => (format!("**{name}** stopped copying <@{}>'s **{}**. Its holdings stay as they are.", c.leader, c.leader_port), data::EMBED_SUCCESS),
+    };
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Copy Trading").description(desc).color(color).footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// Let other members copy one of your portfolios, or stop them
+#[poise::command(slash_command)]
+pub async fn copy_allow(
+    ctx: Context<'_>,
+    #[description = "Your portfolio"] portfolio: String,
+    #[description = "Whether others may copy its trades"] allow: bool,
+) -> Result<(), Error> {
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let result = {
+        let mut ud = u.write().await;
+        match ud.stock.portfolios.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&portfolio)) {
+            None => Err(format!("No portfolio named **{portfolio}** found.")),
+            Some(p) if allow && p.copy.is_some() => Err(format!("**{}** copies another portfolio, so it can't be copied. Run `/copy_stop` first.", p.name)),
+            Some(p) => {
+                p.copyable = allow;
+                Ok(p.name.clone())
+            }
+        }
+    };
+    let (desc, color) = match result {
+        Ok(name) if allow => (format!(
+            "Members can now copy **{name}** with `/copy`. Your trades there, and their value, will be mirrored into their portfolios."
+        ), data::EMBED_SUCCESS),
+        Ok(name) => (format!("**{name}** can no longer be copied. Current followers are unsubscribed on their next sync."), data::EMBED_SUCCESS),
+        Err(desc) => (desc, data::EMBED_ERROR),
+    };
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Copy Trading").description(desc).color(color).footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// Mirrors `trades` into `stock.portfolios[idx]` one at a time at `prices` (USD), so each is
+/// sized against the holdings the previous one left. Returns one line per fill.
+pub(crate) fn mirror_trades(
+    stock: &mut StockProfile,
+    idx: usize,
+    leader: &Portfolio,
+    trades: &[&TradeRecord],
+    sizing: (f64, f64),
+    prices: &HashMap<String, f64>,
+) -> Vec<String> {
+    let mut fills = Vec::new();
+    for trade in trades {
+        if let Some(order) = mirror_order(trade, leader, &stock.portfolios[idx], sizing, prices) {
+            fills.extend(execute_rebalance(stock, idx, &RebalancePlan { orders: vec![order], ..RebalancePlan::default() }));
+        }
+    }
+    fills
+}
+
+/// The message pinging a follower about trades mirrored into `port_name`.
+pub(crate) fn fmt_copy_notice(follower: serenity::UserId, port_name: &str, settings: &CopySettings, fills: &[String]) -> String {
+    format!(
+        "<@{follower}> Copied <@{}>'s **{}** into **{port_name}** (cap ${:.2} per trade):\n{}",
+        settings.leader, settings.leader_port, creds_to_price(settings.max_trade_creds), fills.join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{AssetType, Position, TradeFees};
+
+    fn trade(action: TradeAction, ticker: &str, asset_name: &str, quantity: f64, price: f64) -> TradeRecord {
+        TradeRecord {
+            portfolio: "lead".to_string(),
+            ticker: ticker.to_string(),
+            asset_name: asset_name.to_string(),
+            action,
+            quantity,
+            price_per_unit: price,
+            total_creds: quantity * price,
+            realized_pnl: None,
+            timestamp: Utc::now(),
+            fees: TradeFees::default(),
+            lots: Vec::new(),
+            option_leg: false,
+        }
+    }
+
+    fn quotes(aapl_usd: f64) -> HashMap<String, f64> {
+        HashMap::from([("AAPL".to_string(), aapl_usd)])
+    }
+
+    fn holding(ticker: &str, quantity: f64) -> Position {
+        Position { ticker: ticker.to_string(), asset_type: AssetType::Stock, quantity, avg_cost: 100.0, lots: Vec::new() }
+    }
+
+    #[test]
+    fn buys_scale_by_equity_and_respect_the_cap() {
+        let mut leader = Portfolio::new("lead".to_string());
+        leader.positions.push(holding("AAPL", 10.0));
+        let follower = Portfolio::new("follow".to_string());
+        let prices = quotes(100.0);
+
+        // Leader bought $1,000; a follower a tenth the size buys $100
+        let buy = trade(TradeAction::Buy, "AAPL", "Apple Inc.", 10.0, 10_000.0);
+        let order = mirror_order(&buy, &leader, &follower, (0.1, 1e9), &prices).unwrap();
+        assert_eq!(order.side, OrderSide::Buy);
+        assert!((order.quantity - 1.0).abs() < 1e-9);
+
+        // ...unless the cap is lower
+        let order = mirror_order(&buy, &leader, &follower, (0.1, 5_000.0), &prices).unwrap();
+        assert!((order.quantity - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fills_at_the_current_quote() {
+        let mut leader = Portfolio::new("lead".to_string());
+        leader.positions.push(holding("AAPL", 10.0));
+        let follower = Portfolio::new("follow".to_string());
+        let buy = trade(TradeAction::Buy, "AAPL", "Apple Inc.", 10.0, 10_000.0);
+
+        // The same $1,000 buys half as many shares after the price doubles
+        let order = mirror_order(&buy, &leader, &follower, (1.0, 1e9), &quotes(200.0)).unwrap();
+        assert_eq!(order.price, 20_000.0);
+        assert!((order.quantity - 5.0).abs() < 1e-9);
+        assert!(mirror_order(&buy, &leader, &follower, (1.0, 1e9), &quotes(0.0)).is_none());
+        assert!(mirror_order(&buy, &leader, &follower, (1.0, 1e9), &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn sells_are_clamped_and_options_skipped() {
+        let leader = Portfolio::new("lead".to_string());
+        let mut follower = Portfolio::new("follow".to_string());
+        follower.positions.push(holding("AAPL", 2.0));
+        let mut prices = quotes(100.0);
+        prices.insert("MSFT".to_string(), 1.0);
+
+        let sell = trade(TradeAction::Sell, "AAPL", "Apple Inc.", 50.0, 10_000.0);
+        let order = mirror_order(&sell, &leader, &follower, (1.0, 1e9), &prices).unwrap();
+        assert!((order.quantity - 2.0).abs() < 1e-9);
+        assert!(mirror_order(&trade(TradeAction::Sell, "MSFT", "Microsoft", 1.0, 100.0), &leader, &follower, (1.0, 1e9), &prices).is_none());
+
+        let mut call = trade(TradeAction::Buy, "AAPL", "AAPL CALL $200.00 2026-01-16", 1.0, 500.0);
+        call.option_leg = true;
+        assert!(mirror_order(&call, &leader, &follower, (1.0, 1e9), &prices).is_none());
+        // Shares delivered by an assignment carry a plain name but are still option legs
+        let mut assigned = trade(TradeAction::Sell, "AAPL", "AAPL", 1.0, 10_000.0);
+        assigned.option_leg = true;
+        assert!(mirror_order(&assigned, &leader, &follower, (1.0, 1e9), &prices).is_none());
+        assert!(mirror_order(&trade(TradeAction::Short, "AAPL", "Apple Inc.", 1.0, 100.0), &leader, &follower, (1.0, 1e9), &prices).is_none());
+    }
+}
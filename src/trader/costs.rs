@@ -0,0 +1,141 @@
+//! Simulated execution costs — commission, bid/ask spread, and size-based slippage.
+//! Defaults can be overridden through environment variables at startup.
+
+use crate::data::{AssetType, TradeFees};
+use crate::helper::creds_to_price;
+use std::sync::LazyLock;
+
+/// Flat commission charged per fill, in creds.
+pub const DEFAULT_COMMISSION_CREDS: f64 = 10.0;
+/// Full quoted bid/ask spread in basis points; each fill pays half of it.
+pub const DEFAULT_STOCK_SPREAD_BPS: f64 = 5.0;
+pub const DEFAULT_ETF_SPREAD_BPS: f64 = 2.0;
+pub const DEFAULT_CRYPTO_SPREAD_BPS: f64 = 25.0;
+/// Option quotes are far wider than their underlying's, so premiums pay a wider spread.
+pub const DEFAULT_OPTION_SPREAD_BPS: f64 = 200.0;
+/// Market-impact slippage in basis points per $1,000 of notional.
+pub const DEFAULT_SLIPPAGE_BPS_PER_1K_USD: f64 = 1.0;
+/// Upper bound on slippage for a single fill, in basis points.
+pub const DEFAULT_MAX_SLIPPAGE_BPS: f64 = 50.0;
+
+/// Process-wide cost model used by every live trade path.
+pub(crate) static COST_MODEL: LazyLock<CostModel> = LazyLock::new(CostModel::from_env);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CostModel {
+    pub commission_creds: f64,
+    pub stock_spread_bps: f64,
+    pub etf_spread_bps: f64,
+    pub crypto_spread_bps: f64,
+    pub option_spread_bps: f64,
+    pub slippage_bps_per_1k_usd: f64,
+    pub max_slippage_bps: f64,
+}
+
+impl CostModel {
+    pub const DEFAULT: Self = Self {
+        commission_creds: DEFAULT_COMMISSION_CREDS,
+        stock_spread_bps: DEFAULT_STOCK_SPREAD_BPS,
+        etf_spread_bps: DEFAULT_ETF_SPREAD_BPS,
+        crypto_spread_bps: DEFAULT_CRYPTO_SPREAD_BPS,
+        option_spread_bps: DEFAULT_OPTION_SPREAD_BPS,
+        slippage_bps_per_1k_usd: DEFAULT_SLIPPAGE_BPS_PER_1K_USD,
+        max_slippage_bps: DEFAULT_MAX_SLIPPAGE_BPS,
+    };
+
+    /// Frictionless fills — used by tests and anywhere costs should not apply.
+    #[cfg_attr(not(test), expect(dead_code, reason = "frictionless model is only used by engine tests today"))]
+    pub const FREE: Self = Self {
+        commission_creds: 0.0,
+        stock_spread_bps: 0.0,
+        etf_spread_bps: 0.0,
+        crypto_spread_bps: 0.0,
+        option_spread_bps: 0.0,
+        slippage_bps_per_1k_usd: 0.0,
+        max_slippage_bps: 0.0,
+    };
+
+    fn from_env() -> Self {
+        let var = |key: &str, default: f64| {
+            std::env::var(key).ok().and_then(|v| v.parse::<f64>().ok()).filter(|v| *v >= 0.0).unwrap_or(default)
+        };
+        let d = Self::DEFAULT;
+        Self {
+            commission_creds: var("TRADE_COMMISSION_CREDS", d.commission_creds),
+            stock_spread_bps: var("TRADE_SPREAD_BPS_STOCK", d.stock_spread_bps),
+            etf_spread_bps: var("TRADE_SPREAD_BPS_ETF", d.etf_spread_bps),
+            crypto_spread_bps: var("TRADE_SPREAD_BPS_CRYPTO", d.crypto_spread_bps),
+            option_spread_bps: var("TRADE_SPREAD_BPS_OPTION", d.option_spread_bps),
+            slippage_bps_per_1k_usd: var("TRADE_SLIPPAGE_BPS_PER_1K", d.slippage_bps_per_1k_usd),
+            max_slippage_bps: var("TRADE_SLIPPAGE_MAX_BPS", d.max_slippage_bps),
+        }
+    }
+
+    const fn spread_bps(&self, asset_type: &AssetType) -> f64 {
+        match asset_type {
+            AssetType::ETF => self.etf_spread_bps,
+            AssetType::Crypto => self.crypto_spread_bps,
+            AssetType::Stock => self.stock_spread_bps,
+            AssetType::Option(_) => self.option_spread_bps,
+        }
+    }
+
+    /// Costs of filling `notional_creds` worth of `asset_type`. Zero-size fills are free.
+    pub fn fees(&self, asset_type: &AssetType, notional_creds: f64) -> TradeFees {
+        self.fees_at(self.spread_bps(asset_type), notional_creds)
+    }
+
+    /// Costs of opening or closing options for `premium_creds` in total premium.
+    pub fn option_fees(&self, premium_creds: f64) -> TradeFees {
+        self.fees_at(self.option_spread_bps, premium_creds)
+    }
+
+    fn fees_at(&self, spread_bps: f64, notional_creds: f64) -> TradeFees {
+        if notional_creds <= 0.0 {
+            return TradeFees::default();
+        }
+        let slippage_bps = (self.slippage_bps_per_1k_usd * creds_to_price(notional_creds) / 1000.0)
+            .min(self.max_slippage_bps);
+        TradeFees {
+            commission: self.commission_creds,
+            spread: notional_creds * spread_bps / 2.0 / 10_000.0,
+            slippage: notional_creds * slippage_bps / 10_000.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_model_charges_nothing() {
+        let fees = CostModel::FREE.fees(&AssetType::Stock, 1_000_000.0);
+        assert_eq!(fees.total(), 0.0);
+    }
+
+    #[test]
+    fn spread_depends_on_asset_type() {
+        let m = CostModel::DEFAULT;
+        // $100 notional — half of the quoted spread is paid
+        assert!((m.fees(&AssetType::Stock, 10_000.0).spread - 2.5).abs() < 1e-9);
+        assert!((m.fees(&AssetType::ETF, 10_000.0).spread - 1.0).abs() < 1e-9);
+        assert!((m.fees(&AssetType::Crypto, 10_000.0).spread - 12.5).abs() < 1e-9);
+        assert!((m.option_fees(10_000.0).spread - 100.0).abs() < 1e-9);
+        assert!((m.option_fees(10_000.0).commission - DEFAULT_COMMISSION_CREDS).abs() < 1e-9);
+    }
+
+    #[test]
+    fn slippage_grows_with_size_and_caps() {
+        let m = CostModel::DEFAULT;
+        let small = m.fees(&AssetType::Stock, 100_000.0);     // $1,000 → 1 bps
+        let large = m.fees(&AssetType::Stock, 10_000_000.0);  // $100,000 → capped at 50 bps
+        assert!((small.slippage - 10.0).abs() < 1e-9);
+        assert!((large.slippage - 50_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn zero_notional_is_free() {
+        assert_eq!(CostModel::DEFAULT.fees(&AssetType::Stock, 0.0), TradeFees::default());
+    }
+}
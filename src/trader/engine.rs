@@ -1,9 +1,18 @@
 //! Core trade execution — `apply_buy` and `apply_sell` are pure functions that
 //! mutate Portfolio + `TradeRecord` state without any Discord or async concerns.
 //! Keeping them isolated here makes them straightforward to unit-test.
+//! Execution costs come from the `CostModel` passed in; live callers use `COST_MODEL`.
+//! Long stock positions keep individual tax lots; sales relieve them per `Portfolio::lot_method`.
+//! `apply_transfer` moves cash and holdings between a user's portfolios without trading.
 
-use crate::data::{AssetType, Portfolio, Position, TradeAction, TradeRecord, TRADE_HISTORY_LIMIT};
-use chrono::Utc;
+use super::costs::CostModel;
+use crate::data::{
+    AssetType, HoldingPeriod, LotMethod, LotRelief, Portfolio, Position, TaxLot, TradeAction, TradeFees, TradeRecord,
+    LONG_TERM_HOLDING_DAYS, SHORT_INITIAL_MARGIN_RATIO, SHORT_MAINTENANCE_MARGIN_RATIO, TRADE_HISTORY_LIMIT,
+    TRANSFER_CASH_TICKER,
+};
+use crate::helper::creds_to_price;
+use chrono::{DateTime, Utc};
 use std::collections::VecDeque;
 
 #[expect(clippy::too_many_arguments, reason = "apply_buy mirrors the full trade record — all fields are required")]
@@ -17,12 +26,16 @@ pub(crate) fn apply_buy(
     price_per_unit: f64,
     total_cost_creds: f64,
     portfolio_name: &str,
+    costs: &CostModel,
 ) {
-    port.cash -= total_cost_creds;
+    let fees = costs.fees(&asset_type, total_cost_creds);
+    port.cash -= total_cost_creds + fees.total();
+    let now = Utc::now();
 
-    if let Some(existing) = port.positions.iter_mut().find(|p| {
-        p.ticker == ticker && !matches!(&p.asset_type, AssetType::Option(_))
-    }) {
+    if let Some(existing) = port.positions.iter_mut().find(|p| p.ticker == ticker && p.is_long_stock()) {
+        existing.ensure_lots(port.created_at);
+        let id = existing.next_lot_id();
+        existing.lots.push(TaxLot { id, acquired: now, quantity, cost: total_cost_creds / quantity });
         let total_qty = existing.quantity + quantity;
         existing.avg_cost = existing.avg_cost.mul_add(existing.quantity, total_cost_creds) / total_qty;
         existing.quantity = total_qty;
@@ -32,6 +45,7 @@ pub(crate) fn apply_buy(
             asset_type,
             quantity,
             avg_cost: price_per_unit,
+            lots: vec![TaxLot { id: 1, acquired: now, quantity, cost: total_cost_creds / quantity }],
         });
     }
 
@@ -44,13 +58,19 @@ pub(crate) fn apply_buy(
         price_per_unit,
         total_creds: total_cost_creds,
         realized_pnl: None,
-        timestamp: Utc::now(),
+        timestamp: now,
+        fees,
+        lots: Vec::new(),
+        option_leg: false,
     });
     if history.len() > TRADE_HISTORY_LIMIT {
         history.pop_front();
     }
 }
 
+/// Sells `quantity` of a long position, relieving lots per the portfolio's `lot_method`.
+/// Returns the gross realized P&L, or `None` if there is no long position in `ticker`.
+#[expect(clippy::too_many_arguments, reason = "apply_sell mirrors the full trade record — all fields are required")]
 pub(crate) fn apply_sell(
     port: &mut Portfolio,
     history: &mut VecDeque<TradeRecord>,
@@ -59,19 +79,64 @@ pub(crate) fn apply_sell(
     quantity: f64,
     price_per_unit: f64,
     portfolio_name: &str,
+    costs: &CostModel,
 ) -> Option<f64> {
-    let pos_idx = port.positions.iter()
-        .position(|p| p.ticker == ticker && !matches!(&p.asset_type, AssetType::Option(_)))?;
+    sell_long(port, history, ticker, asset_name, None, quantity, price_per_unit, portfolio_name, costs)
+}
+
+/// Sells `quantity` out of one specific lot. Returns `None` if the lot doesn't exist
+/// or holds less than `quantity`.
+#[expect(clippy::too_many_arguments, reason = "apply_sell_lot mirrors the full trade record — all fields are required")]
+pub(crate) fn apply_sell_lot(
+    port: &mut Portfolio,
+    history: &mut VecDeque<TradeRecord>,
+    ticker: &str,
+    asset_name: &str,
+    lot_id: u32,
+    quantity: f64,
+    price_per_unit: f64,
+    portfolio_name: &str,
+    costs: &CostModel,
+) -> Option<f64> {
+    sell_long(port, history, ticker, asset_name, Some(lot_id), quantity, price_per_unit, portfolio_name, costs)
+}
+
+#[expect(clippy::too_many_arguments, reason = "shared body of apply_sell and apply_sell_lot")]
+fn sell_long(
+    port: &mut Portfolio,
+    history: &mut VecDeque<TradeRecord>,
+    ticker: &str,
+    asset_name: &str,
+    lot_id: Option<u32>,
+    quantity: f64,
+    price_per_unit: f64,
+    portfolio_name: &str,
+    costs: &CostModel,
+) -> Option<f64> {
+    let pos_idx = port.positions.iter().position(|p| p.ticker == ticker && p.is_long_stock())?;
+    let now = Utc::now();
+    let method = port.lot_method;
+    let created_at = port.created_at;
+
+    let pos = &mut port.positions[pos_idx];
+    pos.ensure_lots(created_at);
+    let relieved = relieve_lots(&mut pos.lots, method, lot_id, quantity, price_per_unit, now)?;
 
-    let avg_cost = port.positions[pos_idx].avg_cost;
     let proceeds = price_per_unit * quantity;
-    let pnl      = avg_cost.mul_add(-quantity, proceeds);
+    let pnl      = relieved.iter().map(|l| l.pnl).sum::<f64>();
+    let fees     = costs.fees(&pos.asset_type, proceeds);
 
-    port.cash += proceeds;
-    port.positions[pos_idx].quantity -= quantity;
-    if port.positions[pos_idx].quantity < 1e-9 {
+    port.cash += proceeds - fees.total();
+    let pos = &mut port.positions[pos_idx];
+    pos.quantity -= quantity;
+    if pos.quantity < 1e-9 {
         // Sub-nanoshare residuals treated as fully closed
         port.positions.remove(pos_idx);
+    } else {
+        let lot_qty: f64 = pos.lots.iter().map(|l| l.quantity).sum();
+        if lot_qty > 0.0 {
+            pos.avg_cost = pos.lots.iter().map(|l| l.quantity * l.cost).sum::<f64>() / lot_qty;
+        }
     }
 
     history.push_back(TradeRecord {
@@ -83,7 +148,173 @@ pub(crate) fn apply_sell(
         price_per_unit,
         total_creds: proceeds,
         realized_pnl: Some(pnl),
+        timestamp: now,
+        fees,
+        lots: relieved,
+        option_leg: false,
+    });
+    if history.len() > TRADE_HISTORY_LIMIT {
+        history.pop_front();
+    }
+    Some(pnl)
+}
+
+/// Removes `quantity` units from `lots` — a single lot when `lot_id` is given, otherwise
+/// in `method` order — and returns what each lot gave up. Leaves `lots` untouched on `None`.
+fn relieve_lots(
+    lots: &mut Vec<TaxLot>,
+    method: LotMethod,
+    lot_id: Option<u32>,
+    quantity: f64,
+    price_per_unit: f64,
+    now: DateTime<Utc>,
+) -> Option<Vec<LotRelief>> {
+    let mut order: Vec<usize> = (0..lots.len()).collect();
+    match (lot_id, method) {
+        (Some(id), _) => {
+            let idx = lots.iter().position(|l| l.id == id)?;
+            if lots[idx].quantity + 1e-9 < quantity {
+                return None;
+            }
+            order = vec![idx];
+        }
+        (None, LotMethod::Fifo) => order.sort_by_key(|&i| lots[i].acquired),
+        (None, LotMethod::Lifo) => order.sort_by_key(|&i| std::cmp::Reverse(lots[i].acquired)),
+        (None, LotMethod::Hifo) => order.sort_by(|&a, &b| lots[b].cost.total_cmp(&lots[a].cost)),
+    }
+
+    let mut remaining = quantity;
+    let mut relieved = Vec::new();
+    for i in order {
+        if remaining < 1e-9 {
+            break;
+        }
+        let lot = &mut lots[i];
+        let take = remaining.min(lot.quantity);
+        lot.quantity -= take;
+        remaining -= take;
+        let holding_period = if (now - lot.acquired).num_days() > LONG_TERM_HOLDING_DAYS {
+            HoldingPeriod::LongTerm
+        } else {
+            HoldingPeriod::ShortTerm
+        };
+        relieved.push(LotRelief {
+            lot_id: lot.id,
+            acquired: lot.acquired,
+            quantity: take,
+            cost: lot.cost,
+            pnl: (price_per_unit - lot.cost) * take,
+            holding_period,
+        });
+    }
+    lots.retain(|l| l.quantity > 1e-9);
+    Some(relieved)
+}
+
+/// Cash that must be free to open a short of `shares` at `price_usd`: the proceeds
+/// stay locked in the portfolio, plus `SHORT_INITIAL_MARGIN_RATIO` of the value.
+pub(crate) fn short_margin_usd(price_usd: f64, shares: f64) -> f64 {
+    price_usd * shares * SHORT_INITIAL_MARGIN_RATIO
+}
+
+/// Minimum portfolio equity required to keep `shares` short at `price_usd`.
+pub(crate) fn short_maintenance_usd(price_usd: f64, shares: f64) -> f64 {
+    price_usd * shares * SHORT_MAINTENANCE_MARGIN_RATIO
+}
+
+/// Sell-to-open `quantity` borrowed shares. Proceeds (net of costs) are credited to cash
+/// and locked by `Portfolio::locked_cash` until covered.
+#[expect(clippy::too_many_arguments, reason = "apply_short mirrors the full trade record — all fields are required")]
+pub(crate) fn apply_short(
+    port: &mut Portfolio,
+    history: &mut VecDeque<TradeRecord>,
+    ticker: &str,
+    asset_name: &str,
+    asset_type: AssetType,
+    quantity: f64,
+    price_per_unit: f64,
+    portfolio_name: &str,
+    costs: &CostModel,
+) {
+    let proceeds = price_per_unit * quantity;
+    let fees = costs.fees(&asset_type, proceeds);
+    port.cash += proceeds - fees.total();
+    if port.last_borrow_charged.is_none() {
+        port.last_borrow_charged = Some(Utc::now());
+    }
+
+    if let Some(existing) = port.positions.iter_mut().find(|p| p.ticker == ticker && p.is_short_stock()) {
+        let held = -existing.quantity;
+        existing.avg_cost = existing.avg_cost.mul_add(held, proceeds) / (held + quantity);
+        existing.quantity -= quantity;
+    } else {
+        port.positions.push(Position {
+            ticker: ticker.to_string(),
+            asset_type,
+            quantity: -quantity,
+            avg_cost: price_per_unit,
+            lots: Vec::new(),
+        });
+    }
+
+    history.push_back(TradeRecord {
+        portfolio: portfolio_name.to_string(),
+        ticker: ticker.to_string(),
+        asset_name: asset_name.to_string(),
+        action: TradeAction::Short,
+        quantity,
+        price_per_unit,
+        total_creds: proceeds,
+        realized_pnl: None,
         timestamp: Utc::now(),
+        fees,
+        lots: Vec::new(),
+        option_leg: false,
+    });
+    if history.len() > TRADE_HISTORY_LIMIT {
+        history.pop_front();
+    }
+}
+
+/// Buy-to-close `quantity` shares of a short position. Returns the gross P&L,
+/// or `None` if there is no short position in `ticker`.
+#[expect(clippy::too_many_arguments, reason = "apply_cover mirrors the full trade record — all fields are required")]
+pub(crate) fn apply_cover(
+    port: &mut Portfolio,
+    history: &mut VecDeque<TradeRecord>,
+    ticker: &str,
+    asset_name: &str,
+    quantity: f64,
+    price_per_unit: f64,
+    portfolio_name: &str,
+    costs: &CostModel,
+) -> Option<f64> {
+    let pos_idx = port.positions.iter().position(|p| p.ticker == ticker && p.is_short_stock())?;
+
+    let avg_cost = port.positions[pos_idx].avg_cost;
+    let cost = price_per_unit * quantity;
+    let pnl  = avg_cost.mul_add(quantity, -cost);
+    let fees = costs.fees(&port.positions[pos_idx].asset_type, cost);
+
+    port.cash -= cost + fees.total();
+    port.positions[pos_idx].quantity += quantity;
+    if port.positions[pos_idx].quantity > -1e-9 {
+        port.positions.remove(pos_idx);
+    }
+
+    history.push_back(TradeRecord {
+        portfolio: portfolio_name.to_string(),
+        ticker: ticker.to_string(),
+        asset_name: asset_name.to_string(),
+        action: TradeAction::Cover,
+        quantity,
+        price_per_unit,
+        total_creds: cost,
+        realized_pnl: Some(pnl),
+        timestamp: Utc::now(),
+        fees,
+        lots: Vec::new(),
+        option_leg: false,
     });
     if history.len() > TRADE_HISTORY_LIMIT {
         history.pop_front();
@@ -91,6 +322,135 @@ pub(crate) fn apply_sell(
     Some(pnl)
 }
 
+/// Moves `cash` creds and, with `holding` = (ticker, quantity, creds per unit), that many units
+/// of a long position from `portfolios[from]` to `portfolios[to]`. Units leave the source in its
+/// lot order and keep their acquisition dates and cost. Everything is checked before either
+/// portfolio changes. Both sides log a transfer at cost basis, and the moved value counts as
+/// a cash flow so it doesn't show up as a return.
+pub(crate) fn apply_transfer(
+    portfolios: &mut [Portfolio],
+    history: &mut VecDeque<TradeRecord>,
+    from: usize,
+    to: usize,
+    cash: f64,
+    holding: Option<(&str, f64, f64)>,
+) -> Result<(), String> {
+    if from == to {
+        return Err("Pick two different portfolios.".to_string());
+    }
+    let (src, dst) = if from < to {
+        let (left, right) = portfolios.split_at_mut(to);
+        (&mut left[from], &mut right[0])
+    } else {
+        let (left, right) = portfolios.split_at_mut(from);
+        (&mut right[0], &mut left[to])
+    };
+    if cash < 0.0 || (cash == 0.0 && holding.is_none()) {
+        return Err("Nothing to transfer — give an amount of cash, a ticker, or both.".to_string());
+    }
+    let free = src.cash - src.locked_cash();
+    if cash > free + 1e-9 {
+        return Err(format!("**{}** only has **${:.2}** of free cash.", src.name, creds_to_price(free.max(0.0))));
+    }
+    let moving = match holding {
+        None => None,
+        Some((ticker, quantity, price_per_unit)) => {
+            let Some(idx) = src.positions.iter().position(|p| p.ticker == ticker && p.is_long_stock()) else {
+                return Err(format!("**{}** holds no **{ticker}** to move. Options and shorts stay where they were opened.", src.name));
+            };
+            let held = src.positions[idx].quantity;
+            if quantity <= 0.0 || quantity > held + 1e-9 {
+                return Err(format!("**{}** holds **{}** {ticker}.", src.name, crate::helper::fmt_qty(held)));
+            }
+            if src.cash < 0.0 {
+                return Err(format!("**{}** has a margin loan against its holdings — repay it before moving them out.", src.name));
+            }
+            Some((idx, ticker, quantity.min(held), price_per_unit))
+        }
+    };
+
+    let now = Utc::now();
+    let mut log = |portfolio: &str, ticker: &str, action: TradeAction, quantity: f64, total_creds: f64| {
+        history.push_back(TradeRecord {
+            portfolio: portfolio.to_string(),
+            ticker: ticker.to_string(),
+            asset_name: ticker.to_string(),
+            action,
+            quantity,
+            price_per_unit: total_creds / quantity,
+            total_creds,
+            realized_pnl: None,
+            timestamp: now,
+            fees: TradeFees::default(),
+            lots: Vec::new(),
+            option_leg: false,
+        });
+        if history.len() > TRADE_HISTORY_LIMIT {
+            history.pop_front();
+        }
+    };
+
+    if cash > 0.0 {
+        src.cash -= cash;
+        dst.cash += cash;
+        src.pending_flow -= cash;
+        dst.pending_flow += cash;
+        let dollars = creds_to_price(cash);
+        log(&src.name, TRANSFER_CASH_TICKER, TradeAction::TransferOut, dollars, cash);
+        log(&dst.name, TRANSFER_CASH_TICKER, TradeAction::TransferIn, dollars, cash);
+    }
+
+    if let Some((idx, ticker, quantity, price_per_unit)) = moving {
+        let (method, created_at) = (src.lot_method, src.created_at);
+        let pos = &mut src.positions[idx];
+        pos.ensure_lots(created_at);
+        let moved = relieve_lots(&mut pos.lots, method, None, quantity, 0.0, now).unwrap_or_default();
+        let asset_type = pos.asset_type.clone();
+        let basis: f64 = moved.iter().map(|l| l.quantity * l.cost).sum();
+        pos.quantity -= quantity;
+        if pos.quantity < 1e-9 {
+            src.positions.remove(idx);
+        } else {
+            let lot_qty: f64 = pos.lots.iter().map(|l| l.quantity).sum();
+            if lot_qty > 0.0 {
+                pos.avg_cost = pos.lots.iter().map(|l| l.quantity * l.cost).sum::<f64>() / lot_qty;
+            }
+        }
+
+        let dst_created = dst.created_at;
+        let pos = match dst.positions.iter().position(|p| p.ticker == ticker && p.is_long_stock()) {
+            Some(i) => {
+                let pos = &mut dst.positions[i];
+                pos.ensure_lots(dst_created);
+                pos.avg_cost = pos.avg_cost.mul_add(pos.quantity, basis) / (pos.quantity + quantity);
+                pos.quantity += quantity;
+                pos
+            }
+            None => {
+                dst.positions.push(Position {
+                    ticker: ticker.to_string(),
+                    asset_type,
+                    quantity,
+                    avg_cost: basis / quantity,
+                    lots: Vec::new(),
+                });
+                dst.positions.last_mut().expect("just pushed")
+            }
+        };
+        for lot in &moved {
+            let id = pos.next_lot_id();
+            pos.lots.push(TaxLot { id, acquired: lot.acquired, quantity: lot.quantity, cost: lot.cost });
+        }
+
+        let value = price_per_unit * quantity;
+        src.pending_flow -= value;
+        dst.pending_flow += value;
+        log(&src.name, ticker, TradeAction::TransferOut, quantity, basis);
+        log(&dst.name, ticker, TradeAction::TransferIn, quantity, basis);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,7 +465,7 @@ mod tests {
     #[test]
     fn buy_creates_new_position() {
         let (mut port, mut history) = make_port();
-        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1500.0, 15_000.0, "TestPort");
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1500.0, 15_000.0, "TestPort", &CostModel::FREE);
 
         assert_eq!(port.cash, 85_000.0);
         assert_eq!(port.positions.len(), 1);
@@ -119,9 +479,9 @@ mod tests {
     fn buy_averages_cost_on_existing_position() {
         let (mut port, mut history) = make_port();
         // Buy 10 @ 1000 creds/unit
-        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort");
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort", &CostModel::FREE);
         // Buy 10 more @ 2000 creds/unit
-        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 2000.0, 20_000.0, "TestPort");
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 2000.0, 20_000.0, "TestPort", &CostModel::FREE);
 
         assert_eq!(port.positions.len(), 1);
         assert_eq!(port.positions[0].quantity, 20.0);
@@ -131,8 +491,8 @@ mod tests {
     #[test]
     fn sell_removes_position_when_fully_closed() {
         let (mut port, mut history) = make_port();
-        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort");
-        let pnl = apply_sell(&mut port, &mut history, "AAPL", "Apple", 10.0, 1500.0, "TestPort");
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort", &CostModel::FREE);
+        let pnl = apply_sell(&mut port, &mut history, "AAPL", "Apple", 10.0, 1500.0, "TestPort", &CostModel::FREE);
 
         assert!(port.positions.is_empty());
         assert_eq!(pnl, Some(5000.0)); // (1500 - 1000) * 10
@@ -142,8 +502,8 @@ mod tests {
     #[test]
     fn sell_partial_reduces_quantity() {
         let (mut port, mut history) = make_port();
-        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort");
-        apply_sell(&mut port, &mut history, "AAPL", "Apple", 5.0, 1000.0, "TestPort");
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort", &CostModel::FREE);
+        apply_sell(&mut port, &mut history, "AAPL", "Apple", 5.0, 1000.0, "TestPort", &CostModel::FREE);
 
         assert_eq!(port.positions[0].quantity, 5.0);
     }
@@ -151,15 +511,168 @@ mod tests {
     #[test]
     fn sell_nonexistent_position_returns_none() {
         let (mut port, mut history) = make_port();
-        let result = apply_sell(&mut port, &mut history, "NVDA", "Nvidia", 1.0, 500.0, "TestPort");
+        let result = apply_sell(&mut port, &mut history, "NVDA", "Nvidia", 1.0, 500.0, "TestPort", &CostModel::FREE);
         assert!(result.is_none());
     }
 
     #[test]
     fn sell_pnl_negative_on_loss() {
         let (mut port, mut history) = make_port();
-        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 2000.0, 20_000.0, "TestPort");
-        let pnl = apply_sell(&mut port, &mut history, "AAPL", "Apple", 10.0, 1000.0, "TestPort");
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 2000.0, 20_000.0, "TestPort", &CostModel::FREE);
+        let pnl = apply_sell(&mut port, &mut history, "AAPL", "Apple", 10.0, 1000.0, "TestPort", &CostModel::FREE);
         assert_eq!(pnl, Some(-10_000.0)); // sold at loss
     }
+
+    #[test]
+    fn costs_debited_from_cash_and_recorded() {
+        let (mut port, mut history) = make_port();
+        let costs = CostModel::DEFAULT;
+        let buy_fees = costs.fees(&AssetType::Stock, 10_000.0);
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort", &costs);
+        assert!((port.cash - (90_000.0 - buy_fees.total())).abs() < 1e-9);
+        assert_eq!(history[0].fees, buy_fees);
+        // Cost basis stays at the quoted price — fees are tracked separately
+        assert_eq!(port.positions[0].avg_cost, 1000.0);
+
+        let sell_fees = costs.fees(&AssetType::Stock, 15_000.0);
+        let pnl = apply_sell(&mut port, &mut history, "AAPL", "Apple", 10.0, 1500.0, "TestPort", &costs);
+        assert_eq!(pnl, Some(5000.0)); // gross
+        assert_eq!(history[1].fees, sell_fees);
+        assert!((port.cash - (105_000.0 - buy_fees.total() - sell_fees.total())).abs() < 1e-9);
+    }
+
+    fn two_lot_port(method: LotMethod) -> (Portfolio, VecDeque<TradeRecord>) {
+        let (mut port, mut history) = make_port();
+        port.lot_method = method;
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort", &CostModel::FREE);
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 2000.0, 20_000.0, "TestPort", &CostModel::FREE);
+        // Make lot #1 older than lot #2 regardless of how fast the test runs
+        port.positions[0].lots[0].acquired -= chrono::Duration::days(400);
+        (port, history)
+    }
+
+    #[test]
+    fn lot_method_changes_realized_pnl() {
+        let sell = |method| {
+            let (mut port, mut history) = two_lot_port(method);
+            let pnl = apply_sell(&mut port, &mut history, "AAPL", "Apple", 10.0, 1500.0, "TestPort", &CostModel::FREE);
+            (pnl, port.positions[0].avg_cost)
+        };
+        assert_eq!(sell(LotMethod::Fifo), (Some(5000.0), 2000.0));
+        assert_eq!(sell(LotMethod::Lifo), (Some(-5000.0), 1000.0));
+        assert_eq!(sell(LotMethod::Hifo), (Some(-5000.0), 1000.0));
+    }
+
+    #[test]
+    fn sale_records_lots_and_holding_period() {
+        let (mut port, mut history) = two_lot_port(LotMethod::Fifo);
+        apply_sell(&mut port, &mut history, "AAPL", "Apple", 15.0, 1500.0, "TestPort", &CostModel::FREE);
+        let lots = &history[2].lots;
+        assert_eq!(lots.len(), 2);
+        assert_eq!((lots[0].lot_id, lots[0].quantity, lots[0].holding_period), (1, 10.0, HoldingPeriod::LongTerm));
+        assert_eq!((lots[1].lot_id, lots[1].quantity, lots[1].holding_period), (2, 5.0, HoldingPeriod::ShortTerm));
+        assert_eq!(history[2].pnl_by_term(), (-2500.0, 5000.0));
+    }
+
+    #[test]
+    fn sell_specific_lot() {
+        let (mut port, mut history) = two_lot_port(LotMethod::Fifo);
+        let pnl = apply_sell_lot(&mut port, &mut history, "AAPL", "Apple", 2, 4.0, 2500.0, "TestPort", &CostModel::FREE);
+        assert_eq!(pnl, Some(2000.0));
+        assert_eq!(port.positions[0].quantity, 16.0);
+        assert_eq!(port.positions[0].lots[1].quantity, 6.0);
+        // Too many units for the lot, or an unknown lot, is rejected without side effects
+        assert!(apply_sell_lot(&mut port, &mut history, "AAPL", "Apple", 2, 7.0, 2500.0, "TestPort", &CostModel::FREE).is_none());
+        assert!(apply_sell_lot(&mut port, &mut history, "AAPL", "Apple", 9, 1.0, 2500.0, "TestPort", &CostModel::FREE).is_none());
+        assert_eq!(port.positions[0].quantity, 16.0);
+    }
+
+    #[test]
+    fn untracked_quantity_backfilled_as_lot() {
+        let (mut port, mut history) = make_port();
+        port.positions.push(Position { ticker: "AAPL".to_string(), asset_type: AssetType::Stock, quantity: 5.0, avg_cost: 1000.0, lots: Vec::new() });
+        apply_buy(&mut port, &mut history, "AAPL", "Apple", AssetType::Stock, 5.0, 2000.0, 10_000.0, "TestPort", &CostModel::FREE);
+        let lots = &port.positions[0].lots;
+        assert_eq!(lots.len(), 2);
+        assert_eq!((lots[0].id, lots[0].quantity, lots[0].cost), (1, 5.0, 1000.0));
+        assert_eq!((lots[1].id, lots[1].cost), (2, 2000.0));
+    }
+
+    #[test]
+    fn short_then_cover_realizes_gain() {
+        let (mut port, mut history) = make_port();
+        apply_short(&mut port, &mut history, "TSLA", "Tesla", AssetType::Stock, 10.0, 2000.0, "TestPort", &CostModel::FREE);
+        assert_eq!(port.cash, 120_000.0);
+        assert_eq!(port.positions[0].quantity, -10.0);
+        // proceeds + 50% initial margin are locked
+        assert_eq!(port.locked_cash(), 30_000.0);
+
+        let pnl = apply_cover(&mut port, &mut history, "TSLA", "Tesla", 10.0, 1500.0, "TestPort", &CostModel::FREE);
+        assert_eq!(pnl, Some(5000.0));
+        assert!(port.positions.is_empty());
+        assert_eq!(port.cash, 105_000.0);
+        assert_eq!(history[1].action, TradeAction::Cover);
+    }
+
+    #[test]
+    fn short_averages_entry_and_ignores_long_sells() {
+        let (mut port, mut history) = make_port();
+        apply_short(&mut port, &mut history, "TSLA", "Tesla", AssetType::Stock, 10.0, 1000.0, "TestPort", &CostModel::FREE);
+        apply_short(&mut port, &mut history, "TSLA", "Tesla", AssetType::Stock, 10.0, 2000.0, "TestPort", &CostModel::FREE);
+        assert_eq!(port.positions.len(), 1);
+        assert_eq!(port.positions[0].quantity, -20.0);
+        assert_eq!(port.positions[0].avg_cost, 1500.0);
+        // apply_sell only closes long positions
+        assert!(apply_sell(&mut port, &mut history, "TSLA", "Tesla", 1.0, 1000.0, "TestPort", &CostModel::FREE).is_none());
+    }
+
+    #[test]
+    fn short_margin_requirements() {
+        assert!((short_margin_usd(100.0, 10.0) - 500.0).abs() < 1e-9);
+        assert!((short_maintenance_usd(100.0, 10.0) - 300.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transfer_moves_lots_at_cost_and_counts_as_flow() {
+        let (mut a, mut history) = make_port();
+        apply_buy(&mut a, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort", &CostModel::FREE);
+        apply_buy(&mut a, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 2000.0, 20_000.0, "TestPort", &CostModel::FREE);
+        let mut b = Portfolio::new("Other".to_string());
+        b.cash = 1_000.0;
+        let mut ports = vec![a, b];
+
+        // FIFO source: the 12 units moved are all 10 of the 1000-cost lot and 2 of the 2000-cost lot
+        apply_transfer(&mut ports, &mut history, 0, 1, 5_000.0, Some(("AAPL", 12.0, 2500.0))).unwrap();
+        let (a, b) = (&ports[0], &ports[1]);
+        assert_eq!(a.cash, 65_000.0);
+        assert_eq!(b.cash, 6_000.0);
+        assert_eq!(a.positions[0].quantity, 8.0);
+        assert_eq!(a.positions[0].avg_cost, 2000.0);
+        assert_eq!(b.positions[0].quantity, 12.0);
+        assert!((b.positions[0].avg_cost - 14_000.0 / 12.0).abs() < 1e-9);
+        assert_eq!(b.positions[0].lots.len(), 2);
+        // Both cash and the holding's market value count as flows, so neither shows up as a return
+        assert_eq!(a.pending_flow, -35_000.0);
+        assert_eq!(b.pending_flow, 35_000.0);
+        let transfers: Vec<_> = history.iter().filter(|t| matches!(t.action, TradeAction::TransferIn | TradeAction::TransferOut)).collect();
+        assert_eq!(transfers.len(), 4);
+        assert_eq!(transfers[3].action, TradeAction::TransferIn);
+        assert_eq!(transfers[3].total_creds, 14_000.0);
+    }
+
+    #[test]
+    fn failed_transfer_changes_nothing() {
+        let (mut a, mut history) = make_port();
+        apply_buy(&mut a, &mut history, "AAPL", "Apple", AssetType::Stock, 10.0, 1000.0, 10_000.0, "TestPort", &CostModel::FREE);
+        let mut ports = vec![a, Portfolio::new("Other".to_string())];
+        let before = history.len();
+        // Cash is fine but the holding isn't there in that size
+        assert!(apply_transfer(&mut ports, &mut history, 0, 1, 1_000.0, Some(("AAPL", 11.0, 1000.0))).is_err());
+        assert!(apply_transfer(&mut ports, &mut history, 0, 1, 1_000_000.0, None).is_err());
+        assert!(apply_transfer(&mut ports, &mut history, 0, 0, 1_000.0, None).is_err());
+        assert_eq!(ports[0].cash, 90_000.0);
+        assert_eq!(ports[0].positions[0].quantity, 10.0);
+        assert!(ports[1].positions.is_empty());
+        assert_eq!(history.len(), before);
+    }
 }
@@ -0,0 +1,215 @@
+//! /lots command — view tax lots, choose the lot-relief method, and sell a specific lot.
+
+use super::engine::apply_sell_lot;
+use super::COST_MODEL;
+use crate::api::{fetch_price, is_market_hours, market_data_err};
+use crate::data::{self, HoldingPeriod, LotMethod, Portfolio, LONG_TERM_HOLDING_DAYS};
+use crate::helper::{creds_to_price, default_footer, fmt_pnl, fmt_qty, price_to_creds};
+use crate::{serenity, Context, Error};
+use chrono::Utc;
+use poise::serenity_prelude::EditMessage;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Sell Specific Lot"]
+pub(crate) struct SellLotModal {
+    #[name = "Lot number"]
+    #[placeholder = "e.g. 2"]
+    pub lot_id: String,
+    #[name = "Quantity (blank = whole lot)"]
+    pub quantity: Option<String>,
+}
+
+const METHODS: [(LotMethod, &str); 3] = [
+    (LotMethod::Fifo, "lots_fifo"),
+    (LotMethod::Lifo, "lots_lifo"),
+    (LotMethod::Hifo, "lots_hifo"),
+];
+
+fn build_lots_embed(port: &Portfolio, ticker: &str, price_usd: f64) -> serenity::CreateEmbed {
+    let now = Utc::now();
+    let mut desc = format!("**Lot method:** {}\n\n", port.lot_method.label());
+
+    let pos = port.positions.iter().find(|p| p.ticker == ticker && p.is_long_stock());
+    match pos {
+        None => desc += &format!("*No long **{ticker}** position in **{}**.*", port.name),
+        Some(pos) => {
+            let mut pos = pos.clone();
+            pos.ensure_lots(port.created_at);
+            let price_creds = price_to_creds(price_usd);
+            for lot in &pos.lots {
+                let held_days = (now - lot.acquired).num_days();
+                let term = if held_days > LONG_TERM_HOLDING_DAYS { "LT" } else { "ST" };
+                desc += &format!(
+                    "`#{}` {} — **{}** @ ${:.2} | {}d ({}) | {}\n",
+                    lot.id, lot.acquired.format("%Y-%m-%d"), fmt_qty(lot.quantity),
+                    creds_to_price(lot.cost), held_days, term,
+                    fmt_pnl((price_creds - lot.cost) * lot.quantity),
+                );
+            }
+            desc += &format!("\nNow: **${price_usd:.2}** | Avg cost: **${:.2}**", creds_to_price(pos.avg_cost));
+        }
+    }
+
+    serenity::CreateEmbed::new()
+        .title(format!("Tax Lots — {ticker} ({})", port.name))
+        .description(desc)
+        .color(data::EMBED_CYAN)
+        .footer(default_footer())
+}
+
+fn lots_buttons(current: LotMethod, has_lots: bool) -> Vec<serenity::CreateActionRow> {
+    let mut buttons: Vec<serenity::CreateButton> = METHODS.iter().map(|(m, id)| {
+        serenity::CreateButton::new(*id)
+            .label(m.label())
+            .style(if *m == current { serenity::ButtonStyle::Success } else { serenity::ButtonStyle::Secondary })
+    }).collect();
+    if has_lots {
+        buttons.push(serenity::CreateButton::new("lots_sell").label("Sell Lot").style(serenity::ButtonStyle::Danger));
+    }
+    vec![serenity::CreateActionRow::Buttons(buttons)]
+}
+
+/// View tax lots for a holding and choose how lots are sold
+#[poise::command(slash_command)]
+pub async fn lots(
+    ctx: Context<'_>,
+    #[description = "Ticker symbol (e.g. AAPL)"] ticker: String,
+    #[description = "Portfolio holding the position"] portfolio: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let ticker = ticker.trim().to_uppercase();
+    let Some(mut price_usd) = fetch_price(&ticker).await else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Tax Lots").description(market_data_err(&ticker)).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let serenity_ctx = ctx.serenity_context().clone();
+    let Some(port) = ({
+        let ud = u.read().await;
+        ud.stock.find_portfolio_idx(&portfolio).map(|i| ud.stock.portfolios[i].clone())
+    }) else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new().title("Tax Lots").description(format!("No portfolio named **{portfolio}** found.")).color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+    let port_name = port.name.clone();
+    let has_lots = port.positions.iter().any(|p| p.ticker == ticker && p.is_long_stock());
+
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(build_lots_embed(&port, &ticker, price_usd))
+        .components(lots_buttons(port.lot_method, has_lots)))
+        .await?;
+    let mut msg = reply.into_message().await?;
+
+    loop {
+        let Some(press) = msg
+            .await_component_interaction(&serenity_ctx)
+            .author_id(ctx.author().id)
+            .timeout(Duration::from_secs(60))
+            .await
+        else {
+            msg.edit(&serenity_ctx, EditMessage::default().components(vec![])).await.ok();
+            break;
+        };
+
+        let mut notice: Option<serenity::CreateEmbed> = None;
+        if let Some((method, _)) = METHODS.iter().find(|(_, id)| *id == press.data.custom_id) {
+            press.create_response(&serenity_ctx, serenity::CreateInteractionResponse::Acknowledge).await.ok();
+            let mut ud = u.write().await;
+            if let Some(i) = ud.stock.find_portfolio_idx(&port_name) {
+                ud.stock.portfolios[i].lot_method = *method;
+            }
+        } else if press.data.custom_id == "lots_sell" {
+            let Some(modal) = poise::execute_modal_on_component_interaction::<SellLotModal>(
+                ctx, press, None, Some(Duration::from_secs(30)),
+            ).await? else { continue; };
+
+            notice = Some(if !is_market_hours() {
+                serenity::CreateEmbed::new().title("Sell Lot")
+                    .description("Specific-lot sales can only be placed during market hours.")
+                    .color(data::EMBED_ERROR)
+            } else if let Some(fresh) = fetch_price(&ticker).await.filter(|p| *p > 0.0) {
+                // The embed's quote may be minutes old by the time the modal is submitted
+                price_usd = fresh;
+                sell_lot(&u, &port_name, &ticker, price_usd, &modal).await
+            } else {
+                serenity::CreateEmbed::new().title("Sell Lot")
+                    .description(market_data_err(&ticker))
+                    .color(data::EMBED_ERROR)
+            });
+        } else {
+            continue;
+        }
+
+        let Some(port) = ({
+            let ud = u.read().await;
+            ud.stock.find_portfolio_idx(&port_name).map(|i| ud.stock.portfolios[i].clone())
+        }) else { break };
+        let has_lots = port.positions.iter().any(|p| p.ticker == ticker && p.is_long_stock());
+        let mut embeds = vec![build_lots_embed(&port, &ticker, price_usd)];
+        embeds.extend(notice);
+        msg.edit(&serenity_ctx, EditMessage::default()
+            .embeds(embeds)
+            .components(lots_buttons(port.lot_method, has_lots)))
+            .await.ok();
+    }
+
+    Ok(())
+}
+
+async fn sell_lot(
+    u: &Arc<tokio::sync::RwLock<data::UserData>>,
+    port_name: &str,
+    ticker: &str,
+    price_usd: f64,
+    modal: &SellLotModal,
+) -> serenity::CreateEmbed {
+    let err = |desc: String| serenity::CreateEmbed::new().title("Sell Lot").description(desc).color(data::EMBED_ERROR);
+    let Ok(lot_id) = modal.lot_id.trim().trim_start_matches('#').parse::<u32>() else {
+        return err("Enter a lot number from the list.".to_string());
+    };
+
+    let mut ud = u.write().await;
+    let Some(port_idx) = ud.stock.find_portfolio_idx(port_name) else {
+        return err(format!("Portfolio **{port_name}** no longer exists."));
+    };
+    let created_at = ud.stock.portfolios[port_idx].created_at;
+    let Some(lot_qty) = ud.stock.portfolios[port_idx].positions.iter_mut()
+        .find(|p| p.ticker == ticker && p.is_long_stock())
+        .and_then(|p| { p.ensure_lots(created_at); p.lots.iter().find(|l| l.id == lot_id).map(|l| l.quantity) })
+    else {
+        return err(format!("Lot **#{lot_id}** not found."));
+    };
+
+    let quantity = match modal.quantity.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        None => lot_qty,
+        Some(raw) => match raw.parse::<f64>() {
+            Ok(q) if q > 0.0 && q <= lot_qty + 5e-5 => q.min(lot_qty),
+            _ => return err(format!("Quantity must be between 0 and **{}**.", fmt_qty(lot_qty))),
+        },
+    };
+
+    let stock = &mut ud.stock;
+    let Some(pnl) = apply_sell_lot(
+        &mut stock.portfolios[port_idx], &mut stock.trade_history, ticker, ticker,
+        lot_id, quantity, price_to_creds(price_usd), port_name, &COST_MODEL,
+    ) else {
+        return err(format!("Lot **#{lot_id}** could not be sold."));
+    };
+    let term = stock.trade_history.back()
+        .and_then(|t| t.lots.first())
+        .map_or("", |l| if l.holding_period == HoldingPeriod::LongTerm { "long-term" } else { "short-term" });
+
+    serenity::CreateEmbed::new().title("Sell Lot")
+        .description(format!(
+            "Sold **{} {}** from lot **#{}** @ ${:.2} — {} gain/loss: **{}**",
+            fmt_qty(quantity), ticker, lot_id, price_usd, term, fmt_pnl(pnl),
+        ))
+        .color(if pnl >= 0.0 { data::EMBED_SUCCESS } else { data::EMBED_FAIL })
+}
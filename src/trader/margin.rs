@@ -0,0 +1,155 @@
+//! Margin account math — equity, buying power, maintenance checks, and loan interest.
+//! Pure functions over a `Portfolio` and a ticker → USD price map.
+//!
+//! A margin loan is modelled as a negative cash balance; `margin_ratio` caps how far
+//! below zero cash may go relative to the portfolio's long market value.
+
+use crate::data::{AssetType, OptionSide, Portfolio, MARGIN_MAINTENANCE_RATIO, MARGIN_RATE_SPREAD};
+use crate::helper::{option_intrinsic, price_to_creds};
+use std::collections::HashMap;
+
+/// Extra fraction of the loan raised by a forced liquidation to absorb execution costs.
+const LIQUIDATION_BUFFER: f64 = 0.01;
+
+/// Portfolio equity in creds: cash plus longs minus shorts at `prices`, with options at intrinsic.
+pub(crate) fn portfolio_equity(port: &Portfolio, prices: &HashMap<String, f64>) -> f64 {
+    port.cash + port.positions.iter().map(|pos| {
+        let price_usd = prices.get(&pos.ticker).copied().unwrap_or(0.0);
+        match &pos.asset_type {
+            AssetType::Option(c) => {
+                let value = price_to_creds(option_intrinsic(c.option_type, price_usd, c.strike) * 100.0) * f64::from(c.contracts);
+                if c.side == OptionSide::Short { -value } else { value }
+            }
+            _ => price_to_creds(price_usd) * pos.quantity,
+        }
+    }).sum::<f64>()
+}
+
+/// Market value of long stock/ETF/crypto holdings in creds. Unpriced tickers fall back to cost basis.
+pub(crate) fn long_market_value(port: &Portfolio, prices: &HashMap<String, f64>) -> f64 {
+    port.positions.iter()
+        .filter(|p| p.is_long_stock())
+        .map(|p| prices.get(&p.ticker).map_or(p.avg_cost, |&usd| price_to_creds(usd)) * p.quantity)
+        .sum()
+}
+
+/// Outstanding margin loan in creds.
+pub(crate) fn margin_loan(port: &Portfolio) -> f64 {
+    (-port.cash).max(0.0)
+}
+
+/// Creds available for new purchases. Cash accounts can only spend their cash not locked as
+/// collateral for short stock or short options.
+pub(crate) fn buying_power(port: &Portfolio, prices: &HashMap<String, f64>) -> f64 {
+    let free_cash = port.cash - port.locked_cash();
+    if port.margin_ratio <= 0.0 {
+        return free_cash;
+    }
+    port.margin_ratio.mul_add(long_market_value(port, prices), free_cash)
+}
+
+/// Annual margin loan rate (percent), tracking the fed funds rate.
+pub(crate) fn margin_annual_rate(fed_rate: f64) -> f64 {
+    fed_rate + MARGIN_RATE_SPREAD
+}
+
+/// Simple interest on `loan` creds for `days` at `annual_rate` percent.
+pub(crate) fn margin_interest(loan: f64, annual_rate: f64, days: i64) -> f64 {
+    loan * annual_rate / 100.0 / 365.0 * days as f64
+}
+
+/// Creds of equity missing to meet `MARGIN_MAINTENANCE_RATIO`, or `None` if the
+/// portfolio has no loan or is adequately margined.
+pub(crate) fn maintenance_shortfall(port: &Portfolio, prices: &HashMap<String, f64>) -> Option<f64> {
+    if margin_loan(port) <= 0.0 {
+        return None;
+    }
+    let required = MARGIN_MAINTENANCE_RATIO * long_market_value(port, prices);
+    let equity = portfolio_equity(port, prices);
+    (equity < required).then_some(required - equity)
+}
+
+/// Long positions to sell (ticker, quantity), largest holdings first, so the loan is repaid.
+pub(crate) fn liquidation_plan(port: &Portfolio, prices: &HashMap<String, f64>) -> Vec<(String, f64)> {
+    let mut needed = margin_loan(port) * (1.0 + LIQUIDATION_BUFFER);
+    let mut longs: Vec<_> = port.positions.iter()
+        .filter(|p| p.is_long_stock())
+        .filter_map(|p| prices.get(&p.ticker).map(|&usd| (p, price_to_creds(usd))))
+        .collect();
+    longs.sort_by(|a, b| (b.1 * b.0.quantity).total_cmp(&(a.1 * a.0.quantity)));
+
+    let mut plan = Vec::new();
+    for (pos, price) in longs {
+        if needed <= 0.0 {
+            break;
+        }
+        let qty = (needed / price).min(pos.quantity);
+        needed -= qty * price;
+        plan.push((pos.ticker.clone(), qty));
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Position;
+
+    fn margin_port(cash: f64) -> Portfolio {
+        let mut port = Portfolio::new("M".to_string());
+        port.cash = cash;
+        port.margin_ratio = 0.5;
+        port.positions.push(Position { ticker: "AAPL".to_string(), asset_type: AssetType::Stock, quantity: 10.0, avg_cost: 10_000.0, lots: Vec::new() });
+        port
+    }
+
+    #[test]
+    fn buying_power_cash_vs_margin() {
+        let prices = HashMap::from([("AAPL".to_string(), 100.0)]);
+        let mut port = margin_port(5_000.0);
+        // 5,000 cash + 50% of 100,000 long value
+        assert!((buying_power(&port, &prices) - 55_000.0).abs() < 1e-9);
+        port.margin_ratio = 0.0;
+        assert_eq!(buying_power(&port, &prices), 5_000.0);
+    }
+
+    #[test]
+    fn short_proceeds_cannot_fund_a_buy() {
+        use super::super::costs::CostModel;
+        use super::super::engine::apply_short;
+        let mut port = Portfolio::new("C".to_string());
+        port.cash = 10_000.0;
+        let mut history = std::collections::VecDeque::new();
+        apply_short(&mut port, &mut history, "TSLA", "Tesla", AssetType::Stock, 10.0, 2000.0, "C", &CostModel::FREE);
+        // 20,000 proceeds plus 10,000 initial margin are locked out of 30,000 cash
+        assert_eq!(port.cash, 30_000.0);
+        assert_eq!(buying_power(&port, &HashMap::new()), 0.0);
+        port.margin_ratio = 0.5;
+        assert_eq!(buying_power(&port, &HashMap::new()), 0.0);
+    }
+
+    #[test]
+    fn shortfall_only_when_borrowed_and_under_maintenance() {
+        let healthy = HashMap::from([("AAPL".to_string(), 100.0)]);
+        let crashed = HashMap::from([("AAPL".to_string(), 40.0)]);
+        assert!(maintenance_shortfall(&margin_port(5_000.0), &crashed).is_none()); // no loan
+        let port = margin_port(-40_000.0);
+        assert!(maintenance_shortfall(&port, &healthy).is_none()); // equity 60k ≥ 25k
+        // equity 0 vs required 10,000
+        assert!((maintenance_shortfall(&port, &crashed).unwrap() - 10_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn liquidation_plan_covers_loan() {
+        let prices = HashMap::from([("AAPL".to_string(), 100.0)]);
+        let plan = liquidation_plan(&margin_port(-20_000.0), &prices);
+        assert_eq!(plan.len(), 1);
+        assert!((plan[0].1 - 2.02).abs() < 1e-9); // 20,000 × 1.01 / 10,000 per share
+    }
+
+    #[test]
+    fn margin_interest_is_simple_daily() {
+        assert!((margin_interest(36_500.0, 10.0, 1) - 10.0).abs() < 1e-9);
+        assert!((margin_annual_rate(4.0) - 6.0).abs() < 1e-9);
+    }
+}
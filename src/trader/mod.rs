@@ -1,12 +1,36 @@
-//! Portfolio, watchlist, trades, and core trade execution engine.
+//! Portfolio, sharing, club, copy-trading, watchlist, trades, and core trade execution engine (with simulated costs).
 
+mod club;
+mod copy;
+mod costs;
 mod engine;
+mod lots;
+mod margin;
+mod performance;
 mod portfolio;
+mod rebalance;
+mod risk;
+mod share;
 mod trades;
+mod transfer;
 mod watchlist;
 
 // Re-export engine functions so professor.rs and stock/ can use the same path
-#[doc(inline)] pub(crate) use engine::{apply_buy, apply_sell};
+#[doc(inline)] pub(crate) use club::{club, club_create, club_deposit, club_member, club_propose, club_redeem};
+#[doc(inline)] pub(crate) use copy::{copy, copy_allow, copy_stop, fmt_copy_notice, mirror_trades, traded_copyable_since};
+#[doc(inline)] pub(crate) use costs::{CostModel, COST_MODEL};
+#[doc(inline)] pub(crate) use engine::{
+    apply_buy, apply_cover, apply_sell, apply_short, apply_transfer, short_maintenance_usd, short_margin_usd,
+};
+#[doc(inline)] pub(crate) use lots::lots;
+#[doc(inline)] pub(crate) use margin::{
+    buying_power, liquidation_plan, maintenance_shortfall, margin_annual_rate, margin_interest, margin_loan,
+    portfolio_equity,
+};
+#[doc(inline)] pub(crate) use performance::benchmark_stats;
 #[doc(inline)] pub(crate) use portfolio::portfolio;
+#[doc(inline)] pub(crate) use rebalance::{execute_rebalance, rebalance, rebalance_plan, rebalance_tickers};
+#[doc(inline)] pub(crate) use share::{portfolio_privacy, portfolio_view, share_card};
 #[doc(inline)] pub(crate) use trades::trades;
+#[doc(inline)] pub(crate) use transfer::transfer;
 #[doc(inline)] pub(crate) use watchlist::watchlist;
@@ -0,0 +1,205 @@
+//! Performance history from daily value snapshots — time-weighted returns, drawdown, and
+//! comparison against the benchmark index. Pure functions, no Discord concerns.
+
+use crate::data::ValueSnapshot;
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// Growth factor between two consecutive snapshots with the period's deposits and withdrawals
+/// taken out, or `None` when the earlier value is not positive.
+fn period_growth(prev: &ValueSnapshot, cur: &ValueSnapshot) -> Option<f64> {
+    (prev.total > 0.0).then(|| (cur.total - cur.net_flow) / prev.total)
+}
+
+/// Time-weighted return over `snaps` from the last snapshot on or before `since` (or the first
+/// snapshot, for younger portfolios) to the latest, as a fraction. `None` with fewer than two snapshots.
+pub(crate) fn time_weighted_return(snaps: &[ValueSnapshot], since: NaiveDate) -> Option<f64> {
+    if snaps.len() < 2 {
+        return None;
+    }
+    let start = snaps.iter().rposition(|s| s.date <= since).unwrap_or(0);
+    let growth: f64 = snaps[start..].windows(2).filter_map(|w| period_growth(&w[0], &w[1])).product();
+    Some(growth - 1.0)
+}
+
+/// Largest peak-to-trough fall of the flow-adjusted value, as a non-positive fraction.
+pub(crate) fn max_drawdown(snaps: &[ValueSnapshot]) -> f64 {
+    let mut index = 1.0_f64;
+    let mut peak = 1.0_f64;
+    let mut worst = 0.0_f64;
+    for w in snaps.windows(2) {
+        index *= period_growth(&w[0], &w[1]).unwrap_or(1.0);
+        peak = peak.max(index);
+        worst = worst.min(index / peak - 1.0);
+    }
+    worst
+}
+
+/// Labelled 1W/1M/YTD/All returns as of the latest snapshot.
+pub(crate) fn period_returns(snaps: &[ValueSnapshot]) -> Vec<(&'static str, Option<f64>)> {
+    let Some(latest) = snaps.last().map(|s| s.date) else { return Vec::new() };
+    let year_start = NaiveDate::from_ymd_opt(latest.year(), 1, 1).unwrap_or(latest);
+    vec![
+        ("1W", time_weighted_return(snaps, latest - Duration::days(7))),
+        ("1M", time_weighted_return(snaps, latest - Duration::days(30))),
+        ("YTD", time_weighted_return(snaps, year_start - Duration::days(1))),
+        ("All", time_weighted_return(snaps, NaiveDate::MIN)),
+    ]
+}
+
+/// Flow-adjusted value as a growth index starting at 1.0, one point per snapshot.
+pub(crate) fn growth_index(snaps: &[ValueSnapshot]) -> Vec<f64> {
+    let mut index = 1.0_f64;
+    std::iter::once(1.0)
+        .chain(snaps.windows(2).map(|w| {
+            index *= period_growth(&w[0], &w[1]).unwrap_or(1.0);
+            index
+        }))
+        .take(snaps.len())
+        .collect()
+}
+
+/// Benchmark price relative to the first recorded one, one point per snapshot. Snapshots missing
+/// a price repeat the previous point. `None` if no snapshot has a benchmark price.
+pub(crate) fn benchmark_index(snaps: &[ValueSnapshot]) -> Option<Vec<f64>> {
+    let base = snaps.iter().map(|s| s.benchmark).find(|b| *b > 0.0)?;
+    let mut last = 1.0;
+    Some(snaps.iter().map(|s| {
+        if s.benchmark > 0.0 {
+            last = s.benchmark / base;
+        }
+        last
+    }).collect())
+}
+
+/// Paired periods needed before alpha and beta are reported.
+const MIN_BENCHMARK_PERIODS: usize = 5;
+
+/// A portfolio measured against the benchmark over the same snapshots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BenchmarkStats {
+    /// Time-weighted portfolio return, as a fraction.
+    pub portfolio: f64,
+    /// Benchmark price return, as a fraction.
+    pub benchmark: f64,
+    /// Annualised return not explained by benchmark exposure (Jensen's alpha, no risk-free rate).
+    pub alpha: Option<f64>,
+    /// Sensitivity of period returns to the benchmark's.
+    pub beta: Option<f64>,
+}
+
+impl BenchmarkStats {
+    /// Portfolio return minus benchmark return.
+    pub fn relative(&self) -> f64 {
+        self.portfolio - self.benchmark
+    }
+}
+
+/// Compares snapshots dated on or after `since` against their recorded benchmark prices.
+/// Periods missing a benchmark price are skipped. `None` until there is at least one usable period.
+pub(crate) fn benchmark_stats(snaps: &[ValueSnapshot], since: NaiveDate) -> Option<BenchmarkStats> {
+    let start = snaps.iter().position(|s| s.date >= since)?;
+    let pairs: Vec<(f64, f64)> = snaps[start..].windows(2)
+        .filter(|w| w[0].benchmark > 0.0 && w[1].benchmark > 0.0)
+        .filter_map(|w| Some((period_growth(&w[0], &w[1])? - 1.0, w[1].benchmark / w[0].benchmark - 1.0)))
+        .collect();
+    if pairs.is_empty() {
+        return None;
+    }
+    let portfolio = pairs.iter().map(|(p, _)| 1.0 + p).product::<f64>() - 1.0;
+    let benchmark = pairs.iter().map(|(_, b)| 1.0 + b).product::<f64>() - 1.0;
+
+    let n = pairs.len() as f64;
+    let mean_p = pairs.iter().map(|(p, _)| p).sum::<f64>() / n;
+    let mean_b = pairs.iter().map(|(_, b)| b).sum::<f64>() / n;
+    let cov = pairs.iter().map(|(p, b)| (p - mean_p) * (b - mean_b)).sum::<f64>() / n;
+    let var = pairs.iter().map(|(_, b)| (b - mean_b).powi(2)).sum::<f64>() / n;
+    let (alpha, beta) = if pairs.len() >= MIN_BENCHMARK_PERIODS && var > 0.0 {
+        let beta = cov / var;
+        let days = (snaps[snaps.len() - 1].date - snaps[start].date).num_days().max(1) as f64;
+        let periods_per_year = 365.0 * n / days;
+        (Some(beta.mul_add(-mean_b, mean_p) * periods_per_year), Some(beta))
+    } else {
+        (None, None)
+    };
+    Some(BenchmarkStats { portfolio, benchmark, alpha, beta })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snap(day: u32, total: f64, net_flow: f64) -> ValueSnapshot {
+        ValueSnapshot {
+            date: NaiveDate::from_ymd_opt(2026, 3, day).unwrap(),
+            total,
+            cash: total,
+            positions: 0.0,
+            net_flow,
+            benchmark: 0.0,
+        }
+    }
+
+    #[test]
+    fn indexes_start_at_one_and_skip_flows() {
+        let mut snaps = [snap(1, 1_000.0, 0.0), snap(2, 2_200.0, 1_100.0), snap(3, 2_420.0, 0.0)];
+        let index = growth_index(&snaps);
+        assert_eq!(index.len(), 3);
+        assert!((index[1] - 1.1).abs() < 1e-9 && (index[2] - 1.21).abs() < 1e-9);
+
+        assert!(benchmark_index(&snaps).is_none());
+        snaps[1].benchmark = 400.0;
+        snaps[2].benchmark = 500.0;
+        assert_eq!(benchmark_index(&snaps).unwrap(), [1.0, 1.0, 1.25]);
+    }
+
+    #[test]
+    fn deposits_do_not_count_as_returns() {
+        // +10%, then a 1,100 deposit doubles the value with no market move, then +10% again
+        let snaps = [snap(1, 1_000.0, 0.0), snap(2, 1_100.0, 0.0), snap(3, 2_200.0, 1_100.0), snap(4, 2_420.0, 0.0)];
+        let twr = time_weighted_return(&snaps, NaiveDate::MIN).unwrap();
+        assert!((twr - 0.21).abs() < 1e-9);
+        // Window starts at the last snapshot on or before the date
+        let recent = time_weighted_return(&snaps, NaiveDate::from_ymd_opt(2026, 3, 3).unwrap()).unwrap();
+        assert!((recent - 0.10).abs() < 1e-9);
+        assert!(time_weighted_return(&snaps[..1], NaiveDate::MIN).is_none());
+    }
+
+    #[test]
+    fn drawdown_ignores_withdrawals() {
+        // Peak 1,200, trough 900 (−25%), and a 500 withdrawal that isn't a loss
+        let snaps = [
+            snap(1, 1_000.0, 0.0),
+            snap(2, 1_200.0, 0.0),
+            snap(3, 900.0, 0.0),
+            snap(4, 400.0, -500.0),
+            snap(5, 1_300.0, 0.0),
+        ];
+        assert!((max_drawdown(&snaps) + 0.25).abs() < 1e-9);
+        assert!(max_drawdown(&snaps[..2]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn benchmark_beta_and_alpha() {
+        // Portfolio moves exactly twice the benchmark each day: β 2, no alpha
+        let moves = [0.01, -0.02, 0.015, 0.005, -0.01, 0.02];
+        let mut snaps = vec![ValueSnapshot { benchmark: 100.0, ..snap(1, 1_000.0, 0.0) }];
+        for (i, m) in moves.iter().enumerate() {
+            let prev = snaps[i];
+            snaps.push(ValueSnapshot {
+                benchmark: prev.benchmark * (1.0 + m),
+                ..snap(i as u32 + 2, prev.total * 2.0f64.mul_add(*m, 1.0), 0.0)
+            });
+        }
+        let stats = benchmark_stats(&snaps, NaiveDate::MIN).unwrap();
+        assert!((stats.beta.unwrap() - 2.0).abs() < 1e-9);
+        assert!(stats.alpha.unwrap().abs() < 1e-9);
+        assert!((stats.benchmark - (snaps[6].benchmark / 100.0 - 1.0)).abs() < 1e-12);
+        assert!((stats.relative() - (stats.portfolio - stats.benchmark)).abs() < 1e-12);
+
+        // Too few periods for a regression, and none at all without benchmark prices
+        let short = benchmark_stats(&snaps[..3], NaiveDate::MIN).unwrap();
+        assert!(short.beta.is_none() && short.alpha.is_none());
+        let no_bench = [snap(1, 1_000.0, 0.0), snap(2, 1_100.0, 0.0)];
+        assert!(benchmark_stats(&no_bench, NaiveDate::MIN).is_none());
+    }
+}
@@ -1,13 +1,20 @@
 //! /portfolio command — create, view, fund, withdraw, and delete portfolios.
 
-use crate::api::{fetch_prices_map};
-use crate::data::{self, AssetType, PendingOrder, Portfolio, BASE_HYSA_RATE};
+use crate::api::{fetch_price, fetch_prices_map, fetch_volatility, market_data_err};
+use crate::options::{
+    fmt_bound, fmt_leg, payoff_summary, position_greeks, render_payoff_png, Greeks, PayoffLeg, PricingInputs, PAYOFF_FILENAME,
+};
+use super::risk::run_risk_tab;
+use super::performance::{benchmark_stats, max_drawdown, period_returns};
+use super::margin::{buying_power, margin_annual_rate, margin_loan};
+use crate::data::{self, AssetType, PendingOrder, Portfolio};
 use crate::helper::{creds_to_price, default_footer, fmt_qty, option_intrinsic, price_to_creds};
 use crate::{serenity, Context, Error};
 use std::collections::HashMap;
 use std::time::Duration;
 
-/// Compute total liquidation value (cash + all positions at current market prices) for a portfolio.
+/// Compute total liquidation value (cash + accrued interest + all positions at current market
+/// prices) for a portfolio.
 fn liquidation_value(port: &Portfolio, prices: &HashMap<String, f64>) -> f64 {
     let positions_value: f64 = port.positions.iter().map(|pos| {
         let price_usd = prices.get(&pos.ticker).copied().unwrap_or(0.0);
@@ -19,7 +26,7 @@ fn liquidation_value(port: &Portfolio, prices: &HashMap<String, f64>) -> f64 {
             _ => price_to_creds(price_usd) * pos.quantity,
         }
     }).sum();
-    port.cash + positions_value
+    port.cash + port.accrued_interest + positions_value
 }
 
 pub(crate) const NUM_EMOJI: [&str; 4] = ["1️⃣", "2️⃣", "3️⃣", "4️⃣"];
@@ -34,6 +41,14 @@ pub(crate) struct CreatePortfolioModal {
     pub name: String,
 }
 
+#[derive(Debug, poise::Modal)]
+#[name = "Margin Settings"]
+pub(crate) struct MarginModal {
+    #[name = "Loan-to-value % (0 disables margin)"]
+    #[placeholder = "e.g. 50"]
+    pub ltv_pct: String,
+}
+
 #[derive(Debug, poise::Modal)]
 #[name = "Delete Portfolio"]
 pub(crate) struct DeletePortfolioModal {
@@ -144,6 +159,11 @@ fn parse_dollar_input(raw: &str) -> Option<f64> {
     if v > 0.0 && v <= data::MAX_FUND_USD { Some(v) } else { None }
 }
 
+/// Club cash only moves through unit deposits and redemptions, so members' stakes stay whole.
+fn club_cash_err(port_name: &str) -> String {
+    format!("**{port_name}** is a club — move cash with `/club_deposit` and `/club_redeem`.")
+}
+
 /// Transfer creds from wallet into a named portfolio. Returns new cash balance or error.
 fn try_fund(user_data: &mut data::UserData, port_name: &str, dollars: f64) -> Result<f64, String> {
     let amount = price_to_creds(dollars) as i32;
@@ -152,8 +172,10 @@ fn try_fund(user_data: &mut data::UserData, port_name: &str, dollars: f64) -> Re
     }
     match user_data.stock.portfolios.iter_mut().find(|p| p.name.eq_ignore_ascii_case(port_name)) {
         None => Err(format!("Portfolio **{port_name}** no longer exists.")),
+        Some(p) if p.club.is_some() => Err(club_cash_err(port_name)),
         Some(p) => {
             p.cash += f64::from(amount);
+            p.pending_flow += f64::from(amount);
             let new_cash = p.cash;
             user_data.sub_creds(amount);
             Ok(new_cash)
@@ -161,17 +183,38 @@ fn try_fund(user_data: &mut data::UserData, port_name: &str, dollars: f64) -> Re
     }
 }
 
+/// Set a portfolio's margin loan-to-value. Returns the applied ratio or error.
+fn try_set_margin(user_data: &mut data::UserData, port_name: &str, raw_pct: &str) -> Result<f64, String> {
+    let max_pct = data::MAX_MARGIN_LOAN_RATIO * 100.0;
+    let Some(pct) = raw_pct.trim().trim_end_matches('%').parse::<f64>().ok().filter(|p| (0.0..=max_pct).contains(p)) else {
+        return Err(format!("Loan-to-value must be between 0% and {max_pct:.0}%."));
+    };
+    match user_data.stock.portfolios.iter_mut().find(|p| p.name.eq_ignore_ascii_case(port_name)) {
+        None => Err(format!("Portfolio **{port_name}** no longer exists.")),
+        Some(p) if pct == 0.0 && p.cash < 0.0 => Err(format!(
+            "**{}** has an outstanding margin loan of **${:.2}**. Repay it before disabling margin.",
+            port_name, creds_to_price(-p.cash)
+        )),
+        Some(p) => {
+            p.margin_ratio = pct / 100.0;
+            Ok(p.margin_ratio)
+        }
+    }
+}
+
 /// Transfer creds from a named portfolio back to wallet. Returns remaining cash or error.
 fn try_withdraw(user_data: &mut data::UserData, port_name: &str, dollars: f64) -> Result<f64, String> {
     let amount = price_to_creds(dollars) as i32;
     match user_data.stock.portfolios.iter_mut().find(|p| p.name.eq_ignore_ascii_case(port_name)) {
         None => Err(format!("Portfolio **{port_name}** no longer exists.")),
-        Some(p) if p.cash < f64::from(amount) => Err(format!(
-            "Insufficient cash. **{}** has **${:.2}** but tried to withdraw **${:.2}**.",
-            port_name, creds_to_price(p.cash), dollars
+        Some(p) if p.club.is_some() => Err(club_cash_err(port_name)),
+        Some(p) if p.cash - p.locked_cash() < f64::from(amount) => Err(format!(
+            "Insufficient free cash. **{}** has **${:.2}** not locked as short collateral but tried to withdraw **${:.2}**.",
+            port_name, creds_to_price((p.cash - p.locked_cash()).max(0.0)), dollars
         )),
         Some(p) => {
             p.cash -= f64::from(amount);
+            p.pending_flow -= f64::from(amount);
             let remaining = p.cash;
             user_data.add_creds(amount);
             Ok(remaining)
@@ -179,6 +222,45 @@ fn try_withdraw(user_data: &mut data::UserData, port_name: &str, dollars: f64) -
     }
 }
 
+/// Whether `port` can be deleted: `Err` explains why not, `Ok(None)` means it's empty and goes
+/// without confirmation, and `Ok(Some(detail))` describes what Liquidate & Delete will sell.
+fn delete_check(port: &Portfolio) -> Result<Option<String>, String> {
+    let name = &port.name;
+    if port.club.as_ref().is_some_and(|c| c.total_units() > 0.0) {
+        return Err(format!("**{name}** is a club with units outstanding — every member must `/club_redeem` first."));
+    }
+    if port.cash < 0.0 {
+        return Err(format!(
+            "**{name}** has an outstanding margin loan of **${:.2}**. Sell holdings or deposit cash to repay it before deleting.",
+            creds_to_price(-port.cash),
+        ));
+    }
+    let (cash, positions_count) = (port.cash, port.positions.len());
+    Ok(match (cash > 0.0, positions_count > 0) {
+        (false, false) => None,
+        (true, true) => Some(format!("**{name}** has **{cash:.0}** creds cash and **{positions_count}** open positions.")),
+        (true, false) => Some(format!("**{name}** has **{cash:.0}** creds cash.")),
+        (false, true) => Some(format!("**{name}** has **{positions_count}** open positions.")),
+    })
+}
+
+/// Liquidate a named portfolio at market prices into the wallet and delete it. Re-runs
+/// `delete_check` under the write lock, since the portfolio may have changed while confirming.
+async fn liquidate_and_delete(u: &tokio::sync::RwLock<data::UserData>, port_name: &str) -> Result<(), String> {
+    let tickers: Vec<String> = {
+        let ud = u.read().await;
+        let Some(idx) = ud.stock.find_portfolio_idx(port_name) else { return Ok(()) };
+        ud.stock.portfolios[idx].positions.iter().map(|p| p.ticker.clone()).collect()
+    };
+    let prices = fetch_prices_map(&tickers).await;
+    let mut ud = u.write().await;
+    let Some(idx) = ud.stock.find_portfolio_idx(port_name) else { return Ok(()) };
+    delete_check(&ud.stock.portfolios[idx])?;
+    let port = ud.stock.portfolios.remove(idx);
+    ud.add_creds(liquidation_value(&port, &prices) as i32);
+    Ok(())
+}
+
 // ── Embed builders ────────────────────────────────────────────────────────────
 
 pub(crate) async fn build_portfolio_picker(
@@ -242,8 +324,10 @@ pub(crate) async fn build_portfolio_view_embed(
     portfolio: &Portfolio,
     pending_orders: &[PendingOrder],
     annual_rate: f64,
+    fed_rate: f64,
 ) -> serenity::CreateEmbed {
-    let daily_accrual = (annual_rate / 100.0 / 365.0) * portfolio.cash;
+    let daily_accrual = (annual_rate / 100.0 / 365.0) * (portfolio.cash + portfolio.accrued_interest);
+    let margin_rate = margin_annual_rate(fed_rate);
 
     let unique_tickers: Vec<String> = {
         let mut seen = std::collections::HashSet::new();
@@ -252,49 +336,117 @@ pub(crate) async fn build_portfolio_view_embed(
             .collect()
     };
     let price_cache = fetch_prices_map(&unique_tickers).await;
+    let mut option_inputs = HashMap::new();
+    for pos in &portfolio.positions {
+        if matches!(pos.asset_type, AssetType::Option(_)) && !option_inputs.contains_key(&pos.ticker) {
+            let vol = fetch_volatility(&pos.ticker).await;
+            option_inputs.insert(pos.ticker.clone(), PricingInputs::new(vol, fed_rate));
+        }
+    }
     let positions_value: f64 = portfolio.positions.iter()
         .map(|pos| price_to_creds(*price_cache.get(&pos.ticker).unwrap_or(&0.0)) * pos.quantity)
         .sum();
     let total_value = portfolio.cash + positions_value;
 
     let mut desc = format!(
-        "**Total Value:** ${:.2}\n**Cash:** ${:.2} | **Daily interest:** ~${:.2}\n\n",
+        "**Total Value:** ${:.2}\n**Cash:** ${:.2} | **Daily interest:** ~${:.2} at {:.2}%/yr\n",
         creds_to_price(total_value),
         creds_to_price(portfolio.cash),
-        creds_to_price(daily_accrual)
+        creds_to_price(daily_accrual.max(0.0)),
+        annual_rate
     );
+    if portfolio.accrued_interest > 0.0 {
+        desc += &format!("**Accrued interest:** ${:.2} (posts on the 1st)\n", creds_to_price(portfolio.accrued_interest));
+    }
+    if portfolio.snapshots.len() >= 2 {
+        let returns: Vec<String> = period_returns(&portfolio.snapshots).into_iter()
+            .map(|(label, r)| format!("{label} **{}**", r.map_or_else(|| "—".to_string(), |r| format!("{:+.2}%", r * 100.0))))
+            .collect();
+        desc += &format!(
+            "**Returns** (time-weighted): {} | **Max drawdown:** {:.2}%\n",
+            returns.join(" · "),
+            max_drawdown(&portfolio.snapshots) * 100.0,
+        );
+    }
+    if let Some(vs) = benchmark_stats(&portfolio.snapshots, portfolio.created_at.date_naive()) {
+        let regression = match (vs.alpha, vs.beta) {
+            (Some(alpha), Some(beta)) => format!(" | α **{:+.2}%**/yr · β **{beta:.2}**", alpha * 100.0),
+            _ => String::new(),
+        };
+        desc += &format!(
+            "**vs {}** (since <t:{}:d>): **{:+.2}%** vs **{:+.2}%** → **{:+.2}%**{}\n",
+            data::BENCHMARK_TICKER, portfolio.created_at.timestamp(),
+            vs.portfolio * 100.0, vs.benchmark * 100.0, vs.relative() * 100.0, regression,
+        );
+    }
+    if portfolio.margin_ratio > 0.0 {
+        desc += &format!(
+            "**Margin:** {:.0}% LTV | **Loan:** ${:.2} @ {:.2}%/yr | **Buying power:** ${:.2}\n",
+            portfolio.margin_ratio * 100.0,
+            creds_to_price(margin_loan(portfolio)),
+            margin_rate,
+            creds_to_price(buying_power(portfolio, &price_cache)),
+        );
+    }
+    if let Some(called_at) = portfolio.margin_call_at {
+        let deadline = called_at + chrono::Duration::hours(data::MARGIN_CALL_GRACE_HOURS);
+        desc += &format!("⚠️ **Margin call** — cure by <t:{}:f> or holdings will be liquidated.\n", deadline.timestamp());
+    }
+    if let Some(called_at) = portfolio.option_margin_call_at {
+        let deadline = called_at + chrono::Duration::hours(data::MARGIN_CALL_GRACE_HOURS);
+        desc += &format!("⚠️ **Options margin call** — cure by <t:{}:f> or naked shorts will be bought back.\n", deadline.timestamp());
+    }
+    desc += "\n";
 
     if portfolio.positions.is_empty() {
         desc += "*No open positions.*";
     } else {
         desc += "**Positions:**\n﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\n";
         for pos in &portfolio.positions {
+            // Strategy legs are listed together under their strategy below
+            if matches!(&pos.asset_type, AssetType::Option(c) if c.strategy.is_some()) {
+                continue;
+            }
             let current_price_usd = price_cache.get(&pos.ticker).copied().unwrap_or(0.0);
             let cost_basis = pos.avg_cost * pos.quantity;
 
             if let AssetType::Option(contract) = &pos.asset_type {
-                let intrinsic = option_intrinsic(contract.option_type, current_price_usd, contract.strike);
-                let current_premium = crate::options::option_premium_creds(intrinsic, &contract.expiry, contract.contracts);
+                let inputs = option_inputs.get(&pos.ticker).copied().unwrap_or_else(|| PricingInputs::new(None, fed_rate));
+                let current_premium = crate::options::option_premium_creds(
+                    contract.option_type, contract.style, current_price_usd, contract.strike, &contract.expiry, contract.contracts, inputs,
+                );
                 let type_str = crate::helper::option_type_str(contract.option_type);
+                let european = if contract.style == data::ExerciseStyle::European { " · European" } else { "" };
                 if contract.side == data::OptionSide::Short {
                     let pnl = cost_basis - current_premium;
                     desc += &format!(
-                        "SHORT **{} {} ${:.2}** exp {} — {} contracts\nPremium rcvd: **${:.2}** | Obligation: **${:.2}** | P&L: **${:+.2}**{}\n\n",
+                        "SHORT **{} {} ${:.2}** exp {} — {} contracts{}{} · `{}`\nPremium rcvd: **${:.2}** | Obligation: **${:.2}** | P&L: **${:+.2}**{}\n\n",
                         pos.ticker, type_str, contract.strike,
                         contract.expiry.format("%Y-%m-%d"), contract.contracts,
+                        european, if contract.settlement == data::SettlementMode::Physical { " · physical" } else { "" }, contract.id,
                         creds_to_price(cost_basis), creds_to_price(current_premium),
                         creds_to_price(pnl), crate::helper::fmt_pct_change(pnl, cost_basis)
                     );
                 } else {
                     let pnl = current_premium - cost_basis;
                     desc += &format!(
-                        "**{} {} ${:.2}** exp {} — {} contracts\nCost: **${:.2}** | Value: **${:.2}** | P&L: **${:+.2}**{}\n\n",
+                        "**{} {} ${:.2}** exp {} — {} contracts{} · `{}`\nCost: **${:.2}** | Value: **${:.2}** | P&L: **${:+.2}**{}\n\n",
                         pos.ticker, type_str, contract.strike,
-                        contract.expiry.format("%Y-%m-%d"), contract.contracts,
+                        contract.expiry.format("%Y-%m-%d"), contract.contracts, european, contract.id,
                         creds_to_price(cost_basis), creds_to_price(current_premium),
                         creds_to_price(pnl), crate::helper::fmt_pct_change(pnl, cost_basis)
                     );
                 }
+            } else if pos.is_short_stock() {
+                let shares = -pos.quantity;
+                let pnl = (pos.avg_cost - price_to_creds(current_price_usd)) * shares;
+                desc += &format!(
+                    "SHORT **{}** × {} — Entry: ${:.2} | Now: ${:.2}\nLiability: **${:.2}** | P&L: **${:+.2}**{}\n\n",
+                    pos.ticker, fmt_qty(shares),
+                    creds_to_price(pos.avg_cost), current_price_usd,
+                    current_price_usd * shares,
+                    creds_to_price(pnl), crate::helper::fmt_pct_change(pnl, pos.avg_cost * shares)
+                );
             } else {
                 let current_creds = price_to_creds(current_price_usd);
                 let current_value = current_creds * pos.quantity;
@@ -309,6 +461,47 @@ pub(crate) async fn build_portfolio_view_embed(
                 );
             }
         }
+
+        for strat in &portfolio.strategies {
+            let spot = price_cache.get(&strat.ticker).copied().unwrap_or(0.0);
+            let inputs = option_inputs.get(&strat.ticker).copied().unwrap_or_else(|| PricingInputs::new(None, fed_rate));
+            let mut legs = Vec::new();
+            let mut value = 0.0;
+            for pos in portfolio.strategy_legs(strat.id) {
+                if let AssetType::Option(c) = &pos.asset_type {
+                    let premium = crate::options::option_premium_creds(c.option_type, c.style, spot, c.strike, &c.expiry, c.contracts, inputs);
+                    value += if c.side == data::OptionSide::Short { -premium } else { premium };
+                    legs.push(fmt_leg(c));
+                }
+            }
+            let pnl = value - strat.net_premium;
+            desc += &format!(
+                "`#{}` **{} {}** × {} — {}\n{}: **${:.2}** | Max profit: **{}** | Max loss: **{}** | P&L: **${:+.2}**\n\n",
+                strat.id, strat.ticker, strat.kind.label(), strat.contracts, legs.join(" "),
+                if strat.net_premium >= 0.0 { "Debit" } else { "Credit" }, creds_to_price(strat.net_premium.abs()),
+                fmt_bound(strat.max_profit), fmt_bound(strat.max_loss), creds_to_price(pnl),
+            );
+        }
+    }
+
+    if portfolio.positions.iter().any(|p| matches!(p.asset_type, AssetType::Option(_))) {
+        let now = chrono::Utc::now();
+        let mut by_ticker: Vec<(&str, Greeks)> = Vec::new();
+        for pos in &portfolio.positions {
+            let spot = price_cache.get(&pos.ticker).copied().unwrap_or(0.0);
+            let inputs = option_inputs.get(&pos.ticker).copied().unwrap_or_else(|| PricingInputs::new(None, fed_rate));
+            let g = position_greeks(pos, spot, inputs, now);
+            match by_ticker.iter_mut().find(|(t, _)| *t == pos.ticker) {
+                Some((_, total)) => *total = total.add(g),
+                None => by_ticker.push((&pos.ticker, g)),
+            }
+        }
+        let total = by_ticker.iter().fold(Greeks::default(), |acc, (_, g)| acc.add(*g));
+        let deltas: Vec<String> = by_ticker.iter().map(|(t, g)| format!("{t} {:+.1} sh", g.delta)).collect();
+        desc += &format!(
+            "\n**Risk:**\n﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\nNet Δ: {}\nΓ: **{:+.2}** sh/$ | Θ: **${:+.2}**/day | ν: **${:+.2}**/vol pt | ρ: **${:+.2}**/rate pt\n",
+            deltas.join(" | "), total.gamma, total.theta, total.vega, total.rho,
+        );
     }
 
     if !pending_orders.is_empty() {
@@ -459,69 +652,46 @@ pub async fn portfolio(ctx: Context<'_>) -> Result<(), Error> {
             ).await? else { continue 'picker; };
 
             let del_name = del_modal.name.trim().to_string();
-            let lookup = {
+            let check = {
                 let ud = u.read().await;
-                ud.stock.portfolios.iter()
-                    .find(|p| p.name.eq_ignore_ascii_case(&del_name))
-                    .map(|p| (p.cash > 0.0, !p.positions.is_empty(), p.cash, p.positions.len()))
+                ud.stock.find_portfolio_idx(&del_name).map(|i| delete_check(&ud.stock.portfolios[i]))
             };
 
-            let (has_cash, has_positions, cash, positions_count) = match lookup {
-                None => {
+            let deleted = match check {
+                None => Some(Err(format!("No portfolio named **{del_name}** found."))),
+                Some(Err(desc)) => Some(Err(desc)),
+                Some(Ok(None)) => Some(liquidate_and_delete(&u, &del_name).await),
+                Some(Ok(Some(detail))) => {
                     reply.edit(ctx, poise::CreateReply::default().embed(
                         serenity::CreateEmbed::new().title("Portfolio — Delete")
-                            .description(format!("No portfolio named **{del_name}** found.")).color(data::EMBED_ERROR),
-                    ).components(vec![])).await?;
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    continue 'picker;
-                }
-                Some(v) => v,
-            };
-
-            if !has_cash && !has_positions {
-                { let mut ud = u.write().await; ud.stock.portfolios.retain(|p| !p.name.eq_ignore_ascii_case(&del_name)); }
-                continue 'picker;
-            }
-
-            let detail = if has_cash && has_positions {
-                format!("**{del_name}** has **{cash:.0}** creds cash and **{positions_count}** open positions.")
-            } else if has_cash {
-                format!("**{del_name}** has **{cash:.0}** creds cash.")
-            } else {
-                format!("**{del_name}** has **{positions_count}** open positions.")
-            };
-
-            reply.edit(ctx, poise::CreateReply::default().embed(
-                serenity::CreateEmbed::new().title("Portfolio — Delete")
-                    .description(format!("{detail}\n\nLiquidate all positions at market price and return cash to wallet?"))
-                    .color(data::EMBED_FAIL).footer(default_footer()),
-            ).components(vec![serenity::CreateActionRow::Buttons(vec![
-                serenity::CreateButton::new("pdel_yes").label("Liquidate & Delete").style(serenity::ButtonStyle::Danger),
-                serenity::CreateButton::new("pdel_no").label("Cancel").style(serenity::ButtonStyle::Secondary),
-            ])])).await?;
-
-            let conf = reply.message().await?
-                .await_component_interaction(ctx.serenity_context())
-                .author_id(ctx.author().id)
-                .timeout(Duration::from_secs(45))
-                .await;
-
-            if let Some(c) = conf {
-                c.defer(ctx.http()).await?;
-                if c.data.custom_id == "pdel_yes" {
-                    let port_clone = {
-                        let ud = u.read().await;
-                        ud.stock.portfolios.iter().find(|p| p.name.eq_ignore_ascii_case(&del_name)).cloned()
-                    };
-                    if let Some(port) = port_clone {
-                        let tickers: Vec<String> = port.positions.iter().map(|p| p.ticker.clone()).collect();
-                        let prices = fetch_prices_map(&tickers).await;
-                        let total_proceeds = liquidation_value(&port, &prices);
-                        let mut ud = u.write().await;
-                        ud.stock.portfolios.retain(|p| !p.name.eq_ignore_ascii_case(&del_name));
-                        ud.add_creds(total_proceeds as i32);
+                            .description(format!("{detail}\n\nLiquidate all positions at market price and return cash to wallet?"))
+                            .color(data::EMBED_FAIL).footer(default_footer()),
+                    ).components(vec![serenity::CreateActionRow::Buttons(vec![
+                        serenity::CreateButton::new("pdel_yes").label("Liquidate & Delete").style(serenity::ButtonStyle::Danger),
+                        serenity::CreateButton::new("pdel_no").label("Cancel").style(serenity::ButtonStyle::Secondary),
+                    ])])).await?;
+
+                    let conf = reply.message().await?
+                        .await_component_interaction(ctx.serenity_context())
+                        .author_id(ctx.author().id)
+                        .timeout(Duration::from_secs(45))
+                        .await;
+
+                    match conf {
+                        Some(c) => {
+                            c.defer(ctx.http()).await?;
+                            if c.data.custom_id == "pdel_yes" { Some(liquidate_and_delete(&u, &del_name).await) } else { None }
+                        }
+                        None => None,
                     }
                 }
+            };
+            if let Some(Err(desc)) = deleted {
+                reply.edit(ctx, poise::CreateReply::default().embed(
+                    serenity::CreateEmbed::new().title("Portfolio — Delete")
+                        .description(desc).color(data::EMBED_ERROR),
+                ).components(vec![])).await?;
+                tokio::time::sleep(Duration::from_secs(2)).await;
             }
 
         // ── Numbered portfolio (view) ─────────────────────────────────────────
@@ -543,11 +713,12 @@ pub async fn portfolio(ctx: Context<'_>) -> Result<(), Error> {
                 };
                 let Some(port) = port_opt else { continue 'picker; };
 
-                let annual_rate = {
+                let (annual_rate, gold) = {
                     let ud = u.read().await;
-                    if crate::helper::is_gold(&ud) { crate::helper::gold_hysa_rate(fed_rate_val) } else { BASE_HYSA_RATE }
+                    let rate = crate::helper::hysa_rate(&crate::helper::HYSA_TIERS, ud.get_level(), fed_rate_val);
+                    (rate, crate::helper::is_gold(&ud))
                 };
-                let embed = build_portfolio_view_embed(&port, &port_orders, annual_rate).await;
+                let embed = build_portfolio_view_embed(&port, &port_orders, annual_rate, fed_rate_val).await;
                 let mut view_btns = vec![
                     serenity::CreateButton::new("pv_back").label("↩ Back").style(serenity::ButtonStyle::Secondary),
                     serenity::CreateButton::new("pv_fund").label("Fund").style(serenity::ButtonStyle::Success),
@@ -555,8 +726,16 @@ pub async fn portfolio(ctx: Context<'_>) -> Result<(), Error> {
                 if port.cash > 0.0 {
                     view_btns.push(serenity::CreateButton::new("pv_withdraw").label("Withdraw").style(serenity::ButtonStyle::Primary));
                 }
+                if gold || port.margin_ratio > 0.0 {
+                    view_btns.push(serenity::CreateButton::new("pv_margin").label("Margin").style(serenity::ButtonStyle::Secondary));
+                }
                 view_btns.push(serenity::CreateButton::new("pv_delete").label("Delete").style(serenity::ButtonStyle::Danger));
-                let mut action_buttons = vec![serenity::CreateActionRow::Buttons(view_btns)];
+                let mut action_buttons = vec![
+                    serenity::CreateActionRow::Buttons(view_btns),
+                    serenity::CreateActionRow::Buttons(vec![
+                        serenity::CreateButton::new("pv_risk").label("🛡 Risk").style(serenity::ButtonStyle::Secondary),
+                    ]),
+                ];
                 if !port_orders.is_empty() {
                     let cancel_btns: Vec<serenity::CreateButton> = port_orders.iter().take(5).enumerate().map(|(i, o)| {
                         serenity::CreateButton::new(format!("pv_cancel_{}", o.id))
@@ -565,6 +744,20 @@ pub async fn portfolio(ctx: Context<'_>) -> Result<(), Error> {
                     }).collect();
                     action_buttons.push(serenity::CreateActionRow::Buttons(cancel_btns));
                 }
+                let mut option_tickers: Vec<&str> = Vec::new();
+                for pos in &port.positions {
+                    if matches!(pos.asset_type, AssetType::Option(_)) && !option_tickers.contains(&pos.ticker.as_str()) {
+                        option_tickers.push(&pos.ticker);
+                    }
+                }
+                if !option_tickers.is_empty() {
+                    let payoff_btns: Vec<serenity::CreateButton> = option_tickers.iter().take(5).map(|t| {
+                        serenity::CreateButton::new(format!("pv_payoff_{t}"))
+                            .label(format!("📈 {t}"))
+                            .style(serenity::ButtonStyle::Secondary)
+                    }).collect();
+                    action_buttons.push(serenity::CreateActionRow::Buttons(payoff_btns));
+                }
                 reply.edit(ctx, poise::CreateReply::default().embed(embed.clone()).components(action_buttons)).await?;
 
                 let Some(action) = reply.message().await?
@@ -626,59 +819,117 @@ pub async fn portfolio(ctx: Context<'_>) -> Result<(), Error> {
                         return Ok(());
                     }
 
-                    "pv_delete" => {
-                        action.defer(ctx.http()).await?;
-                        let port_info = { let ud = u.read().await; ud.stock.portfolios.iter().find(|p| p.name == port_name).map(|p| (p.cash > 0.0, !p.positions.is_empty(), p.cash, p.positions.len())) };
-                        let (has_cash, has_positions, cash, positions_count) = match port_info { None => continue 'picker, Some(v) => v };
-
-                        if !has_cash && !has_positions {
-                            { let mut ud = u.write().await; ud.stock.portfolios.retain(|p| p.name != port_name); }
-                            continue 'picker;
+                    "pv_margin" => {
+                        let current = format!("{:.0}", port.margin_ratio * 100.0);
+                        let Some(modal) = poise::execute_modal_on_component_interaction::<MarginModal>(
+                            ctx, action,
+                            Some(MarginModal { ltv_pct: current }),
+                            Some(Duration::from_secs(30)),
+                        ).await? else { return Ok(()); };
+                        let margin_result = {
+                            let mut ud = u.write().await;
+                            if crate::helper::is_gold(&ud) || modal.ltv_pct.trim() == "0" {
+                                try_set_margin(&mut ud, &port_name, &modal.ltv_pct)
+                            } else {
+                                Err("Margin accounts require **Gold Status**.".to_string())
+                            }
+                        };
+                        match margin_result {
+                            Err(msg) => { reply.edit(ctx, poise::CreateReply::default().embed(serenity::CreateEmbed::new().title("Portfolio — Margin").description(msg).color(data::EMBED_ERROR)).components(vec![])).await?; }
+                            Ok(ratio) if ratio > 0.0 => { reply.edit(ctx, poise::CreateReply::default().embed(serenity::CreateEmbed::new().title("Portfolio — Margin").description(format!("**{}** can now borrow up to **{:.0}%** of its long holdings at **{:.2}%**/yr.\nEquity below **{:.0}%** of holdings triggers a margin call.", port_name, ratio * 100.0, margin_annual_rate(fed_rate_val), data::MARGIN_MAINTENANCE_RATIO * 100.0)).color(data::EMBED_GOLD).footer(default_footer())).components(vec![])).await?; }
+                            Ok(_) => { reply.edit(ctx, poise::CreateReply::default().embed(serenity::CreateEmbed::new().title("Portfolio — Margin").description(format!("Margin disabled — **{port_name}** is a cash account again.")).color(data::EMBED_SUCCESS).footer(default_footer())).components(vec![])).await?; }
                         }
+                        return Ok(());
+                    }
 
-                        let detail = if has_cash && has_positions { format!("**{port_name}** has **{cash:.0}** creds cash and **{positions_count}** open positions.") }
-                            else if has_cash { format!("**{port_name}** has **{cash:.0}** creds cash.") }
-                            else { format!("**{port_name}** has **{positions_count}** open positions.") };
-
-                        reply.edit(ctx, poise::CreateReply::default().embed(
-                            serenity::CreateEmbed::new().title("Portfolio — Delete")
-                                .description(format!("{detail}\n\nLiquidate all positions at market price and return cash to wallet?"))
-                                .color(data::EMBED_FAIL).footer(default_footer()),
-                        ).components(vec![serenity::CreateActionRow::Buttons(vec![
-                            serenity::CreateButton::new("del_yes").label("Liquidate & Delete").style(serenity::ButtonStyle::Danger),
-                            serenity::CreateButton::new("del_no").label("Cancel").style(serenity::ButtonStyle::Secondary),
-                        ])])).await?;
-
-                        let conf = reply.message().await?
-                            .await_component_interaction(ctx.serenity_context())
-                            .author_id(ctx.author().id)
-                            .timeout(Duration::from_secs(45))
-                            .await;
-
-                        match conf {
+                    "pv_delete" => {
+                        action.defer(ctx.http()).await?;
+                        let check = { let ud = u.read().await; ud.stock.portfolios.iter().find(|p| p.name == port_name).map(delete_check) };
+                        let deleted = match check {
                             None => continue 'picker,
-                            Some(c) => {
-                                c.defer(ctx.http()).await?;
-                                if c.data.custom_id == "del_yes" {
-                                    let port_clone = {
-                                        let ud = u.read().await;
-                                        ud.stock.portfolios.iter().find(|p| p.name == port_name).cloned()
-                                    };
-                                    if let Some(port) = port_clone {
-                                        let tickers: Vec<String> = port.positions.iter().map(|p| p.ticker.clone()).collect();
-                                        let prices = fetch_prices_map(&tickers).await;
-                                        let total_proceeds = liquidation_value(&port, &prices);
-                                        let mut ud = u.write().await;
-                                        ud.stock.portfolios.retain(|p| p.name != port_name);
-                                        ud.add_creds(total_proceeds as i32);
+                            Some(Err(desc)) => Err(desc),
+                            Some(Ok(None)) => liquidate_and_delete(&u, &port_name).await,
+                            Some(Ok(Some(detail))) => {
+                                reply.edit(ctx, poise::CreateReply::default().embed(
+                                    serenity::CreateEmbed::new().title("Portfolio — Delete")
+                                        .description(format!("{detail}\n\nLiquidate all positions at market price and return cash to wallet?"))
+                                        .color(data::EMBED_FAIL).footer(default_footer()),
+                                ).components(vec![serenity::CreateActionRow::Buttons(vec![
+                                    serenity::CreateButton::new("del_yes").label("Liquidate & Delete").style(serenity::ButtonStyle::Danger),
+                                    serenity::CreateButton::new("del_no").label("Cancel").style(serenity::ButtonStyle::Secondary),
+                                ])])).await?;
+
+                                let conf = reply.message().await?
+                                    .await_component_interaction(ctx.serenity_context())
+                                    .author_id(ctx.author().id)
+                                    .timeout(Duration::from_secs(45))
+                                    .await;
+
+                                match conf {
+                                    None => continue 'picker,
+                                    Some(c) => {
+                                        c.defer(ctx.http()).await?;
+                                        if c.data.custom_id != "del_yes" {
+                                            continue 'view;
+                                        }
+                                        liquidate_and_delete(&u, &port_name).await
                                     }
-                                    continue 'picker;
                                 }
+                            }
+                        };
+                        match deleted {
+                            Ok(()) => continue 'picker,
+                            Err(desc) => {
+                                reply.edit(ctx, poise::CreateReply::default().embed(
+                                    serenity::CreateEmbed::new().title("Portfolio — Delete")
+                                        .description(desc).color(data::EMBED_ERROR),
+                                ).components(vec![])).await?;
+                                tokio::time::sleep(Duration::from_secs(2)).await;
                                 continue 'view;
                             }
                         }
                     }
 
+                    "pv_risk" => {
+                        action.defer(ctx.http()).await?;
+                        if run_risk_tab(ctx, &reply, &u, &port_name, fed_rate_val).await? {
+                            continue 'view;
+                        }
+                        return Ok(());
+                    }
+
+                    id if id.starts_with("pv_payoff_") => {
+                        action.defer(ctx.http()).await?;
+                        let ticker = id.trim_start_matches("pv_payoff_");
+                        let legs: Vec<PayoffLeg> = port.positions.iter()
+                            .filter(|p| p.ticker == ticker)
+                            .filter_map(PayoffLeg::from_position)
+                            .collect();
+                        let chart = match fetch_price(ticker).await {
+                            Some(spot) => render_payoff_png(&legs, spot).map(|png| (spot, png)),
+                            None => None,
+                        };
+                        let Some((spot, png)) = chart else {
+                            ctx.send(poise::CreateReply::default().embed(
+                                serenity::CreateEmbed::new().title("Payoff at Expiry").description(market_data_err(ticker)).color(data::EMBED_ERROR),
+                            )).await?;
+                            continue 'view;
+                        };
+                        ctx.send(poise::CreateReply::default()
+                            .embed(serenity::CreateEmbed::new()
+                                .title(format!("Payoff at Expiry — {ticker}"))
+                                .description(format!(
+                                    "{} option leg{} in **{}**, premiums included.\n{}",
+                                    legs.len(), if legs.len() == 1 { "" } else { "s" }, port_name, payoff_summary(&legs, spot),
+                                ))
+                                .image(format!("attachment://{PAYOFF_FILENAME}"))
+                                .color(data::EMBED_CYAN)
+                                .footer(default_footer()))
+                            .attachment(serenity::CreateAttachment::bytes(png, PAYOFF_FILENAME)))
+                            .await?;
+                        continue 'view;
+                    }
+
                     id if id.starts_with("pv_cancel_") => {
                         action.defer(ctx.http()).await?;
                         let order_id: u32 = id.strip_prefix("pv_cancel_").and_then(|s| s.parse().ok()).unwrap_or(u32::MAX);
@@ -693,3 +944,32 @@ pub async fn portfolio(ctx: Context<'_>) -> Result<(), Error> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Club;
+    use poise::serenity_prelude::UserId;
+
+    #[test]
+    fn delete_check_refuses_clubs_with_units() {
+        let manager = UserId::new(1);
+        let mut port = Portfolio::new("Club".to_string());
+        port.club = Some(Club::new(manager));
+        assert_eq!(delete_check(&port), Ok(None));
+
+        port.club.as_mut().unwrap().issue(manager, 10_000.0, data::CLUB_INITIAL_UNIT_CREDS).unwrap();
+        port.cash = 10_000.0;
+        assert!(delete_check(&port).is_err());
+    }
+
+    #[test]
+    fn delete_check_refuses_an_outstanding_margin_loan() {
+        let mut port = Portfolio::new("Margin".to_string());
+        port.margin_ratio = 0.5;
+        port.cash = -1_000.0;
+        assert!(delete_check(&port).is_err());
+        port.cash = 0.0;
+        assert_eq!(delete_check(&port), Ok(None));
+    }
+}
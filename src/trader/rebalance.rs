@@ -0,0 +1,473 @@
+//! /rebalance command — target weights per ticker or asset class, drift-band rebalance plans,
+//! and the execution shared with the scheduled auto-rebalance sweep.
+
+use crate::api::{fetch_prices_map, market_data_err, resolve_ticker};
+use crate::data::{self, AssetClass, AssetType, OrderSide, Portfolio, RebalanceSchedule, StockProfile, TargetKey};
+use crate::helper::{creds_to_price, default_footer, fmt_qty, price_to_creds};
+use crate::{serenity, Context, Error};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use super::{apply_buy, apply_sell, COST_MODEL};
+
+/// Trades smaller than this many creds ($1) are left out of a plan.
+const MIN_TRADE_CREDS: f64 = 100.0;
+
+/// One market order in a rebalance plan, priced at plan time.
+#[derive(Debug, Clone)]
+pub(crate) struct RebalanceOrder {
+    pub ticker: String,
+    pub asset_name: String,
+    pub asset_type: AssetType,
+    pub side: OrderSide,
+    pub quantity: f64,
+    /// Creds per unit.
+    pub price: f64,
+}
+
+/// A target's current and desired share of the portfolio, as fractions.
+#[derive(Debug, Clone)]
+pub(crate) struct TargetDrift {
+    pub label: String,
+    pub current: f64,
+    pub target: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RebalancePlan {
+    pub drifts: Vec<TargetDrift>,
+    /// Sells first, then buys.
+    pub orders: Vec<RebalanceOrder>,
+    /// Targets outside their band that no order can move.
+    pub unfillable: Vec<String>,
+    /// A holding couldn't be priced, so the plan has no orders and should be retried later.
+    pub unpriced: bool,
+}
+
+/// Orders that bring every target drifting more than the portfolio's band back to its weight.
+/// Only long stock, ETF and crypto holdings are traded; options and shorts are left out of the
+/// base the weights apply to. `prices` are USD. If any holding can't be priced, every weight
+/// would be misjudged, so the plan has no orders.
+pub(crate) fn rebalance_plan(port: &Portfolio, prices: &HashMap<String, f64>) -> RebalancePlan {
+    let settings = &port.rebalance;
+    let mut plan = RebalancePlan::default();
+    let mut unpriced: Vec<&str> = port.positions.iter()
+        .filter(|p| p.is_long_stock() && prices.get(&p.ticker).is_none_or(|&usd| usd <= 0.0))
+        .map(|p| p.ticker.as_str())
+        .collect();
+    if !unpriced.is_empty() {
+        unpriced.sort_unstable();
+        unpriced.dedup();
+        plan.unfillable.push(format!("No price right now for **{}** — try again shortly.", unpriced.join(", ")));
+        plan.unpriced = true;
+        return plan;
+    }
+    let held: Vec<(&data::Position, f64)> = port.positions.iter()
+        .filter(|p| p.is_long_stock())
+        .map(|p| (p, price_to_creds(prices[&p.ticker]) * p.quantity))
+        .collect();
+    let base = port.cash + held.iter().map(|(_, v)| v).sum::<f64>();
+    if base <= 0.0 {
+        return plan;
+    }
+    let band = settings.drift_band_pct / 100.0;
+    let held_value = |ticker: &str| held.iter().filter(|(p, _)| p.ticker == ticker).map(|(_, v)| v).sum::<f64>();
+    let ticker_targeted = |ticker: &str| settings.targets.iter()
+        .any(|t| matches!(&t.key, TargetKey::Ticker { symbol, .. } if symbol == ticker));
+
+    // (ticker, asset name, asset type, desired value in creds)
+    let mut desired: Vec<(String, String, AssetType, f64)> = Vec::new();
+    for target in &settings.targets {
+        let weight = target.weight_pct / 100.0;
+        match &target.key {
+            TargetKey::Ticker { symbol, asset_name, asset_type } => {
+                let current = held_value(symbol);
+                plan.drifts.push(TargetDrift { label: symbol.clone(), current: current / base, target: weight });
+                if (current / base - weight).abs() <= band {
+                    continue;
+                }
+                if prices.get(symbol).is_none_or(|&usd| usd <= 0.0) {
+                    plan.unfillable.push(format!("**{symbol}** — no price right now."));
+                    continue;
+                }
+                desired.push((symbol.clone(), asset_name.clone(), asset_type.clone(), weight * base));
+            }
+            TargetKey::Class(class) => {
+                let members: Vec<&(&data::Position, f64)> = held.iter()
+                    .filter(|(p, _)| AssetClass::of(&p.asset_type) == Some(*class) && !ticker_targeted(&p.ticker))
+                    .collect();
+                let current: f64 = members.iter().map(|(_, v)| v).sum();
+                plan.drifts.push(TargetDrift { label: target.key.label(), current: current / base, target: weight });
+                if (current / base - weight).abs() <= band {
+                    continue;
+                }
+                if current <= 0.0 {
+                    plan.unfillable.push(format!("**{}** — no holdings to scale; add a ticker target to buy into it.", target.key.label()));
+                    continue;
+                }
+                let scale = weight * base / current;
+                for (pos, value) in members {
+                    desired.push((pos.ticker.clone(), pos.ticker.clone(), pos.asset_type.clone(), value * scale));
+                }
+            }
+        }
+    }
+
+    for (ticker, asset_name, asset_type, want) in desired {
+        let price = price_to_creds(prices[&ticker]);
+        let delta = want - held_value(&ticker);
+        if delta.abs() < MIN_TRADE_CREDS {
+            continue;
+        }
+        let (side, quantity) = if delta > 0.0 {
+            (OrderSide::Buy, delta / price)
+        } else {
+            let owned: f64 = held.iter().filter(|(p, _)| p.ticker == ticker).map(|(p, _)| p.quantity).sum();
+            (OrderSide::Sell, (-delta / price).min(owned))
+        };
+        plan.orders.push(RebalanceOrder { ticker, asset_name, asset_type, side, quantity, price });
+    }
+    plan.orders.sort_by_key(|o| o.side == OrderSide::Buy);
+    plan
+}
+
+/// Fills `plan` in `stock.portfolios[port_idx]`: sells first, then buys trimmed to the free cash
+/// they leave. Returns one line per fill.
+pub(crate) fn execute_rebalance(stock: &mut StockProfile, port_idx: usize, plan: &RebalancePlan) -> Vec<String> {
+    let port_name = stock.portfolios[port_idx].name.clone();
+    let mut fills = Vec::new();
+    for order in &plan.orders {
+        let port = &mut stock.portfolios[port_idx];
+        match order.side {
+            OrderSide::Sell => {
+                let Some(pnl) = apply_sell(
+                    port, &mut stock.trade_history, &order.ticker, &order.asset_name,
+                    order.quantity, order.price, &port_name, &COST_MODEL,
+                ) else { continue };
+                fills.push(format!(
+                    "SELL **{} {}** @ ${:.2} (${:.2}) | P&L **${:+.2}**",
+                    fmt_qty(order.quantity), order.ticker, creds_to_price(order.price),
+                    creds_to_price(order.quantity * order.price), creds_to_price(pnl),
+                ));
+            }
+            OrderSide::Buy => {
+                let free = port.cash - port.locked_cash();
+                let mut total = order.quantity * order.price;
+                let fees = COST_MODEL.fees(&order.asset_type, total).total();
+                // Fees only shrink with the notional, so one step back leaves room for them
+                if total + fees > free {
+                    total = free - fees;
+                }
+                if total < MIN_TRADE_CREDS {
+                    fills.push(format!("~~BUY {}~~ — not enough free cash left.", order.ticker));
+                    continue;
+                }
+                let quantity = total / order.price;
+                apply_buy(
+                    port, &mut stock.trade_history, &order.ticker, &order.asset_name, order.asset_type.clone(),
+                    quantity, order.price, total, &port_name, &COST_MODEL,
+                );
+                fills.push(format!(
+                    "BUY **{} {}** @ ${:.2} (${:.2})",
+                    fmt_qty(quantity), order.ticker, creds_to_price(order.price), creds_to_price(total),
+                ));
+            }
+        }
+    }
+    fills
+}
+
+/// Tickers a rebalance of `port` needs prices for.
+pub(crate) fn rebalance_tickers(port: &Portfolio) -> Vec<String> {
+    let mut tickers: Vec<String> = port.positions.iter().filter(|p| p.is_long_stock()).map(|p| p.ticker.clone()).collect();
+    for target in &port.rebalance.targets {
+        if let TargetKey::Ticker { symbol, .. } = &target.key {
+            tickers.push(symbol.clone());
+        }
+    }
+    tickers.sort();
+    tickers.dedup();
+    tickers
+}
+
+fn build_plan_embed(port: &Portfolio, plan: &RebalancePlan) -> serenity::CreateEmbed {
+    let settings = &port.rebalance;
+    let schedule = match (settings.schedule, settings.next_run) {
+        (RebalanceSchedule::Off, _) | (_, None) => "manual".to_string(),
+        (s, Some(next)) => format!("{} — next <t:{}:R>", s.label(), next.timestamp()),
+    };
+    let mut desc = format!(
+        "**Drift band:** ±{:.1} pts | **Auto-rebalance:** {} | **Cash target:** {:.1}%\n\n",
+        settings.drift_band_pct, schedule, 100.0 - settings.total_weight_pct(),
+    );
+    if settings.targets.is_empty() {
+        desc += "*No targets yet.* Run `/rebalance` with a **ticker** or **asset_class** and a **weight** to add one.";
+        return serenity::CreateEmbed::new()
+            .title(format!("Rebalance — {}", port.name))
+            .description(desc)
+            .color(data::EMBED_CYAN)
+            .footer(default_footer());
+    }
+
+    desc += "**Targets:**\n﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\n";
+    for d in &plan.drifts {
+        let drift = (d.current - d.target) * 100.0;
+        let flag = if drift.abs() > settings.drift_band_pct { " ⚠️" } else { "" };
+        desc += &format!(
+            "**{}** — now {:.1}% | target {:.1}% | drift {:+.1} pts{}\n",
+            d.label, d.current * 100.0, d.target * 100.0, drift, flag,
+        );
+    }
+    desc += "\n**Plan:**\n﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\n";
+    if plan.orders.is_empty() {
+        desc += "*Every target is within its drift band — nothing to trade.*\n";
+    }
+    for o in &plan.orders {
+        desc += &format!(
+            "{} **{} {}** @ ${:.2} ≈ **${:.2}**\n",
+            o.side.label().to_uppercase(), fmt_qty(o.quantity), o.ticker,
+            creds_to_price(o.price), creds_to_price(o.quantity * o.price),
+        );
+    }
+    for note in &plan.unfillable {
+        desc += &format!("⚠️ {note}\n");
+    }
+    if !plan.orders.is_empty() {
+        desc += "\n*Market orders, sells first. Buys are trimmed to the cash available after fees.*";
+    }
+    serenity::CreateEmbed::new()
+        .title(format!("Rebalance — {}", port.name))
+        .description(desc)
+        .color(data::EMBED_CYAN)
+        .footer(default_footer())
+}
+
+/// Set target allocations for a portfolio and rebalance back to them
+#[poise::command(slash_command)]
+pub async fn rebalance(
+    ctx: Context<'_>,
+    #[description = "Portfolio to rebalance"] portfolio: String,
+    #[description = "Ticker to set a target weight for"] ticker: Option<String>,
+    #[description = "Asset class to set a target weight for (covers holdings without their own target)"] asset_class: Option<AssetClass>,
+    #[description = "Target weight in percent (0 removes the target)"] weight: Option<f64>,
+    #[description = "Percentage points a target may drift before it's traded"] drift_band: Option<f64>,
+    #[description = "Rebalance automatically on a schedule"] auto: Option<RebalanceSchedule>,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Rebalance").description(desc).color(data::EMBED_ERROR),
+    );
+    let key_given = ticker.is_some() || asset_class.is_some();
+    if ticker.is_some() && asset_class.is_some() {
+        ctx.send(err_embed("Set a target for a **ticker** or an **asset_class**, not both at once.".to_string())).await?;
+        return Ok(());
+    }
+    if key_given != weight.is_some() {
+        ctx.send(err_embed("A target needs both a **ticker** or **asset_class** and a **weight**.".to_string())).await?;
+        return Ok(());
+    }
+    if drift_band.is_some_and(|b| !(0.0..=50.0).contains(&b)) {
+        ctx.send(err_embed("Drift band must be between 0 and 50 percentage points.".to_string())).await?;
+        return Ok(());
+    }
+    ctx.defer().await?;
+
+    let key = match (ticker, asset_class) {
+        (Some(query), _) => {
+            let Some(quote) = resolve_ticker(&query).await else {
+                ctx.send(err_embed(market_data_err(&query))).await?;
+                return Ok(());
+            };
+            Some(TargetKey::Ticker { symbol: quote.symbol.clone(), asset_name: quote.display_name(), asset_type: quote.asset_type() })
+        }
+        (None, Some(class)) => Some(TargetKey::Class(class)),
+        (None, None) => None,
+    };
+
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let result = {
+        let mut ud = u.write().await;
+        match ud.stock.find_portfolio_idx(&portfolio) {
+            None => Err(format!("No portfolio named **{portfolio}** found.")),
+            Some(idx) => {
+                let settings = &mut ud.stock.portfolios[idx].rebalance;
+                let set = match (key, weight) {
+                    (Some(key), Some(weight)) => settings.set_target(key, weight),
+                    _ => Ok(()),
+                };
+                set.map(|()| {
+                    if let Some(band) = drift_band {
+                        settings.drift_band_pct = band;
+                    }
+                    if let Some(schedule) = auto {
+                        let now = Utc::now();
+                        settings.schedule = schedule;
+                        settings.next_run = schedule.next_after(now, now);
+                    }
+                    idx
+                })
+            }
+        }
+    };
+    let port_idx = match result {
+        Ok(idx) => idx,
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+
+    let port = u.read().await.stock.portfolios[port_idx].clone();
+    let prices = fetch_prices_map(&rebalance_tickers(&port)).await;
+    let plan = rebalance_plan(&port, &prices);
+    let embed = build_plan_embed(&port, &plan);
+    let buttons = vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new("rb_go").label("Rebalance Now").style(serenity::ButtonStyle::Success).disabled(plan.orders.is_empty()),
+        serenity::CreateButton::new("rb_clear").label("Clear Targets").style(serenity::ButtonStyle::Danger).disabled(port.rebalance.targets.is_empty()),
+    ])];
+    let reply = ctx.send(poise::CreateReply::default().embed(embed.clone()).components(buttons)).await?;
+
+    let Some(press) = reply.message().await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(60))
+        .await
+    else {
+        reply.edit(ctx, poise::CreateReply::default().embed(embed).components(vec![])).await?;
+        return Ok(());
+    };
+    press.defer(ctx.http()).await?;
+
+    // The preview's quotes may be a minute old; fill at fresh ones
+    let tickers = {
+        let ud = u.read().await;
+        ud.stock.find_portfolio_idx(&port.name).map(|idx| rebalance_tickers(&ud.stock.portfolios[idx])).unwrap_or_default()
+    };
+    let prices = if press.data.custom_id == "rb_go" { fetch_prices_map(&tickers).await } else { prices };
+    let done = {
+        let mut ud = u.write().await;
+        // Re-plan under the lock in case the portfolio changed while the preview was up
+        ud.stock.find_portfolio_idx(&port.name).map(|idx| {
+            if press.data.custom_id == "rb_clear" {
+                ud.stock.portfolios[idx].rebalance = data::RebalanceSettings::default();
+                return Vec::new();
+            }
+            let plan = rebalance_plan(&ud.stock.portfolios[idx], &prices);
+            if plan.orders.is_empty() {
+                return plan.unfillable.iter().map(|note| format!("⚠️ {note}")).collect();
+            }
+            execute_rebalance(&mut ud.stock, idx, &plan)
+        })
+    };
+    let desc = match done {
+        None => format!("Portfolio **{}** no longer exists.", port.name),
+        Some(_) if press.data.custom_id == "rb_clear" => format!("Targets and auto-rebalance cleared for **{}**.", port.name),
+        Some(fills) if fills.is_empty() => "Nothing left to trade — the portfolio is already within its bands.".to_string(),
+        Some(fills) => fills.join("\n"),
+    };
+    reply.edit(ctx, poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title(format!("Rebalance — {}", port.name))
+            .description(desc)
+            .color(data::EMBED_SUCCESS)
+            .footer(default_footer()),
+    ).components(vec![])).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Position, RebalanceSettings};
+
+    fn stock(ticker: &str, asset_type: AssetType, quantity: f64) -> Position {
+        Position { ticker: ticker.to_string(), asset_type, quantity, avg_cost: 100.0, lots: Vec::new() }
+    }
+
+    fn ticker_key(symbol: &str) -> TargetKey {
+        TargetKey::Ticker { symbol: symbol.to_string(), asset_name: symbol.to_string(), asset_type: AssetType::Stock }
+    }
+
+    /// $4,000 AAPL, $4,000 VTI, $1,000 VXUS and $1,000 cash
+    fn port_with(targets: &[(TargetKey, f64)]) -> (Portfolio, HashMap<String, f64>) {
+        let mut port = Portfolio::new("test".to_string());
+        port.cash = 100_000.0;
+        port.positions = vec![
+            stock("AAPL", AssetType::Stock, 40.0),
+            stock("VTI", AssetType::ETF, 20.0),
+            stock("VXUS", AssetType::ETF, 20.0),
+        ];
+        port.rebalance = RebalanceSettings::default();
+        for (key, w) in targets {
+            port.rebalance.set_target(key.clone(), *w).unwrap();
+        }
+        let prices = [("AAPL", 100.0), ("VTI", 200.0), ("VXUS", 50.0), ("MSFT", 400.0)]
+            .into_iter().map(|(t, p)| (t.to_string(), p)).collect();
+        (port, prices)
+    }
+
+    #[test]
+    fn plan_trades_only_targets_outside_the_band() {
+        // AAPL is 40% vs 42% (inside ±5), MSFT is 0% vs 20% (outside)
+        let (port, prices) = port_with(&[(ticker_key("AAPL"), 42.0), (ticker_key("MSFT"), 20.0)]);
+        let plan = rebalance_plan(&port, &prices);
+        assert_eq!(plan.drifts.len(), 2);
+        assert_eq!(plan.orders.len(), 1);
+        let buy = &plan.orders[0];
+        assert_eq!((buy.ticker.as_str(), buy.side.clone()), ("MSFT", OrderSide::Buy));
+        assert!((buy.quantity - 5.0).abs() < 1e-9); // $2,000 of a $400 stock
+    }
+
+    #[test]
+    fn class_targets_scale_holdings_without_their_own_target() {
+        // ETFs are 50% and should be 30%: VTI and VXUS both shrink by 40%, sells before buys
+        let (port, prices) = port_with(&[(TargetKey::Class(AssetClass::Etfs), 30.0), (ticker_key("AAPL"), 60.0)]);
+        let plan = rebalance_plan(&port, &prices);
+        let sides: Vec<_> = plan.orders.iter().map(|o| (o.ticker.as_str(), o.side.clone())).collect();
+        assert_eq!(sides, [("VTI", OrderSide::Sell), ("VXUS", OrderSide::Sell), ("AAPL", OrderSide::Buy)]);
+        assert!((plan.orders[0].quantity - 8.0).abs() < 1e-9);
+        assert!((plan.orders[1].quantity - 8.0).abs() < 1e-9);
+
+        // A class with nothing held can't be scaled into
+        let (port, prices) = port_with(&[(TargetKey::Class(AssetClass::Crypto), 10.0)]);
+        let plan = rebalance_plan(&port, &prices);
+        assert!(plan.orders.is_empty());
+        assert_eq!(plan.unfillable.len(), 1);
+    }
+
+    #[test]
+    fn execution_trims_buys_to_free_cash() {
+        // MSFT wants $5,000 but only $1,000 of cash is free
+        let (port, prices) = port_with(&[(ticker_key("MSFT"), 50.0)]);
+        let plan = rebalance_plan(&port, &prices);
+        let mut profile = StockProfile::default();
+        profile.portfolios.push(port);
+        let fills = execute_rebalance(&mut profile, 0, &plan);
+        assert_eq!(fills.len(), 1);
+        let port = &profile.portfolios[0];
+        assert!(port.cash >= 0.0 && port.cash < price_to_creds(5.0));
+        assert!(port.positions.iter().any(|p| p.ticker == "MSFT" && p.quantity > 2.0 && p.quantity < 2.5));
+    }
+
+    #[test]
+    fn unpriced_holding_blocks_the_plan() {
+        // A failed fetch comes back as 0.0; without VTI the others would look overweight
+        let (port, mut prices) = port_with(&[(ticker_key("MSFT"), 20.0)]);
+        prices.insert("VTI".to_string(), 0.0);
+        let plan = rebalance_plan(&port, &prices);
+        assert!(plan.orders.is_empty() && plan.unpriced);
+        assert_eq!(plan.unfillable.len(), 1);
+        assert!(plan.unfillable[0].contains("VTI"));
+
+        prices.remove("VTI");
+        assert!(rebalance_plan(&port, &prices).orders.is_empty());
+
+        // Each ticker is named once, however its holdings are spread
+        let (mut port, mut prices) = port_with(&[]);
+        port.positions.push(stock("VTI", AssetType::ETF, 5.0));
+        prices.insert("VTI".to_string(), 0.0);
+        prices.insert("AAPL".to_string(), 0.0);
+        let plan = rebalance_plan(&port, &prices);
+        assert!(plan.unfillable[0].contains("**AAPL, VTI**"));
+    }
+}
@@ -0,0 +1,313 @@
+//! Risk tab of the portfolio view — allocation by asset type and sector, concentration,
+//! and volatility / value-at-risk estimated from a year of daily closes.
+
+use crate::api::{fetch_daily_closes, fetch_fmp_profile, fetch_prices_map, fetch_volatility};
+use crate::data::{self, AssetType, OptionSide, Portfolio, Position, UserData};
+use crate::helper::{creds_to_price, default_footer, price_to_creds};
+use crate::options::{option_premium_creds, position_greeks, PricingInputs, TRADING_DAYS_PER_YEAR};
+use crate::{serenity, Context, Error};
+use poise::serenity_prelude::futures;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Confidence level of the one-day historical value-at-risk.
+const VAR_CONFIDENCE: f64 = 0.95;
+/// Yahoo range of daily closes the volatility and value-at-risk are drawn from.
+const RISK_HISTORY_RANGE: &str = "1y";
+/// Groups shown per allocation line before the rest are folded into "Other".
+const MAX_ALLOCATION_GROUPS: usize = 6;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Concentration Limit"]
+pub(crate) struct ConcentrationModal {
+    #[name = "Warn when one ticker exceeds this % (1-100)"]
+    #[placeholder = "e.g. 25"]
+    pub limit_pct: String,
+}
+
+/// Set a portfolio's concentration warning threshold. Returns the applied percent or error.
+fn try_set_concentration_limit(user_data: &mut UserData, port_name: &str, raw_pct: &str) -> Result<f64, String> {
+    let Some(pct) = raw_pct.trim().trim_end_matches('%').parse::<f64>().ok().filter(|p| (1.0..=100.0).contains(p)) else {
+        return Err("Concentration limit must be between 1% and 100%.".to_string());
+    };
+    match user_data.stock.portfolios.iter_mut().find(|p| p.name == port_name) {
+        None => Err(format!("Portfolio **{port_name}** no longer exists.")),
+        Some(p) => {
+            p.concentration_limit_pct = pct;
+            Ok(pct)
+        }
+    }
+}
+
+/// Allocation label for a position's asset type.
+const fn asset_class(pos: &Position) -> &'static str {
+    match pos.asset_type {
+        AssetType::Option(_) => "Options",
+        AssetType::ETF => "ETFs",
+        AssetType::Crypto => "Crypto",
+        AssetType::Stock if pos.quantity < 0.0 => "Short stock",
+        AssetType::Stock => "Stocks",
+    }
+}
+
+/// Sums the absolute value of each `(group, value)` pair and returns each group's share of `gross`, largest first.
+fn group_weights<I: IntoIterator<Item = (String, f64)>>(items: I, gross: f64) -> Vec<(String, f64)> {
+    let mut groups: Vec<(String, f64)> = Vec::new();
+    if gross <= 0.0 {
+        return groups;
+    }
+    for (key, value) in items {
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, total)) => *total += value.abs() / gross,
+            None => groups.push((key, value.abs() / gross)),
+        }
+    }
+    groups.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+/// Daily dollar P&L of holding each `(exposure_usd, closes)` pair over the closes all series
+/// share, counted back from the latest. Series with fewer than two closes are ignored.
+fn daily_pnl(exposures: &[(f64, Vec<f64>)]) -> Vec<f64> {
+    let usable: Vec<&(f64, Vec<f64>)> = exposures.iter().filter(|(_, c)| c.len() >= 2).collect();
+    let Some(len) = usable.iter().map(|(_, c)| c.len()).min() else { return Vec::new() };
+    (1..len).map(|t| {
+        usable.iter()
+            .map(|(exposure, closes)| {
+                let (prev, cur) = (closes[closes.len() - len + t - 1], closes[closes.len() - len + t]);
+                if prev > 0.0 { exposure * (cur / prev - 1.0) } else { 0.0 }
+            })
+            .sum()
+    }).collect()
+}
+
+/// Annualised volatility of `pnl` relative to `equity_usd`, or `None` with fewer than two days.
+fn annualized_volatility(pnl: &[f64], equity_usd: f64) -> Option<f64> {
+    if pnl.len() < 2 || equity_usd <= 0.0 {
+        return None;
+    }
+    let n = pnl.len() as f64;
+    let mean = pnl.iter().sum::<f64>() / n;
+    let variance = pnl.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Some(variance.sqrt() / equity_usd * TRADING_DAYS_PER_YEAR.sqrt())
+}
+
+/// One-day loss in dollars not exceeded on `confidence` of the days in `pnl`, never negative.
+fn historical_var(pnl: &[f64], confidence: f64) -> Option<f64> {
+    if pnl.is_empty() {
+        return None;
+    }
+    let mut sorted = pnl.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let idx = (((1.0 - confidence) * sorted.len() as f64).floor() as usize).min(sorted.len() - 1);
+    Some((-sorted[idx]).max(0.0))
+}
+
+/// "Group 12.3% | Group 4.5%", folding anything past `MAX_ALLOCATION_GROUPS` into "Other".
+fn fmt_weights(weights: &[(String, f64)]) -> String {
+    let mut parts: Vec<String> = weights.iter().take(MAX_ALLOCATION_GROUPS)
+        .map(|(k, w)| format!("{k} **{:.1}%**", w * 100.0))
+        .collect();
+    let rest: f64 = weights.iter().skip(MAX_ALLOCATION_GROUPS).map(|(_, w)| w).sum();
+    if rest > 0.0 {
+        parts.push(format!("Other **{:.1}%**", rest * 100.0));
+    }
+    parts.join(" | ")
+}
+
+pub(crate) async fn build_risk_embed(portfolio: &Portfolio, fed_rate: f64) -> serenity::CreateEmbed {
+    let title = format!("Portfolio Risk — {}", portfolio.name);
+    if portfolio.positions.is_empty() {
+        return serenity::CreateEmbed::new()
+            .title(title)
+            .description("*No open positions* — the portfolio is all cash.")
+            .color(data::EMBED_ERROR)
+            .footer(default_footer());
+    }
+
+    let mut tickers: Vec<String> = Vec::new();
+    for pos in &portfolio.positions {
+        if !tickers.contains(&pos.ticker) {
+            tickers.push(pos.ticker.clone());
+        }
+    }
+    let prices = fetch_prices_map(&tickers).await;
+    let (profiles, closes) = futures::future::join(
+        futures::future::join_all(tickers.iter().map(|t| fetch_fmp_profile(t))),
+        futures::future::join_all(tickers.iter().map(|t| fetch_daily_closes(t, RISK_HISTORY_RANGE))),
+    ).await;
+    let mut option_inputs = HashMap::new();
+    for pos in &portfolio.positions {
+        if matches!(pos.asset_type, AssetType::Option(_)) && !option_inputs.contains_key(&pos.ticker) {
+            option_inputs.insert(pos.ticker.clone(), PricingInputs::new(fetch_volatility(&pos.ticker).await, fed_rate));
+        }
+    }
+
+    // Signed market value (creds) per position, and delta-equivalent dollar exposure per ticker
+    let now = chrono::Utc::now();
+    let mut values: Vec<(&Position, f64)> = Vec::new();
+    let mut exposure_usd: HashMap<&str, f64> = HashMap::new();
+    for pos in &portfolio.positions {
+        let spot = prices.get(&pos.ticker).copied().unwrap_or(0.0);
+        let inputs = option_inputs.get(&pos.ticker).copied().unwrap_or_else(|| PricingInputs::new(None, fed_rate));
+        let value = match &pos.asset_type {
+            AssetType::Option(c) => {
+                let premium = option_premium_creds(c.option_type, c.style, spot, c.strike, &c.expiry, c.contracts, inputs);
+                if c.side == OptionSide::Short { -premium } else { premium }
+            }
+            _ => price_to_creds(spot) * pos.quantity,
+        };
+        values.push((pos, value));
+        *exposure_usd.entry(&pos.ticker).or_default() += position_greeks(pos, spot, inputs, now).delta * spot;
+    }
+    let cash = portfolio.cash.max(0.0);
+    let gross: f64 = values.iter().map(|(_, v)| v.abs()).sum::<f64>() + cash;
+    let equity = portfolio.cash + values.iter().map(|(_, v)| v).sum::<f64>();
+
+    let sector_of = |ticker: &str, pos: &Position| -> String {
+        if matches!(pos.asset_type, AssetType::Crypto) {
+            return "Crypto".to_string();
+        }
+        tickers.iter().position(|t| t == ticker)
+            .and_then(|i| profiles[i].as_ref())
+            .and_then(|p| p.sector.clone())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "Unclassified".to_string())
+    };
+    let cash_entry = (cash > 0.0).then(|| ("Cash".to_string(), cash));
+    let by_class = group_weights(values.iter().map(|(p, v)| (asset_class(p).to_string(), *v)).chain(cash_entry.clone()), gross);
+    let by_sector = group_weights(values.iter().map(|(p, v)| (sector_of(&p.ticker, p), *v)).chain(cash_entry), gross);
+    let by_ticker = group_weights(values.iter().map(|(p, v)| (p.ticker.clone(), *v)), gross);
+
+    let mut desc = format!(
+        "**Gross exposure:** ${:.2} | **Equity:** ${:.2}\n\n**By asset type:** {}\n**By sector:** {}\n",
+        creds_to_price(gross), creds_to_price(equity), fmt_weights(&by_class), fmt_weights(&by_sector),
+    );
+    if let Some((top, weight)) = by_ticker.first() {
+        let top3: f64 = by_ticker.iter().take(3).map(|(_, w)| w).sum();
+        desc += &format!(
+            "**Concentration:** largest **{top}** {:.1}% | top 3 **{:.1}%** | {} ticker{}\n",
+            weight * 100.0, top3 * 100.0, by_ticker.len(), if by_ticker.len() == 1 { "" } else { "s" },
+        );
+    }
+
+    let series: Vec<(f64, Vec<f64>)> = tickers.iter().zip(closes)
+        .filter_map(|(t, c)| Some((exposure_usd.get(t.as_str()).copied().unwrap_or(0.0), c?)))
+        .collect();
+    let pnl = daily_pnl(&series);
+    let equity_usd = creds_to_price(equity);
+    match (annualized_volatility(&pnl, equity_usd), historical_var(&pnl, VAR_CONFIDENCE)) {
+        (Some(vol), Some(var)) => desc += &format!(
+            "**Volatility:** {:.1}%/yr | **1-day VaR ({:.0}%):** ${:.2} ({:.1}% of equity)\n*From {} days of closes, options at their current delta.*\n",
+            vol * 100.0, VAR_CONFIDENCE * 100.0, var, var / equity_usd * 100.0, pnl.len(),
+        ),
+        _ => desc += "**Volatility:** — *not enough price history to estimate.*\n",
+    }
+    if series.len() < tickers.len() {
+        desc += &format!("*{} ticker(s) had no price history and are left out of volatility.*\n", tickers.len() - series.len());
+    }
+
+    let limit = portfolio.concentration_limit_pct;
+    let breaches: Vec<String> = by_ticker.iter()
+        .filter(|(_, w)| w * 100.0 > limit)
+        .map(|(t, w)| format!("⚠️ **{t}** is **{:.1}%** of the portfolio — above your {limit:.0}% limit.", w * 100.0))
+        .collect();
+    desc += &format!("\n**Concentration limit:** {limit:.0}% per ticker\n");
+    if !breaches.is_empty() {
+        desc += &breaches.join("\n");
+    }
+
+    serenity::CreateEmbed::new()
+        .title(title)
+        .description(desc)
+        .color(if breaches.is_empty() { data::EMBED_CYAN } else { data::EMBED_FAIL })
+        .footer(default_footer())
+}
+
+/// Shows the risk tab on `reply` until the user goes back (`true`) or it times out (`false`).
+pub(crate) async fn run_risk_tab(
+    ctx: Context<'_>,
+    reply: &poise::ReplyHandle<'_>,
+    user: &RwLock<UserData>,
+    port_name: &str,
+    fed_rate: f64,
+) -> Result<bool, Error> {
+    loop {
+        let Some(port) = user.read().await.stock.portfolios.iter().find(|p| p.name == port_name).cloned() else {
+            return Ok(true);
+        };
+        let embed = build_risk_embed(&port, fed_rate).await;
+        let buttons = vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new("risk_back").label("↩ Back").style(serenity::ButtonStyle::Secondary),
+            serenity::CreateButton::new("risk_limit").label("Set Limit").style(serenity::ButtonStyle::Primary),
+        ])];
+        reply.edit(ctx, poise::CreateReply::default().embed(embed.clone()).components(buttons)).await?;
+
+        let Some(press) = reply.message().await?
+            .await_component_interaction(ctx.serenity_context())
+            .author_id(ctx.author().id)
+            .timeout(Duration::from_secs(45))
+            .await
+        else {
+            reply.edit(ctx, poise::CreateReply::default().embed(embed).components(vec![])).await?;
+            return Ok(false);
+        };
+
+        if press.data.custom_id != "risk_limit" {
+            press.defer(ctx.http()).await?;
+            return Ok(true);
+        }
+        let Some(modal) = poise::execute_modal_on_component_interaction::<ConcentrationModal>(
+            ctx, press,
+            Some(ConcentrationModal { limit_pct: format!("{:.0}", port.concentration_limit_pct) }),
+            Some(Duration::from_secs(30)),
+        ).await? else { continue };
+        let result = { let mut ud = user.write().await; try_set_concentration_limit(&mut ud, port_name, &modal.limit_pct) };
+        if let Err(msg) = result {
+            reply.edit(ctx, poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new().title("Portfolio Risk — Limit").description(msg).color(data::EMBED_ERROR),
+            ).components(vec![])).await?;
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_group_by_absolute_value() {
+        let items = [("AAPL", 600.0), ("MSFT", 200.0), ("AAPL", -200.0), ("Cash", 1_000.0)]
+            .map(|(k, v)| (k.to_string(), v));
+        let w = group_weights(items, 2_000.0);
+        assert_eq!(w.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(), ["Cash", "AAPL", "MSFT"]);
+        assert!((w[1].1 - 0.4).abs() < 1e-12);
+        assert!((w.iter().map(|(_, x)| x).sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!(group_weights([("X".to_string(), 1.0)], 0.0).is_empty());
+    }
+
+    #[test]
+    fn pnl_aligns_series_from_the_latest_close() {
+        // Longer history is trimmed to the shorter one; the short position gains when price falls
+        let series = [(1_000.0, vec![50.0, 100.0, 110.0, 99.0]), (-500.0, vec![20.0, 18.0, 18.0])];
+        let pnl = daily_pnl(&series);
+        assert_eq!(pnl.len(), 2);
+        assert!((pnl[0] - (100.0 + 50.0)).abs() < 1e-9);
+        assert!((pnl[1] + 100.0).abs() < 1e-9);
+        assert!(daily_pnl(&[(1_000.0, vec![10.0])]).is_empty());
+    }
+
+    #[test]
+    fn volatility_and_var() {
+        let pnl = [10.0, -10.0, 10.0, -10.0, 10.0, -10.0, 10.0, -10.0, 10.0, -30.0];
+        let vol = annualized_volatility(&pnl, 1_000.0).unwrap();
+        assert!(vol > 0.0);
+        assert!(annualized_volatility(&pnl[..1], 1_000.0).is_none());
+        // Worst day of ten is the 5th-percentile loss
+        assert!((historical_var(&pnl, 0.95).unwrap() - 30.0).abs() < 1e-12);
+        assert!(historical_var(&[5.0, 8.0], 0.95).unwrap().abs() < 1e-12);
+        assert!(historical_var(&[], 0.95).is_none());
+    }
+}
@@ -0,0 +1,287 @@
+//! Portfolio sharing — per-portfolio privacy, /portfolio_view of other members' public
+//! portfolios, and a rendered performance card to post in chat.
+
+use crate::api::fetch_prices_map;
+use crate::data::{self, Portfolio, PortfolioPrivacy, ValueSnapshot};
+use crate::helper::{creds_to_price, default_footer, price_to_creds, Canvas};
+use crate::{serenity, Context, Error};
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::performance::{benchmark_index, benchmark_stats, growth_index, max_drawdown, period_returns};
+use super::portfolio_equity;
+
+/// Attachment name the share card embed points at (`attachment://share_card.png`).
+const SHARE_CARD_FILENAME: &str = "share_card.png";
+const WIDTH: usize = 800;
+const HEIGHT: usize = 400;
+const PAD: usize = 24;
+/// Height of the accent band across the top of the card.
+const BAND: usize = 10;
+
+const BACKGROUND: [u8; 3] = [0x2b, 0x2d, 0x31];
+const GRID: [u8; 3] = [0x3f, 0x42, 0x48];
+const AXIS: [u8; 3] = [0x9a, 0x9c, 0xa0];
+const BENCHMARK: [u8; 3] = [0x6d, 0x6f, 0x78];
+const PROFIT: [u8; 3] = [0x57, 0xf2, 0x87];
+const LOSS: [u8; 3] = [0xed, 0x42, 0x45];
+
+/// Renders growth since the first snapshot against the benchmark's as PNG bytes: an accent band
+/// colored by the overall result, the portfolio's curve shaded against its starting value, and
+/// the benchmark in grey. Like the payoff chart, the image carries no text. `None` with fewer
+/// than two snapshots.
+pub(crate) fn render_share_card_png(snaps: &[ValueSnapshot]) -> Option<Vec<u8>> {
+    if snaps.len() < 2 {
+        return None;
+    }
+    let growth = growth_index(snaps);
+    let bench = benchmark_index(snaps);
+    let top = PAD + BAND;
+    let plot_w = WIDTH - 2 * PAD;
+    let plot_h = HEIGHT - top - PAD;
+
+    let mut lo = growth.iter().chain(bench.iter().flatten()).copied().fold(1.0, f64::min);
+    let mut hi = growth.iter().chain(bench.iter().flatten()).copied().fold(1.0, f64::max);
+    let span = (hi - lo).max(0.01);
+    lo -= span * 0.1;
+    hi += span * 0.1;
+    let y_at = |v: f64| top + (((hi - v) / (hi - lo)) * (plot_h - 1) as f64).round() as usize;
+    // Value under pixel column `x`, interpolated between snapshots
+    let at = |points: &[f64], x: usize| {
+        let t = (x - PAD) as f64 / (plot_w - 1) as f64 * (points.len() - 1) as f64;
+        let i = (t.floor() as usize).min(points.len() - 2);
+        (points[i + 1] - points[i]).mul_add(t - i as f64, points[i])
+    };
+    let side = |v: f64| if v >= 1.0 { PROFIT } else { LOSS };
+
+    let mut canvas = Canvas::new(WIDTH, HEIGHT, BACKGROUND);
+    canvas.fill(0, 0, WIDTH, BAND, side(growth[growth.len() - 1]));
+    for i in 0..=4 {
+        canvas.hline(PAD, PAD + plot_w, top + i * (plot_h - 1) / 4, GRID);
+    }
+    let base_y = y_at(1.0);
+    for x in PAD..PAD + plot_w {
+        let v = at(&growth, x);
+        canvas.vline_blend(x, base_y, y_at(v), side(v), 0.18);
+    }
+    canvas.dashed_hline(PAD, PAD + plot_w, base_y, AXIS);
+
+    let curves = bench.iter().map(|b| (b.as_slice(), Some(BENCHMARK))).chain(std::iter::once((growth.as_slice(), None)));
+    for (points, color) in curves {
+        let mut prev_y = y_at(points[0]);
+        for x in PAD..PAD + plot_w {
+            let v = at(points, x);
+            let y = y_at(v);
+            canvas.vline(x, prev_y.min(y).saturating_sub(1), prev_y.max(y) + 1, color.unwrap_or_else(|| side(v)));
+            prev_y = y;
+        }
+    }
+    canvas.encode()
+}
+
+/// Time-weighted returns, drawdown and the benchmark comparison, one per line.
+fn performance_lines(port: &Portfolio) -> String {
+    let mut desc = String::new();
+    if port.snapshots.len() >= 2 {
+        let returns: Vec<String> = period_returns(&port.snapshots).into_iter()
+            .map(|(label, r)| format!("{label} **{}**", r.map_or_else(|| "—".to_string(), |r| format!("{:+.2}%", r * 100.0))))
+            .collect();
+        desc += &format!(
+            "**Returns** (time-weighted): {}\n**Max drawdown:** {:.2}%\n",
+            returns.join(" · "),
+            max_drawdown(&port.snapshots) * 100.0,
+        );
+    } else {
+        desc += "*Returns appear once the portfolio has two days of history.*\n";
+    }
+    if let Some(vs) = benchmark_stats(&port.snapshots, port.created_at.date_naive()) {
+        desc += &format!(
+            "**vs {}** (since <t:{}:d>): **{:+.2}%** vs **{:+.2}%** → **{:+.2}%**\n",
+            data::BENCHMARK_TICKER, port.created_at.timestamp(),
+            vs.portfolio * 100.0, vs.benchmark * 100.0, vs.relative() * 100.0,
+        );
+    }
+    desc
+}
+
+fn build_public_embed(owner: serenity::UserId, port: &Portfolio, prices: &HashMap<String, f64>, full: bool) -> serenity::CreateEmbed {
+    let equity = portfolio_equity(port, prices);
+    let mut desc = format!("**Owner:** <@{owner}>\n**Value:** ${:.2}\n", creds_to_price(equity));
+    desc += &performance_lines(port);
+
+    if full {
+        desc += "\n**Holdings:**\n﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋﹋\n";
+        // Unpriced holdings show "—" rather than a 0% weight and return
+        let mut holdings: Vec<(&str, Option<f64>, Option<f64>)> = port.positions.iter().filter(|p| p.is_long_stock()).map(|p| {
+            let price = prices.get(&p.ticker).copied().filter(|usd| *usd > 0.0).map(price_to_creds);
+            let ret = price.filter(|_| p.avg_cost > 0.0).map(|price| price / p.avg_cost - 1.0);
+            (p.ticker.as_str(), price.map(|price| price * p.quantity), ret)
+        }).collect();
+        holdings.sort_by(|a, b| b.1.unwrap_or(f64::NEG_INFINITY).total_cmp(&a.1.unwrap_or(f64::NEG_INFINITY)));
+        let dash = || "—".to_string();
+        for (ticker, value, ret) in &holdings {
+            let weight = value.filter(|_| equity > 0.0).map_or_else(dash, |v| format!("{:.1}%", v / equity * 100.0));
+            let ret = ret.map_or_else(dash, |r| format!("{:+.2}%", r * 100.0));
+            desc += &format!("**{ticker}** — {weight} of portfolio | {ret}\n");
+        }
+        let others = port.positions.len() - holdings.len();
+        if others > 0 {
+            desc += &format!("*…and {others} option or short positions*\n");
+        }
+        if port.positions.is_empty() {
+            desc += "*All cash.*\n";
+        }
+        desc += &format!("**Cash:** {:.1}%\n", if equity > 0.0 { port.cash / equity * 100.0 } else { 100.0 });
+    }
+    serenity::CreateEmbed::new()
+        .title(format!("Portfolio — {}", port.name))
+        .description(desc)
+        .color(data::EMBED_CYAN)
+        .footer(default_footer())
+}
+
+/// Choose who can see one of your portfolios with /portfolio_view
+#[poise::command(slash_command)]
+pub async fn portfolio_privacy(
+    ctx: Context<'_>,
+    #[description = "Your portfolio"] portfolio: String,
+    #[description = "Private, Summary (value and returns) or Full (plus holdings)"] level: PortfolioPrivacy,
+) -> Result<(), Error> {
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let updated = {
+        let mut ud = u.write().await;
+        ud.stock.portfolios.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&portfolio)).map(|p| {
+            p.privacy = level;
+            p.name.clone()
+        })
+    };
+    let (desc, color) = match updated {
+        None => (format!("No portfolio named **{portfolio}** found."), data::EMBED_ERROR),
+        Some(name) => (match level {
+            PortfolioPrivacy::Private => format!("**{name}** is now private — only you can see it."),
+            PortfolioPrivacy::Summary => format!("Members can now see **{name}**'s value and returns with `/portfolio_view`."),
+            PortfolioPrivacy::Full => format!("Members can now see **{name}**'s value, returns and holdings with `/portfolio_view`."),
+        }, data::EMBED_SUCCESS),
+    };
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Portfolio — Privacy").description(desc).color(color).footer(default_footer()),
+    )).await?;
+    Ok(())
+}
+
+/// View another member's public portfolios
+#[poise::command(slash_command)]
+pub async fn portfolio_view(
+    ctx: Context<'_>,
+    #[description = "Member whose portfolios to view"] user: serenity::User,
+    #[description = "Portfolio name (default: list their public portfolios)"] portfolio: Option<String>,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Portfolio View").description(desc).color(data::EMBED_ERROR),
+    );
+    let Some(u) = ctx.data().users.get(&user.id).map(|u| Arc::clone(u.value())) else {
+        ctx.send(err_embed(format!("<@{}> hasn't started using ProfessorBot yet.", user.id))).await?;
+        return Ok(());
+    };
+    ctx.defer().await?;
+    // Owners always see everything of their own
+    let privacy_of = |p: &Portfolio| if user.id == ctx.author().id { PortfolioPrivacy::Full } else { p.privacy };
+
+    let Some(name) = portfolio else {
+        let rows: Vec<String> = u.read().await.stock.portfolios.iter()
+            .filter(|p| privacy_of(p) != PortfolioPrivacy::Private)
+            .map(|p| {
+                let all = period_returns(&p.snapshots).into_iter().find(|(label, _)| *label == "All").and_then(|(_, r)| r);
+                format!(
+                    "**{}** — {} | All-time **{}**",
+                    p.name, privacy_of(p).label(), all.map_or_else(|| "—".to_string(), |r| format!("{:+.2}%", r * 100.0)),
+                )
+            })
+            .collect();
+        if rows.is_empty() {
+            ctx.send(err_embed(format!("<@{}> has no public portfolios.", user.id))).await?;
+            return Ok(());
+        }
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title(format!("Portfolios — {}", user.name))
+                .description(format!("{}\n\nRun `/portfolio_view` with a **portfolio** name for details.", rows.join("\n")))
+                .thumbnail(user.avatar_url().unwrap_or_default())
+                .color(data::EMBED_CYAN)
+                .footer(default_footer()),
+        )).await?;
+        return Ok(());
+    };
+
+    let port = u.read().await.stock.portfolios.iter()
+        .find(|p| p.name.eq_ignore_ascii_case(&name) && privacy_of(p) != PortfolioPrivacy::Private)
+        .cloned();
+    // Private portfolios read as missing so their names don't leak
+    let Some(port) = port else {
+        ctx.send(err_embed(format!("<@{}> has no public portfolio named **{name}**.", user.id))).await?;
+        return Ok(());
+    };
+    let tickers: Vec<String> = port.positions.iter().map(|p| p.ticker.clone()).collect();
+    let prices = fetch_prices_map(&tickers).await;
+    let embed = build_public_embed(user.id, &port, &prices, privacy_of(&port) == PortfolioPrivacy::Full)
+        .thumbnail(user.avatar_url().unwrap_or_default());
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Post a performance card for one of your portfolios
+#[poise::command(slash_command)]
+pub async fn share_card(
+    ctx: Context<'_>,
+    #[description = "Your portfolio"] portfolio: String,
+) -> Result<(), Error> {
+    let u = Arc::clone(ctx.data().users.get(&ctx.author().id).unwrap().value());
+    let port = u.read().await.stock.portfolios.iter().find(|p| p.name.eq_ignore_ascii_case(&portfolio)).cloned();
+    let card = port.as_ref().and_then(|p| render_share_card_png(&p.snapshots));
+    let (Some(port), Some(png)) = (port, card) else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title("Share Card")
+                .description(format!("No portfolio named **{portfolio}** with at least two days of history. Portfolios are snapshotted daily."))
+                .color(data::EMBED_ERROR),
+        )).await?;
+        return Ok(());
+    };
+
+    let legend = if port.snapshots.iter().any(|s| s.benchmark > 0.0) {
+        format!("\n*Colored line: {} · Grey line: {} · Dashed: starting value*", port.name, data::BENCHMARK_TICKER)
+    } else {
+        String::new()
+    };
+    ctx.send(poise::CreateReply::default()
+        .embed(serenity::CreateEmbed::new()
+            .title(format!("{} — by {}", port.name, ctx.author().name))
+            .description(format!("{}{legend}", performance_lines(&port)))
+            .thumbnail(ctx.author().avatar_url().unwrap_or_default())
+            .image(format!("attachment://{SHARE_CARD_FILENAME}"))
+            .color(data::EMBED_CYAN)
+            .footer(default_footer()))
+        .attachment(serenity::CreateAttachment::bytes(png, SHARE_CARD_FILENAME))
+    ).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn renders_png_only_with_history() {
+        let snap = |day: u32, total: f64, benchmark: f64| ValueSnapshot {
+            date: NaiveDate::from_ymd_opt(2026, 3, day).unwrap(),
+            total,
+            cash: total,
+            positions: 0.0,
+            net_flow: 0.0,
+            benchmark,
+        };
+        assert!(render_share_card_png(&[snap(1, 1_000.0, 500.0)]).is_none());
+        let png = render_share_card_png(&[snap(1, 1_000.0, 500.0), snap(2, 900.0, 0.0), snap(3, 1_200.0, 520.0)]).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}
@@ -11,11 +11,23 @@ use std::time::Duration;
 pub(crate) enum TradeFilter { Gains, Losses }
 
 pub(crate) fn build_summary_embed(trades: &VecDeque<TradeRecord>) -> serenity::CreateEmbed {
-    // (gains, losses, count, cost_basis)
-    let mut map: HashMap<&str, (f64, f64, u32, f64)> = HashMap::new();
+    // (gains, losses, count, cost_basis, fees)
+    let mut map: HashMap<&str, (f64, f64, u32, f64, f64)> = HashMap::new();
+    let (mut short_term, mut long_term, mut has_lots) = (0.0_f64, 0.0_f64, false);
     for t in trades {
-        let entry = map.entry(t.portfolio.as_str()).or_insert((0.0, 0.0, 0, 0.0));
+        // Transfers move cost basis between portfolios and interest is posted cash; neither is a trade
+        if matches!(t.action, TradeAction::TransferIn | TradeAction::TransferOut | TradeAction::Interest) {
+            continue;
+        }
+        if !t.lots.is_empty() {
+            let (st, lt) = t.pnl_by_term();
+            short_term += st;
+            long_term += lt;
+            has_lots = true;
+        }
+        let entry = map.entry(t.portfolio.as_str()).or_insert((0.0, 0.0, 0, 0.0, 0.0));
         entry.2 += 1;
+        entry.4 += t.fees.total();
         if let Some(pnl) = t.realized_pnl {
             let cost = t.total_creds - pnl;
             entry.3 += cost;
@@ -35,20 +47,35 @@ pub(crate) fn build_summary_embed(trades: &VecDeque<TradeRecord>) -> serenity::C
     }
 
     let mut desc = String::new();
-    let mut total_net = 0.0_f64;
+    let mut total_gross = 0.0_f64;
+    let mut total_fees = 0.0_f64;
     let mut total_basis = 0.0_f64;
     let mut sorted: Vec<_> = map.iter().collect();
     sorted.sort_by_key(|(k, _)| *k);
-    for (name, (gains, losses, count, basis)) in sorted {
-        let net = gains + losses;
-        total_net += net;
+    for (name, (gains, losses, count, basis, fees)) in sorted {
+        let gross = gains + losses;
+        let net = gross - fees;
+        total_gross += gross;
+        total_fees += fees;
         total_basis += basis;
         desc += &format!(
-            "**{}** — {} trades | +${:.2} gains | -${:.2} losses | Net: **${:+.2}{}**\n",
-            name, count, creds_to_price(*gains), creds_to_price(losses.abs()), creds_to_price(net), crate::helper::fmt_pct_change(net, *basis)
+            "**{}** — {} trades | +${:.2} gains | -${:.2} losses | Gross: ${:+.2} | Fees: ${:.2} | Net: **${:+.2}{}**\n",
+            name, count, creds_to_price(*gains), creds_to_price(losses.abs()), creds_to_price(gross),
+            creds_to_price(*fees), creds_to_price(net), crate::helper::fmt_pct_change(net, *basis)
+        );
+    }
+    let total_net = total_gross - total_fees;
+    desc += &format!(
+        "\nTotal Gross P&L: ${:+.2} | Total Fees: ${:.2}\n**Total Net P&L: ${:+.2}{}**",
+        creds_to_price(total_gross), creds_to_price(total_fees),
+        creds_to_price(total_net), crate::helper::fmt_pct_change(total_net, total_basis)
+    );
+    if has_lots {
+        desc += &format!(
+            "\nLot sales — Short-term: ${:+.2} | Long-term: ${:+.2}",
+            creds_to_price(short_term), creds_to_price(long_term)
         );
     }
-    desc += &format!("\n**Total Net P&L: ${:+.2}{}**", creds_to_price(total_net), crate::helper::fmt_pct_change(total_net, total_basis));
 
     serenity::CreateEmbed::new()
         .title("Trade History — Summary")
@@ -109,6 +136,11 @@ pub(crate) fn build_all_trades_embed(trades: &VecDeque<TradeRecord>) -> serenity
         let action = match t.action {
             TradeAction::Buy => "BUY ",
             TradeAction::Sell => "SELL",
+            TradeAction::Short => "SHRT",
+            TradeAction::Cover => "CVR ",
+            TradeAction::TransferIn => "XIN ",
+            TradeAction::TransferOut => "XOUT",
+            TradeAction::Interest => "INT ",
         };
         let pnl_str = t
             .realized_pnl
@@ -117,13 +149,15 @@ pub(crate) fn build_all_trades_embed(trades: &VecDeque<TradeRecord>) -> serenity
                 format!(" | P&L: **${:+.2}{}**", creds_to_price(p), crate::helper::fmt_pct_change(p, cost))
             })
             .unwrap_or_default();
+        let fee_str = if t.fees.total() > 0.0 { format!(" | fees ${:.2}", creds_to_price(t.fees.total())) } else { String::new() };
         desc += &format!(
-            "{} `{}` **{}** × {} — **${:.2}**{}\n",
+            "{} `{}` **{}** × {} — **${:.2}**{}{}\n",
             t.timestamp.format("%m/%d"),
             action,
             t.ticker,
             fmt_qty(t.quantity),
             creds_to_price(t.total_creds),
+            fee_str,
             pnl_str
         );
     }
@@ -214,7 +248,7 @@ pub async fn trades(ctx: Context<'_>) -> Result<(), Error> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::data::TradeAction;
+    use crate::data::{TradeAction, TradeFees};
     use chrono::Utc;
 
     fn make_trade(portfolio: &str, action: TradeAction, qty: f64, price: f64, pnl: Option<f64>) -> TradeRecord {
@@ -229,6 +263,9 @@ mod tests {
             total_creds: total,
             realized_pnl: pnl,
             timestamp: Utc::now(),
+            fees: TradeFees::default(),
+            lots: Vec::new(),
+            option_leg: false,
         }
     }
 
@@ -0,0 +1,95 @@
+//! /transfer command — move cash and holdings between your own portfolios without selling.
+
+use crate::api::fetch_price;
+use crate::data;
+use crate::helper::{creds_to_price, default_footer, fmt_qty, price_to_creds};
+use crate::professor::PROFESSOR_PORT;
+use crate::{serenity, Context, Error};
+use super::apply_transfer;
+
+/// Move cash and/or a holding between your portfolios, keeping cost basis and tax lots
+#[poise::command(slash_command)]
+pub async fn transfer(
+    ctx: Context<'_>,
+    #[description = "Portfolio to move from"] from: String,
+    #[description = "Portfolio to move into"] to: String,
+    #[description = "Dollars of cash to move"] cash: Option<f64>,
+    #[description = "Ticker of a stock, ETF or crypto holding to move"] ticker: Option<String>,
+    #[description = "Units of the holding to move (default: all of it)"] quantity: Option<f64>,
+) -> Result<(), Error> {
+    let err_embed = |desc: String| poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new().title("Transfer").description(desc).color(data::EMBED_ERROR),
+    );
+    if cash.is_some_and(|c| !(0.01..=data::MAX_FUND_USD).contains(&c)) {
+        ctx.send(err_embed(format!("Cash must be between $0.01 and ${:.2}.", data::MAX_FUND_USD))).await?;
+        return Ok(());
+    }
+    if quantity.is_some_and(|q| q <= 0.0) {
+        ctx.send(err_embed("Quantity must be positive.".to_string())).await?;
+        return Ok(());
+    }
+    if [&from, &to].iter().any(|p| p.eq_ignore_ascii_case(PROFESSOR_PORT)) {
+        ctx.send(err_embed(format!("**{PROFESSOR_PORT}** is managed by the professor and can't take part in transfers."))).await?;
+        return Ok(());
+    }
+    ctx.defer().await?;
+
+    let ticker = ticker.map(|t| t.trim().to_uppercase());
+    // Market value of the moved units, for the portfolios' return history; cost basis if unpriced
+    let price = match &ticker {
+        Some(t) => fetch_price(t).await.map(price_to_creds),
+        None => None,
+    };
+
+    let u = ctx.data().users.get(&ctx.author().id).unwrap().clone();
+    let result = {
+        let mut ud = u.write().await;
+        let stock = &mut ud.stock;
+        match (stock.find_portfolio_idx(&from), stock.find_portfolio_idx(&to)) {
+            (None, _) => Err(format!("No portfolio named **{from}** found.")),
+            (_, None) => Err(format!("No portfolio named **{to}** found.")),
+            (Some(from_idx), Some(to_idx)) if [from_idx, to_idx].iter().any(|&i| stock.portfolios[i].club.is_some()) => {
+                Err("Club portfolios can't take part in transfers — members move cash with `/club_deposit` and `/club_redeem`.".to_string())
+            }
+            (Some(from_idx), Some(to_idx)) => {
+                let holding = ticker.as_deref().map(|t| {
+                    let held = stock.portfolios[from_idx].positions.iter().find(|p| p.ticker == t && p.is_long_stock());
+                    let qty = quantity.or_else(|| held.map(|p| p.quantity)).unwrap_or(0.0);
+                    let unit = price.or_else(|| held.map(|p| p.avg_cost)).unwrap_or(0.0);
+                    (t, qty, unit)
+                });
+                let cash_creds = cash.map_or(0.0, price_to_creds);
+                apply_transfer(&mut stock.portfolios, &mut stock.trade_history, from_idx, to_idx, cash_creds, holding)
+                    .map(|()| {
+                        let names = (stock.portfolios[from_idx].name.clone(), stock.portfolios[to_idx].name.clone());
+                        // The newest entry is the destination's record of the holding, if one moved
+                        let basis = holding.and_then(|_| stock.trade_history.back()).map(|t| (t.quantity, t.total_creds));
+                        (names, basis)
+                    })
+            }
+        }
+    };
+
+    let ((from_name, to_name), basis) = match result {
+        Ok(done) => done,
+        Err(desc) => {
+            ctx.send(err_embed(desc)).await?;
+            return Ok(());
+        }
+    };
+    let mut moved = Vec::new();
+    if let Some(dollars) = cash {
+        moved.push(format!("**${dollars:.2}** cash"));
+    }
+    if let (Some(t), Some((qty, cost))) = (&ticker, basis) {
+        moved.push(format!("**{} {t}** (cost basis ${:.2}, lots and holding periods kept)", fmt_qty(qty), creds_to_price(cost)));
+    }
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("Transfer")
+            .description(format!("Moved {} from **{from_name}** to **{to_name}**.", moved.join(" and ")))
+            .color(data::EMBED_SUCCESS)
+            .footer(default_footer()),
+    )).await?;
+    Ok(())
+}